@@ -28,7 +28,27 @@ enum Commands {
         #[arg(short, long, value_parser)]
         search_paths: Option<Vec<PathBuf>>,
     },
-    /// Convert api to yaml
+    /// Bundle a spec and its imports into a single file
+    Bundle {
+        /// Input file path
+        #[arg(short, long, value_parser)]
+        input: PathBuf,
+
+        /// Output file path, its extension (api, yaml or json) is the format of the bundle
+        #[arg(short, long, value_parser)]
+        output: PathBuf,
+
+        /// Search paths
+        #[arg(short, long, value_parser)]
+        search_paths: Option<Vec<PathBuf>>,
+    },
+    /// Rewrite the deprecated syntax of api files to the current one
+    Migrate {
+        /// Input file or directory paths, every api file in a directory is migrated
+        #[arg(short, long, value_parser, required = true)]
+        input: Vec<PathBuf>,
+    },
+    /// Convert api to yaml, or to json with --format json
     Yaml {
         /// Input file path
         #[arg(short, long, value_parser)]
@@ -37,6 +57,10 @@ enum Commands {
         /// Output to stdout
         #[arg(short, long, default_value_t = false)]
         stdout: bool,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = SpecFormat::Yaml)]
+        format: SpecFormat,
     },
     /// Convert yaml to api
     Api {
@@ -50,7 +74,11 @@ enum Commands {
     },
 }
 
-
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum SpecFormat {
+    Yaml,
+    Json,
+}
 
 fn main() -> Result<(), Box<dyn Error>> {
     // tracing_subscriber::FmtSubscriber::builder()
@@ -59,6 +87,14 @@ fn main() -> Result<(), Box<dyn Error>> {
     // .with_span_events(FmtSpan::CLOSE)
     // .init();
 
+    // warnings of the parser, e.g. deprecated syntax
+    tracing_subscriber::fmt()
+        .with_max_level(Level::WARN)
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .without_time()
+        .init();
+
     let args = Args::parse();
     match args.command {
         Some(Commands::Gen { input, stdout, search_paths }) => {
@@ -98,7 +134,13 @@ fn main() -> Result<(), Box<dyn Error>> {
             }
             
         },
-        Some(Commands::Yaml { input, stdout }) => {
+        Some(Commands::Bundle { input, output, search_paths }) => {
+            bundle(&input, &output, search_paths.as_ref())?;
+        },
+        Some(Commands::Migrate { input }) => {
+            migrate(&input)?;
+        },
+        Some(Commands::Yaml { input, stdout, format }) => {
             match input {
                 Some(i) => {
                     let target_path = PathBuf::from(i);
@@ -106,7 +148,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                         Ok(md) => {
                             if md.is_file() {
                                 let content = std::fs::read_to_string(&target_path)?;
-                                let output = api_to_yaml(&content)?;
+                                let output = convert_api(&content, format)?;
                                 if stdout {
                                     print!("{}", output);
                                 }
@@ -121,7 +163,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                 },
                 None => {
                     let stdin_content = read_from_stdin();
-                    let output = api_to_yaml(&stdin_content)?;
+                    let output = convert_api(&stdin_content, format)?;
                     if stdout {
                         print!("{}", output);
                     }
@@ -162,6 +204,58 @@ pub fn run(entry_file: &Path, search_paths: Option<&Vec<PathBuf>>) -> Result<()>
     ctx.dump()
 }
 
+/// Write the spec of the entry file with its imports resolved, so that it is self-contained
+pub fn bundle(entry_file: &Path, output: &Path, search_paths: Option<&Vec<PathBuf>>) -> Result<()> {
+    let abs_file = std::path::absolute(entry_file)?;
+    let mut explored = HashSet::new();
+    let mut spec = cronus_parser::from_file(&abs_file, true, search_paths, &mut explored)?;
+    spec.imports = None;
+    spec.scoped_imports = None;
+
+    let content = match output.extension().and_then(|ext| ext.to_str()) {
+        Some("api") => cronus_parser::to_api_str(&spec)?,
+        Some("yaml") | Some("yml") => cronus_parser::to_yaml_str(&spec)?,
+        Some("json") => cronus_parser::to_json_str(&spec)?,
+        _ => anyhow::bail!("unsupported bundle format of '{}', expect an .api, .yaml or .json file", output.display()),
+    };
+    std::fs::write(output, content)?;
+    Ok(())
+}
+
+/// Rewrite the api files of the given paths in place, the directories are walked for their api files
+pub fn migrate(paths: &[PathBuf]) -> Result<()> {
+    let mut files = Vec::new();
+    for path in paths {
+        collect_api_files(path, &mut files)?;
+    }
+    for file in files {
+        let content = std::fs::read_to_string(&file)?;
+        let migrated = cronus_parser::api_migrate::migrate(&content)
+            .map_err(|err| anyhow::anyhow!("failed to migrate '{}': {}", file.display(), err))?;
+        if migrated != content {
+            std::fs::write(&file, migrated)?;
+            println!("migrated {}", file.display());
+        }
+    }
+    Ok(())
+}
+
+fn collect_api_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    if path.is_dir() {
+        let mut entries = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<PathBuf>>>()?;
+        entries.sort();
+        for entry in entries {
+            if entry.is_dir() || entry.extension().is_some_and(|ext| ext == "api") {
+                collect_api_files(&entry, files)?;
+            }
+        }
+    } else {
+        files.push(path.to_path_buf());
+    }
+    Ok(())
+}
 
 pub fn generate_from_yaml(content: &str) -> Result<String> {
     match cronus_parser::from_yaml_str(content) {
@@ -186,6 +280,18 @@ pub fn api_to_yaml(content: &str) -> Result<String> {
     }
 }
 
+fn convert_api(content: &str, format: SpecFormat) -> Result<String> {
+    match format {
+        SpecFormat::Yaml => api_to_yaml(content),
+        SpecFormat::Json => api_to_json(content),
+    }
+}
+
+pub fn api_to_json(content: &str) -> Result<String> {
+    let spec = cronus_parser::api_parse::parse(PathBuf::new(), content)?;
+    cronus_parser::to_json_str(&spec)
+}
+
 fn run_raw_spec(spec: cronus_spec::RawSpec) -> Result<String> {
 
     let ctx = Ctxt::new(spec);
@@ -210,7 +316,7 @@ fn run_raw_spec(spec: cronus_spec::RawSpec) -> Result<String> {
 
 pub fn generate_from_api(content: &str) -> Result<String> {        
 
-    match cronus_parser::api_parse::parse(PathBuf::new(), content) {
+    match cronus_parser::from_api_str(content) {
         Ok(spec) => {
             run_raw_spec(spec)
         },
@@ -70,7 +70,7 @@ fn run_raw_spec(spec: RawSpec) -> Result<JsValue, String> {
 pub fn generate_from_api(content: &str) -> Result<JsValue, String> {        
     console_error_panic_hook::set_once();
 
-    match cronus_parser::api_parse::parse(PathBuf::new(), content) {
+    match cronus_parser::from_api_str(content) {
         Ok(spec) => {
             run_raw_spec(spec)
         },
@@ -1,23 +1,29 @@
 use std::{any::type_name, cell::RefCell, collections::HashSet, fmt::format, path::PathBuf};
 
 use convert_case::{Case, Casing};
-use cronus_spec::{RawSchema, GolangGeneratorOption};
+use cronus_spec::{RawDeprecated, RawError, RawSchema, GolangGeneratorOption};
 
 use crate::{
-    utils::{self, get_path_from_optional_parent, get_request_name, get_response_name, get_schema_by_name, get_usecase_name, spec_ty_to_golang_builtin_ty, spec_ty_to_rust_builtin_ty}, Ctxt, Generator
+    utils::{self, get_path_from_optional_parent, get_request_name, get_response_name, get_schema_by_name, get_schema_parents, get_schema_properties, get_usecase_name, spec_ty_to_golang_builtin_ty, spec_ty_to_rust_builtin_ty}, Ctxt, Generator
 };
 use tracing::{self, debug, span, Level};
-use anyhow::{Ok, Result};
+use anyhow::{bail, Ok, Result};
 
 pub struct GolangGenerator {
-    generated_tys: RefCell<HashSet<String>>
+    generated_tys: RefCell<HashSet<String>>,
+    /// generated structs with a New<Type>WithDefaults constructor
+    constructed_tys: RefCell<HashSet<String>>,
+    /// properties which are pointers to break a cycle of structs, (struct, property)
+    pointer_props: RefCell<HashSet<(String, String)>>
 }
 
 
 impl GolangGenerator {
     pub fn new() -> Self {
         Self {
-            generated_tys: RefCell::new(HashSet::new())
+            generated_tys: RefCell::new(HashSet::new()),
+            constructed_tys: RefCell::new(HashSet::new()),
+            pointer_props: RefCell::new(HashSet::new())
         }
     }
 }
@@ -28,10 +34,20 @@ impl Generator for GolangGenerator {
     }
 
     fn before_all(&self, ctx: &Ctxt) -> Result<()> {
-        
+        // slices and the optional or nullable properties, which are pointers, hold no struct by value
+        *self.pointer_props.borrow_mut() = utils::get_recursive_props(ctx, |prop| {
+            prop.items.is_some() || prop.required == Some(false) || prop.nullable.unwrap_or(false)
+        })?;
+
         let mut imports = vec![
-            "context"
+            "context".to_string()
         ];
+        // the declared errors are sentinel errors
+        if ctx.spec.errors.as_ref().is_some_and(|errors| !errors.is_empty()) {
+            imports.push("errors".to_string());
+        }
+        // imports of the scalar and declared types
+        imports.extend(utils::get_golang_imports(ctx, &utils::get_spec_tys(ctx)));
 
 
         let pkg = self.get_gen_option(ctx)
@@ -49,7 +65,14 @@ impl Generator for GolangGenerator {
     }
 
     fn generate_schema(&self, ctx: &Ctxt, schema_name:&str, schema: &RawSchema) -> Result<()> {
-        self.generate_struct(ctx, schema, Some(schema_name.to_owned()), None);
+        self.generate_struct(ctx, schema, Some(schema_name.to_owned()), None)?;
+        Ok(())
+    }
+
+    /// Generate the sentinel error of a declared error, the usecase implementations wrap it to add details
+    fn generate_error(&self, ctx: &Ctxt, error_name: &str, _error: &RawError) -> Result<()> {
+        let result = format!("var {} = errors.New(\"{}\")\n\n", utils::get_golang_error_name(error_name), error_name);
+        ctx.append_file(self.name(), &self.dst(ctx), &result);
         Ok(())
     }
 
@@ -69,27 +92,53 @@ impl Generator for GolangGenerator {
         let mut result = String::new();
 
        
+        if let Some(deprecated) = utils::get_usecase_deprecated(usecase) {
+            result += &go_deprecated_comment(deprecated, "");
+        }
         result += &format!("type {} interface {{\n", trait_name);
         for (method_name, method) in &usecase.methods {
 
+            if let Some(deprecated) = utils::get_method_deprecated(method) {
+                result += &go_deprecated_comment(deprecated, "  ");
+            }
             result += "  ";
             result += &method_name.to_case(Case::UpperCamel);
             let mut method_params: Vec<String> = vec![];
             method_params.push("ctx context.Context".to_string());
 
             if let Some(req) = &method.req {
-                let request_ty = get_request_name(ctx, method_name);
-                self.generate_struct(ctx, &req, Some(request_ty.clone()), None)?;
-                method_params.push(format!("request *{}", request_ty));
+                // a named type is used as is, by pointer if it is a struct
+                let request_ty = if !utils::is_named_ty(req) {
+                    let request_ty = get_request_name(ctx, method_name);
+                    self.generate_struct(ctx, req, Some(request_ty.clone()), None)?;
+                    format!("*{}", request_ty)
+                } else if utils::is_struct_ty(ctx, req) {
+                    format!("*{}", self.generate_struct(ctx, req, None, None)?)
+                } else {
+                    self.generate_struct(ctx, req, None, None)?
+                };
+                method_params.push(format!("request {}", request_ty));
             }
             let params_str = method_params.join(", ");
             result += &format!("({})", params_str);
 
             
             if let Some(res) = &method.res {
-                let response_ty = get_response_name(ctx, method_name);
-                self.generate_struct(ctx, &res, Some(response_ty.clone()), None)?;
-                result += format!("(*{}, error)", response_ty).as_str();
+                let response_ty = if !utils::is_named_ty(res) {
+                    let response_ty = get_response_name(ctx, method_name);
+                    self.generate_struct(ctx, res, Some(response_ty.clone()), None)?;
+                    format!("*{}", response_ty)
+                } else if utils::is_struct_ty(ctx, res) {
+                    format!("*{}", self.generate_struct(ctx, res, None, None)?)
+                } else {
+                    self.generate_struct(ctx, res, None, None)?
+                };
+                // a stream method sends its responses over a channel, closed at the end of the stream
+                if utils::is_stream_method(method) {
+                    result += format!("(<-chan {}, error)", response_ty).as_str();
+                } else {
+                    result += format!("({}, error)", response_ty).as_str();
+                }
             } else {
                 result += "error";
             }
@@ -131,7 +180,7 @@ impl GolangGenerator {
 
         // find out the correct type name
         if let Some(ty) = &override_ty {
-            type_name = ty.to_case(Case::UpperCamel);
+            type_name = utils::get_prefixed_ty_name(ty);
         }
         else if schema.items.is_some() {
 
@@ -139,6 +188,12 @@ impl GolangGenerator {
 
             return Ok(format!("[]{}", type_name).to_owned());
         }
+        else if let Some(values) = &schema.values {
+            let keys = utils::get_map_keys(schema);
+            let key_ty = spec_ty_to_golang_builtin_ty(ctx, keys).unwrap_or(utils::get_prefixed_ty_name(keys));
+            let value_ty = self.generate_struct(ctx, values, None, root_schema_ty.clone())?;
+            return Ok(format!("map[{}]{}", key_ty, value_ty));
+        }
         else {
             type_name = schema.ty.as_ref().unwrap().clone();
         }
@@ -151,16 +206,11 @@ impl GolangGenerator {
         let _enter = span.enter();
 
         // if type name belongs to built-in type, return directly
-        if let Some(ty) = spec_ty_to_golang_builtin_ty(&type_name) {
+        if let Some(ty) = spec_ty_to_golang_builtin_ty(ctx, &type_name) {
             return Ok(ty);
         }
 
         if self.generated_tys.borrow().contains(&type_name) {
-            if let Some(root_schema_ty) = root_schema_ty  {
-                if  root_schema_ty == type_name {
-                    return Ok(format!("{type_name}"))
-                }
-            }
             return Ok(type_name);
         }
 
@@ -184,7 +234,7 @@ impl GolangGenerator {
             } else {
                 "string"
             };
-            let mut enum_def = format!("type {} {}\n", type_name, enum_actual_ty);
+            let mut enum_def = format!("{}type {} {}\n", go_doc(schema), type_name, enum_actual_ty);
             for item in enum_items {
                 let enum_value = if enum_int {
                     if item.value.is_none() {
@@ -194,6 +244,9 @@ impl GolangGenerator {
                 } else {
                     format!("\"{}\"", item.name.to_uppercase())
                 };
+                if let Some(deprecated) = utils::get_enum_item_deprecated(item) {
+                    enum_def += &go_deprecated_comment(deprecated, "");
+                }
                 enum_def += &format!("const {} {} = {}\n", item.name.to_case(Case::UpperSnake), type_name, enum_value);
             }
             ctx.append_file(self.name(), &self.dst(ctx), &enum_def);
@@ -202,62 +255,96 @@ impl GolangGenerator {
 
 
 
-        let mut result = format!("type {} struct {{\n", type_name).to_string();
-
-
-        if let Some(properties) = &schema.properties {
-            for (prop_name, prop_schema) in properties {
+        let mut result = format!("{}type {} struct {{\n", go_doc(schema), type_name).to_string();
+        let mut constructor_fields: Vec<String> = Vec::new();
 
-                // let mut attrs: Vec<String> = vec![];
-                // match &prop_schema.option {
-                //     Some(option) => {
-                //         match &option.rust {
-                //             Some(rust_opt) => {
-                //                 match &rust_opt.attrs {
-                //                     Some(custom_attrs) => {
-                //                         attrs.extend(custom_attrs.iter().map(|attr| format!("#[{}]", attr).to_string()) );
-                //                     },
-                //                     None => {},
-                //                 }
-                //             },
-                //             None => {}
-                //         }
-                //     },
-                //     None => {},
-                // }
+        // parents are embedded, so their fields are promoted (also in json)
+        for (_, parent) in get_schema_parents(schema) {
+            let parent_ty = self.generate_struct(ctx, &RawSchema::new(schema.def_loc.clone(), parent), None, None)?;
+            result += &format!("  {}\n", parent_ty);
+            if self.constructed_tys.borrow().contains(&parent_ty) {
+                constructor_fields.push(format!("{}: New{}WithDefaults()", parent_ty, parent_ty));
+            }
+        }
 
-                // if !attrs.is_empty() {
-                //     result += &format!("  {}\n", attrs.join("\n"));
-                // }
+        let properties = get_schema_properties(ctx, schema)?;
+        utils::check_ident_collisions(&type_name, properties.keys(), utils::go_ident)?;
+        for (prop_name, prop_schema) in &properties {
+
+            // let mut attrs: Vec<String> = vec![];
+            // match &prop_schema.option {
+            //     Some(option) => {
+            //         match &option.rust {
+            //             Some(rust_opt) => {
+            //                 match &rust_opt.attrs {
+            //                     Some(custom_attrs) => {
+            //                         attrs.extend(custom_attrs.iter().map(|attr| format!("#[{}]", attr).to_string()) );
+            //                     },
+            //                     None => {},
+            //                 }
+            //             },
+            //             None => {}
+            //         }
+            //     },
+            //     None => {},
+            // }
+
+            // if !attrs.is_empty() {
+            //     result += &format!("  {}\n", attrs.join("\n"));
+            // }
+
+            if let Some(deprecated) = utils::get_schema_deprecated(prop_schema) {
+                result += &go_deprecated_comment(deprecated, "  ");
+            }
+            result += "  ";
+            result += &utils::go_ident(prop_name);
+            result += " ";
+
+            let default = utils::get_default_value(ctx, prop_schema)?;
+            // a property with a default value is always there
+            let optional = match prop_schema.required {
+                Some(req) => !req && default.is_none(),
+                None => false
+            };
 
-                result += "  ";
-                result += prop_name.to_case(Case::UpperCamel).as_str();
-                result += " ";
+            let prop_ty = self.generate_struct(ctx, prop_schema, None, Some(type_name.clone()))?;
+            if let Some(default) = &default {
+                constructor_fields.push(format!("{}: {}", utils::go_ident(prop_name), utils::default_value_to_golang_literal(default)));
+            }
 
-                let optional = match prop_schema.required {
-                    Some(req) => !req,
-                    None => false
-                };
+            // nil is an absent or a null property, a struct holding itself through other structs needs a pointer as well
+            let recursive = override_ty.as_ref().is_some_and(|ty| self.pointer_props.borrow().contains(&(ty.clone(), prop_name.clone())));
+            if optional || prop_schema.nullable.unwrap_or(false) || recursive {
+                result += &format!("*{}", prop_ty);
 
-                let prop_ty = self.generate_struct(ctx, &prop_schema, None, Some(type_name.clone()))?;
+            } else {
+                result += &prop_ty;
+            }
 
-                if optional {
-                    result += &format!("*{}", prop_ty);
+            let add_json_tag = true; // TODO: make it configurable
+            if add_json_tag {
+                let json_name = utils::get_wire_name(ctx, prop_name, prop_schema).unwrap_or_else(|| prop_name.to_case(Case::Camel));
+                result += &format!(" `json:\"{}\"`", json_name);
+            }
 
-                } else {
-                    result += &prop_ty;
-                }
+            result += "\n";
+        }
 
-                let add_json_tag = true; // TODO: make it configurable
-                if add_json_tag {
-                    result += &format!(" `json:\"{}\"`", prop_name.to_case(Case::Camel));
-                }
+        result += "}\n";
 
-                result += "\n";
+        // default values are set by the constructor, decoding into the constructed struct keeps them for absent fields
+        if !constructor_fields.is_empty() {
+            let constructor = format!("New{}WithDefaults", type_name);
+            if ctx.spec.ty.iter().flatten().any(|(name, _)| utils::get_prefixed_ty_name(name) == constructor) {
+                bail!("the constructor '{}' of the default values of '{}' has the name of a type", constructor, type_name)
             }
+            result += &format!("func {}() {} {{\n  return {}{{\n", constructor, type_name, type_name);
+            for field in constructor_fields {
+                result += &format!("    {},\n", field);
+            }
+            result += "  }\n}\n";
+            self.constructed_tys.borrow_mut().insert(type_name.clone());
         }
-
-        result += "}\n";
         ctx.append_file(self.name(), &self.dst(ctx), &result);
 
 
@@ -279,4 +366,80 @@ impl GolangGenerator {
         default_file.into()
 
     }
-}
\ No newline at end of file
+}
+
+/// The doc comment of a type, from its description and its deprecation
+fn go_doc(schema: &RawSchema) -> String {
+    let mut doc = utils::get_schema_description(schema).map_or(String::new(), |description| utils::description_to_comment(description, "// "));
+    if let Some(deprecated) = utils::get_schema_deprecated(schema) {
+        // the deprecation is a paragraph of its own, which is how the go tools find it
+        if !doc.is_empty() {
+            doc += "//\n";
+        }
+        doc += &go_deprecated_comment(deprecated, "");
+    }
+    doc
+}
+
+/// The "Deprecated:" comment of a deprecated item
+fn go_deprecated_comment(deprecated: &RawDeprecated, indent: &str) -> String {
+    utils::description_to_comment(&utils::deprecation_text(deprecated), &format!("{}// ", indent))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use cronus_parser::api_parse;
+
+    use crate::{run_generator, Ctxt, Generator};
+    use anyhow::{Ok, Result};
+    use super::GolangGenerator;
+
+    #[test]
+    fn go_default_constructor() -> Result<()>{
+        let api_file: &'static str = r#"
+        struct Base {
+            count: int = 10
+        }
+        struct Todo : Base {
+            id: string
+            title: string = "untitled"
+        }
+        struct NewTodo = omit Todo (id)
+        "#;
+
+        // the derived struct is expanded when the spec is loaded
+        let spec = cronus_parser::from_api_str(api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = GolangGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("domain.golang").unwrap();
+        assert!(file_content.contains("func NewTodoWithDefaults() Todo {"), "{}", file_content);
+        assert!(file_content.contains("    Base: NewBaseWithDefaults(),\n"), "{}", file_content);
+        assert!(file_content.contains("type NewTodo struct {"), "{}", file_content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn go_colliding_constructor() -> Result<()>{
+        let api_file: &'static str = r#"
+        struct Todo {
+            title: string = "untitled"
+        }
+        struct NewTodoWithDefaults {
+            title: string
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let err = run_generator(&GolangGenerator::new(), &ctx).unwrap_err();
+        assert!(err.to_string().contains("the constructor 'NewTodoWithDefaults' of the default values of 'Todo' has the name of a type"), "{}", err);
+
+        Ok(())
+    }
+}
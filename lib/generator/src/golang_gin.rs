@@ -1,8 +1,8 @@
 use anyhow::bail;
 use convert_case::{Case, Casing};
 use cronus_spec::{
-    GolangGinGeneratorOption, PythonFastApiGeneratorOption, RawSchema, RawUsecaseMethod,
-    RawUsecaseMethodRestOption,
+    GolangGinGeneratorOption, PythonFastApiGeneratorOption, RawSchema, RawSchemaPropertyFormat,
+    RawUsecaseMethod, RawUsecaseMethodRestOption,
 };
 use std::{any::type_name, cell::RefCell, collections::HashSet, fmt::format, path::PathBuf};
 
@@ -46,7 +46,38 @@ impl Generator for GolangGinGenerator {
         let domain_import = gen_opt
             .and_then(|opt| opt.domain_import.as_ref())
             .ok_or_else(|| anyhow::anyhow!("golang_gin domain_import option is not set"))?;
-        let imports = vec![domain_import, "net/http", "github.com/gin-gonic/gin"];
+        let mut imports = vec![domain_import.clone(), "net/http".to_string(), "github.com/gin-gonic/gin".to_string()];
+        // imports of the scalar and declared types, only the request fields of the rest methods are declared in this file
+        let mut used_tys = HashSet::new();
+        let mut has_errors = false;
+        let mut has_streams = false;
+        let mut has_patterns = false;
+        for usecase in ctx.spec.usecases.iter().flat_map(|usecases| usecases.values()) {
+            for method in usecase.methods.values() {
+                if method.option.as_ref().and_then(|option| option.rest.as_ref()).is_none() {
+                    continue;
+                }
+                if let Some(req) = &utils::resolve_method_spreads(ctx, method)?.req {
+                    utils::collect_schema_tys(req, &mut used_tys);
+                    has_patterns |= req.properties.iter().flatten().any(|(_, prop)| get_pattern(prop).is_some());
+                }
+                has_errors |= !utils::get_method_errors(ctx, method)?.is_empty();
+                has_streams |= utils::is_stream_method(method);
+            }
+        }
+        // the declared errors are matched with errors.Is
+        if has_errors {
+            imports.push("errors".to_string());
+        }
+        // ctx.Stream writes to an io.Writer
+        if has_streams {
+            imports.push("io".to_string());
+        }
+        // the patterns are matched after binding
+        if has_patterns {
+            imports.push("regexp".to_string());
+        }
+        imports.extend(utils::get_golang_imports(ctx, &used_tys));
         let mut imports_str = imports
             .iter()
             .map(|import| format!("import \"{}\"", import))
@@ -123,13 +154,16 @@ impl Generator for GolangGinGenerator {
                 None => &"".to_string(),
             };
 
+            let method = &utils::resolve_method_spreads(ctx, method)?;
+            utils::check_readonly_params(method)?;
+
             // register the route with the lambda function
             result += &format!(
                 "  {}.{}(\"{}\", {})\n",
                 rg_var,
                 rest.method.to_ascii_uppercase(),
                 rest_path,
-                self.gen_usecase_method(ctx, service_var, domain_package, &method_name, method)?
+                self.gen_usecase_method(ctx, service_var, domain_package, method_name, method)?
             );
 
             //     // prepare body if http method is not get
@@ -320,6 +354,7 @@ impl Generator for GolangGinGenerator {
 }
 
 fn get_method_path_names_and_tys(
+    ctx: &Ctxt,
     method: &RawUsecaseMethod,
 ) -> Result<Option<(Vec<String>, Vec<String>)>> {
     let path_params = utils::get_path_params(method);
@@ -340,11 +375,11 @@ fn get_method_path_names_and_tys(
 
                 let ty: String;
 
-                if prop_schema.items.is_some() {
-                    bail!("array property cannot be used as path variable")
+                if prop_schema.items.is_some() || prop_schema.values.is_some() {
+                    bail!("array or map property cannot be used as path variable")
                 }
 
-                if let Some(t) = utils::spec_ty_to_py_builtin_ty(prop_schema.ty.as_ref().unwrap()) {
+                if let Some(t) = utils::spec_ty_to_py_builtin_ty(ctx, prop_schema.ty.as_ref().unwrap()) {
                     ty = t;
                 } else {
                     ty = prop_schema.ty.as_ref().unwrap().clone();
@@ -370,8 +405,14 @@ impl GolangGinGenerator {
         uri_tag_props: Option<HashSet<String>>,
         json_tag_props: Option<HashSet<String>>,
     ) -> Result<String> {
+        let domain_package = self
+            .get_gen_option(ctx)
+            .and_then(|gen_opt| gen_opt.domain_package.as_ref())
+            .ok_or_else(|| anyhow::anyhow!("golang_gin domain_package option is not set"))?;
         let mut result = String::new();
         result += "struct {\n";
+        let ty_name = name.clone().unwrap_or_default();
+        utils::check_ident_collisions(&ty_name, schema.properties.as_ref().unwrap().keys(), utils::go_ident)?;
         for (prop_name, prop_schema) in schema.properties.as_ref().unwrap() {
             if prop_schema
                 .option
@@ -382,42 +423,35 @@ impl GolangGinGenerator {
                 // skip properties if exclude is set
                 continue;
             }
-            let ty = if prop_schema.items.is_some() {
-                // if items is set, it is an array type
-                let item_ty = prop_schema
-                    .items
-                    .as_ref()
-                    .and_then(|items| items.ty.as_ref())
-                    .ok_or_else(|| anyhow::anyhow!("items type is not set for {}", prop_name))?;
-                let ty = spec_ty_to_golang_builtin_ty(item_ty)
-                    .unwrap_or_else(|| item_ty.clone());
-                format!("[]{}", ty)
-                
-            } else {
-                spec_ty_to_golang_builtin_ty(prop_schema.ty.as_ref().unwrap())
-                .unwrap_or_else(|| prop_schema.ty.as_ref().unwrap().clone())
-            };
+            let ty = get_field_ty(ctx, domain_package, prop_name, prop_schema)?;
             
            
-            let actual_ty = if prop_schema.required.unwrap_or(false) {
-                // if required, use the type directly
-                ty
-            } else {
-                // if not required, use pointer type
+            let default = utils::get_default_value(ctx, prop_schema)?;
+            let actual_ty = if is_pointer_field(ctx, prop_schema)? {
+                // if not required or nullable, use pointer type
                 format!("*{}", ty)
+            } else {
+                // if required or defaulted, use the type directly
+                ty
             };
             let camel_prop_name = prop_name.to_case(Case::Camel);
-            let upper_camel_prop_name = camel_prop_name.to_case(Case::UpperCamel);
+            let upper_camel_prop_name = utils::go_ident(prop_name);
+            // the body and query fields are named as the property on the wire
+            let wire_name = utils::get_wire_name(ctx, prop_name, prop_schema).unwrap_or_else(|| camel_prop_name.clone());
             let mut tags: Vec<String> = Vec::new();
             if let Some(json_props) = json_tag_props.as_ref() {
-                if json_props.contains(&camel_prop_name) {
-                    tags.push(format!("json:\"{}\"", camel_prop_name));
+                if json_props.contains(prop_name) {
+                    tags.push(format!("json:\"{}\"", wire_name));
                 }
             }
 
             if let Some(form_props) = form_tag_props.as_ref() {
-                if form_props.contains(&camel_prop_name) {
-                    tags.push(format!("form:\"{}\"", camel_prop_name));
+                if form_props.contains(prop_name) {
+                    match &default {
+                        // gin fills in the default value of an absent form field
+                        Some(default) => tags.push(format!("form:\"{},default={}\"", wire_name, utils::default_value_to_json(default).to_string().trim_matches('"'))),
+                        None => tags.push(format!("form:\"{}\"", wire_name)),
+                    }
                 }
             }
 
@@ -426,6 +460,11 @@ impl GolangGinGenerator {
                     tags.push(format!("uri:\"{}\"", camel_prop_name));
                 }
             }
+
+            let bindings = get_binding_rules(prop_schema, actual_ty.starts_with('*'));
+            if !bindings.is_empty() {
+                tags.push(format!("binding:\"{}\"", bindings.join(",")));
+            }
             let tags_str = if tags.is_empty() {
                 "".to_string()
             } else {
@@ -482,9 +521,19 @@ impl GolangGinGenerator {
             query_params.clone()
         };
 
+        // a named request struct (createTodo NewTodo) is the domain request itself
+        let request_ty = match &method.req {
+            Some(req) if utils::is_named_ty(req) => {
+                if !utils::is_struct_ty(ctx, req) {
+                    bail!("the input of method '{}' has to be a struct to be bound from the request", method_name)
+                }
+                utils::get_prefixed_ty_name(req.ty.as_ref().unwrap())
+            },
+            _ => get_request_name(ctx, method_name),
+        };
+
         // request variable creation
         if let Some(req) = &method.req {
-            let request_ty = get_request_name(ctx, method_name);
             result += &format!(
                 "  var request {}\n",
                 self.gen_anonymous_struct(
@@ -535,7 +584,7 @@ impl GolangGinGenerator {
             .map_or_else(HashSet::new, |props| {
                 props
                     .iter()
-                    .map(|(k, _)| k.to_string().to_case(Case::UpperCamel))
+                    .map(|(k, _)| utils::go_ident(k))
                     .collect::<HashSet<String>>()
             });
 
@@ -572,8 +621,11 @@ impl GolangGinGenerator {
             }
         }
 
+        if let Some(req) = &method.req {
+            result += &gen_pattern_checks(ctx, req)?;
+        }
+
         if method.req.is_some() || !extra_fields.is_empty() {
-            let request_ty = get_request_name(ctx, method_name);
             result += &format!(" domain_request := {}.{}{{\n", domain_package, request_ty);
 
             // handle extra request fields
@@ -595,7 +647,7 @@ impl GolangGinGenerator {
                         // skip properties if exclude is set
                         continue;
                     }
-                    let upper_camel_prop_name = prop_name.to_case(Case::UpperCamel);
+                    let upper_camel_prop_name = utils::go_ident(prop_name);
                     // let passed_by = if prop_schema.required.unwrap_or(false) {
                     //     ""
                     // } else {
@@ -612,18 +664,41 @@ impl GolangGinGenerator {
         }
 
         let has_res = method.res.is_some();
-        let res_var = if has_res { "response, " } else { "" };
+        let is_stream = utils::is_stream_method(method);
+        let res_var = if is_stream { "stream," } else if has_res { "response, " } else { "" };
         result += &format!(
             "  {} err := {}.{}(ctx, &domain_request)\n",
             res_var,
             usecase_var,
             method_name.to_case(Case::UpperCamel)
         );
+        let errors = utils::get_method_errors(ctx, method)?;
         result += "  if err != nil {\n";
-        result += "    ctx.JSON(http.StatusInternalServerError, gin.H{\"error\": err.Error()})\n";
+        if errors.is_empty() {
+            result += "    ctx.JSON(http.StatusInternalServerError, gin.H{\"error\": err.Error()})\n";
+        } else {
+            // the declared errors are responded with their status codes
+            result += "    switch {\n";
+            for (error_name, error) in errors {
+                result += &format!("    case errors.Is(err, {}.{}):\n", domain_package, utils::get_golang_error_name(error_name));
+                result += &format!("      ctx.JSON({}, gin.H{{\"error\": err.Error()}})\n", error.status());
+            }
+            result += "    default:\n";
+            result += "      ctx.JSON(http.StatusInternalServerError, gin.H{\"error\": err.Error()})\n";
+            result += "    }\n";
+        }
         result += "    return\n";
         result += "  }\n";
-        if has_res {
+        if is_stream {
+            // the responses are sent as server-sent events until the channel is closed
+            result += "  ctx.Stream(func(w io.Writer) bool {\n";
+            result += "    if response, ok := <-stream; ok {\n";
+            result += "      ctx.SSEvent(\"message\", response)\n";
+            result += "      return true\n";
+            result += "    }\n";
+            result += "    return false\n";
+            result += "  })\n";
+        } else if has_res {
             result += "  ctx.JSON(http.StatusOK, response)\n";
         } else {
             result += "  ctx.Status(http.StatusNoContent)\n";
@@ -654,3 +729,94 @@ impl GolangGinGenerator {
             .unwrap_or_else(|| default_file.into())
     }
 }
+
+/// Get the golang type of a request field, arrays and maps are nested into each other, e.g. [][]string or map[string][]int32
+///
+/// A declared type is one of the domain package, by its prefixed name, e.g. domain.BillingInvoice for billing.Invoice
+fn get_field_ty(ctx: &Ctxt, domain_package: &str, prop_name: &str, schema: &RawSchema) -> Result<String> {
+    let domain_ty = |ty: &str| spec_ty_to_golang_builtin_ty(ctx, ty)
+        .unwrap_or_else(|| format!("{}.{}", domain_package, utils::get_prefixed_ty_name(ty)));
+    if let Some(items) = &schema.items {
+        return Ok(format!("[]{}", get_field_ty(ctx, domain_package, prop_name, items)?))
+    }
+    if let Some(values) = &schema.values {
+        let key_ty = domain_ty(utils::get_map_keys(schema));
+        return Ok(format!("map[{}]{}", key_ty, get_field_ty(ctx, domain_package, prop_name, values)?))
+    }
+    let ty = schema.ty.as_ref().ok_or_else(|| anyhow::anyhow!("type is not set for {}", prop_name))?;
+    Ok(domain_ty(ty))
+}
+
+/// Whether the request field of a property is a pointer, which is nil when the property is absent or null
+fn is_pointer_field(ctx: &Ctxt, schema: &RawSchema) -> Result<bool> {
+    let defaulted = utils::get_default_value(ctx, schema)?.is_some();
+    Ok(!(schema.required.unwrap_or(false) || defaulted) || schema.nullable.unwrap_or(false))
+}
+
+fn get_pattern(schema: &RawSchema) -> Option<&String> {
+    schema.option.as_ref().and_then(|option| option.pattern.as_ref())
+}
+
+/// Match the string fields of the bound request against their `pattern`, which has no builtin validator tag
+fn gen_pattern_checks(ctx: &Ctxt, req: &RawSchema) -> Result<String> {
+    let mut props: Vec<_> = req.properties.iter().flatten().collect();
+    props.sort_by_key(|(name, _)| *name);
+    let mut result = String::new();
+    for (prop_name, prop_schema) in props {
+        let Some(pattern) = get_pattern(prop_schema) else {
+            continue
+        };
+        if prop_schema.option.as_ref().and_then(|o| o.golang_gin.as_ref().and_then(|opt| opt.exclude)).unwrap_or(false) {
+            continue
+        }
+        if prop_schema.items.is_some() || prop_schema.ty.as_deref() != Some("string") {
+            bail!("the pattern of property '{}' can only be matched against a string", prop_name)
+        }
+        let field = format!("request.{}", utils::go_ident(prop_name));
+        let condition = if is_pointer_field(ctx, prop_schema)? {
+            format!("{} != nil && !regexp.MustCompile({}).MatchString(*{})", field, utils::string_literal(pattern), field)
+        } else {
+            format!("!regexp.MustCompile({}).MatchString({})", utils::string_literal(pattern), field)
+        };
+        let message = format!("{} does not match the pattern {}", prop_name, pattern);
+        result += &format!("  if {} {{\n", condition);
+        result += &format!("    ctx.JSON(http.StatusBadRequest, gin.H{{\"error\": {}}})\n", utils::string_literal(&message));
+        result += "    return\n";
+        result += "  }\n";
+    }
+    Ok(result)
+}
+
+/// Get the gin (go-playground/validator) binding rules for the constraints of a property.
+///
+/// `pattern` has no builtin validator tag, it is matched by [gen_pattern_checks] after binding.
+fn get_binding_rules(schema: &RawSchema, optional: bool) -> Vec<String> {
+    let mut rules = Vec::new();
+    let option = match &schema.option {
+        Some(option) => option,
+        None => return rules,
+    };
+    if let Some(min) = option.min {
+        rules.push(format!("gte={}", utils::constraint_number_to_literal(min)));
+    }
+    if let Some(max) = option.max {
+        rules.push(format!("lte={}", utils::constraint_number_to_literal(max)));
+    }
+    // for strings and slices, min and max check the length
+    if let Some(min_len) = option.min_len {
+        rules.push(format!("min={}", min_len));
+    }
+    if let Some(max_len) = option.max_len {
+        rules.push(format!("max={}", max_len));
+    }
+    match option.format {
+        Some(RawSchemaPropertyFormat::Email) => rules.push("email".to_string()),
+        Some(RawSchemaPropertyFormat::Url) => rules.push("url".to_string()),
+        None => {}
+    }
+    if optional && !rules.is_empty() {
+        // an absent optional field skips the rest of the rules
+        rules.insert(0, "omitempty".to_string());
+    }
+    rules
+}
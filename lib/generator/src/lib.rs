@@ -17,7 +17,7 @@ use std::{rc::Rc, cell::{RefCell}, collections::{HashMap, HashSet}, path::{Path,
 use openapi::OpenAPIGenerator;
 use rust::RustGenerator;
 use rust_axum::RustAxumGenerator;
-use cronus_spec::{RawSchema, RawSpec, RawUsecase, RawUsecaseMethod};
+use cronus_spec::{RawError, RawSchema, RawSpec, RawUsecase, RawUsecaseMethod};
 use tracing::info;
 use ts::TypescriptGenerator;
 use ts_nestjs::TypescriptNestjsGenerator;
@@ -140,6 +140,9 @@ pub trait Generator {
     fn generate_usecase(&self, _ctx: &Ctxt, _usecase_name: &str, _usecase: &RawUsecase) -> Result<()> {
         Ok(())
     }
+    fn generate_error(&self, _ctx: &Ctxt, _error_name: &str, _error: &RawError) -> Result<()> {
+        Ok(())
+    }
 }
 
 pub fn generate(ctx: &Ctxt) -> Result<()> {
@@ -203,10 +206,24 @@ pub fn run_generator(g: &dyn Generator, ctx: &Ctxt) -> Result<()> {
             .flat_map(|t| t.iter());
 
     for (name, schema) in schema_items {
+        if schema.alias.is_some() {
+            // declared types (type Timestamp) are mapped to the types of each language, not generated
+            continue;
+        }
         g.generate_schema(ctx, name,schema)?
     }
 
-    
+    let mut error_items: Vec<(&String, &RawError)> = ctx.spec
+    .errors
+    .iter()
+    .flat_map(|m| m.iter())
+    .collect();
+    error_items.sort_by_key(|(name, _)| *name);
+
+    for (name, error) in error_items {
+        g.generate_error(ctx, name, error)?
+    }
+
     let usecase_items = ctx.spec
     .usecases
     .iter()
@@ -224,7 +241,7 @@ pub fn run_generator(g: &dyn Generator, ctx: &Ctxt) -> Result<()> {
 
 #[cfg(test)]
 mod test {
-    use std::{collections::HashSet, path::{Path, PathBuf}, process::Command};
+    use std::{collections::HashSet, fs, path::{Path, PathBuf}, process::Command};
 
     use cronus_spec::RawSpec;
     use anyhow::{bail, Result};
@@ -258,7 +275,7 @@ mod test {
         let spec = cronus_parser::from_file(&spec_file, true, None, &mut explored)?;
         let ctx = Ctxt::new(spec);
         generate(&ctx)?;
-        run_cargo_check(&proj_dir)
+        run_cargo_check(&proj_dir, None)
     }
 
     #[test]
@@ -269,14 +286,66 @@ mod test {
         let spec = cronus_parser::from_file(&spec_file, true, None, &mut explored)?;
         let ctx = Ctxt::new(spec);
         generate(&ctx)?;
-        run_cargo_check(&proj_dir)
+        run_cargo_check(&proj_dir, None)
+    }
+
+    #[test]
+    fn e2e_deprecated_rust() -> Result<()> {
+        // the crate denies the use of deprecated items, the generated code allows the ones it refers to
+        let proj_dir = get_cargo_manifest_dir().unwrap().join("testdata").join("deprecated").join("rust");
+        check_generated_project(&proj_dir)
     }
 
-    fn run_cargo_check(dir: &Path) -> Result<()> {
-        let output = Command::new("cargo")
-            .arg("check")
-            .current_dir(dir)
-            .output()?;
+    #[test]
+    fn e2e_readonly_rust() -> Result<()> {
+        // a readonly field is skipped when deserialized, so its type needs a default
+        let proj_dir = get_cargo_manifest_dir().unwrap().join("testdata").join("readonly").join("rust");
+        check_generated_project(&proj_dir)
+    }
+
+    /// Generate the code of a testdata project into a copy of it and check the copy, the project is left untouched
+    fn check_generated_project(proj_dir: &Path) -> Result<()> {
+        let name = proj_dir.iter().rev().take(2).map(|part| part.to_string_lossy()).collect::<Vec<_>>().join("_");
+        let dir = std::env::temp_dir().join(format!("cronus_{}_{}", name, std::process::id()));
+        copy_project(proj_dir, &dir)?;
+
+        let result = (|| {
+            let mut explored = HashSet::new();
+            let spec = cronus_parser::from_file(&dir.join("main.api"), true, None, &mut explored)?;
+            let ctx = Ctxt::new(spec);
+            generate(&ctx)?;
+            ctx.dump()?;
+            // the build of the project is kept between runs
+            run_cargo_check(&dir, Some(&proj_dir.join("target")))
+        })();
+        fs::remove_dir_all(&dir)?;
+        result
+    }
+
+    /// Copy the sources of a project, without its build
+    fn copy_project(from: &Path, to: &Path) -> Result<()> {
+        fs::create_dir_all(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            if entry.file_name() == "target" {
+                continue;
+            }
+            if entry.file_type()?.is_dir() {
+                copy_project(&entry.path(), &to.join(entry.file_name()))?;
+            } else {
+                fs::copy(entry.path(), to.join(entry.file_name()))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn run_cargo_check(dir: &Path, target_dir: Option<&Path>) -> Result<()> {
+        let mut command = Command::new("cargo");
+        command.arg("check").current_dir(dir);
+        if let Some(target_dir) = target_dir {
+            command.env("CARGO_TARGET_DIR", target_dir);
+        }
+        let output = command.output()?;
 
         if !output.status.success() {
             bail!("Stdout: {}\nStderr: {}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr))
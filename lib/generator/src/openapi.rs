@@ -2,10 +2,10 @@ use std::{cell::RefCell, collections::{HashMap, HashSet}, fmt::format};
 
 use anyhow::{bail, Ok, Result};
 use convert_case::{Case, Casing};
-use cronus_spec::{OpenapiGeneratorOption, RawSchema, RawUsecaseMethod};
+use cronus_spec::{OpenapiGeneratorOption, RawSchema, RawSchemaPropertyFormat, RawUsecaseMethod};
 use tracing::{span, Level};
 
-use crate::{openapi_utils::{InfoObject, MediaTypeObject, OpenApiComponentsObject, OpenApiDocument, OperationObject, ParameterObject, PathItemObject, RequestBodyObject, ResponseObject, ResponsesObject, SchemaObject}, utils::{self, extract_url_variables, get_path_from_optional_parent, get_request_name, get_response_name, get_schema_by_name, parse_map_type, spec_ty_to_openapi_builtin_ty, spec_ty_to_rust_builtin_ty}, Ctxt, Generator};
+use crate::{openapi_utils::{InfoObject, MediaTypeObject, OpenApiComponentsObject, OpenApiDocument, OperationObject, ParameterObject, PathItemObject, RequestBodyObject, ResponseObject, ResponsesObject, SchemaObject}, utils::{self, get_path_from_optional_parent, get_request_name, get_response_name, get_schema_by_name, get_schema_parents, get_type_alias, spec_ty_to_openapi_builtin_ty}, Ctxt, Generator};
 
 
 
@@ -14,6 +14,57 @@ pub struct OpenAPIGenerator {
     openapi_doc: RefCell<Option<OpenApiDocument>>
 }
 
+/// Allow null for a property, the siblings of a $ref are ignored so a reference is wrapped into allOf
+fn make_nullable(mut obj: Box<SchemaObject>) -> Box<SchemaObject> {
+    if obj.ref_.is_none() {
+        obj.nullable = Some(true);
+        return obj
+    }
+    let default = obj.default.take();
+    Box::new(SchemaObject {
+        all_of: Some(vec![*obj]),
+        default,
+        nullable: Some(true),
+        ..Default::default()
+    })
+}
+
+/// Set the default value of a property, the siblings of a $ref are ignored so a reference is wrapped into allOf
+fn set_default(mut obj: Box<SchemaObject>, default: Option<serde_json::Value>) -> Box<SchemaObject> {
+    if default.is_none() {
+        return obj
+    }
+    if obj.ref_.is_some() {
+        obj = Box::new(SchemaObject {
+            all_of: Some(vec![*obj]),
+            ..Default::default()
+        });
+    }
+    obj.default = default;
+    obj
+}
+
+/// Mark a property readOnly, writeOnly or deprecated, [readonly], [writeonly] or [deprecated], a reference is wrapped into allOf as well
+fn apply_property_modifiers(mut obj: Box<SchemaObject>, schema: &RawSchema) -> Box<SchemaObject> {
+    let (readonly, writeonly) = (utils::is_readonly(schema), utils::is_writeonly(schema));
+    let deprecated = utils::get_schema_deprecated(schema).is_some();
+    if !readonly && !writeonly && !deprecated {
+        return obj
+    }
+    if obj.ref_.is_some() {
+        let default = obj.default.take();
+        obj = Box::new(SchemaObject {
+            all_of: Some(vec![*obj]),
+            default,
+            ..Default::default()
+        });
+    }
+    obj.read_only = readonly.then_some(true);
+    obj.write_only = writeonly.then_some(true);
+    obj.deprecated = deprecated.then_some(true);
+    obj
+}
+
 impl OpenAPIGenerator {
     pub fn new() -> Self {
         Self {
@@ -54,6 +105,9 @@ impl Generator for OpenAPIGenerator {
     }
 
     fn generate_schema(&self, ctx: &Ctxt, schema_name:&str, schema: &RawSchema) -> Result<()> {
+        if is_excluded(schema) {
+            return Ok(());
+        }
         self.generate_schema_with_ignore(ctx, Some(schema_name.to_string()), schema,  None);
         Ok(())
     }
@@ -70,8 +124,14 @@ impl Generator for OpenAPIGenerator {
                 if let Some(rest_option) = &options.rest {
                     let method_path = rest_option.path.as_ref().map(|p| if usecase_prefix.ends_with("/") { format!("{}{}", usecase_prefix, p)} else { format!("{}/{}", usecase_prefix, p)} ).unwrap_or(usecase_prefix.clone());
                     let path_item = openapi.paths.entry(replace_colon_with_braces(&method_path, case)).or_insert_with(PathItemObject::default);
+                    let method = &utils::resolve_method_spreads(ctx, method)?;
+                    utils::check_readonly_params(method)?;
                     let mut operation = self.create_operation_object(ctx, method_name, method)?;
                     operation.tags = Some(vec![usecase_name.to_string()]);
+                    // every operation of a deprecated usecase is deprecated
+                    if utils::get_usecase_deprecated(usecase).is_some() {
+                        operation.deprecated = Some(true);
+                    }
                     match rest_option.method.to_lowercase().as_str() {
                         "get" => path_item.get = Some(operation),
                         "put" => path_item.put = Some(operation),
@@ -119,7 +179,7 @@ impl Generator for OpenAPIGenerator {
 
 enum SchemaType {
     Ref(String),
-    Basic(String),
+    Basic(SchemaObject),
     Arr(Box<SchemaType>),
     Dict(Option<Box<SchemaType>>)
 }
@@ -128,24 +188,69 @@ impl SchemaType {
     fn to_schema_object(self) -> Box<SchemaObject> {
         match self {
             SchemaType::Ref(s) => Box::new(SchemaObject::new_with_ref(openapi_ref_type(&s))),
-            SchemaType::Basic(s) => Box::new(SchemaObject::new_with_type(s)),
+            SchemaType::Basic(s) => Box::new(s),
             SchemaType::Arr(s) => Box::new(SchemaObject::new_with_items(s.to_schema_object())),
             SchemaType::Dict(s) => Box::new(SchemaObject::new_dict(s.and_then(|s|Some(s.to_schema_object()))))
         }
     }
 }
 
+/// Whether a type or a property is left out of the document, [openapi.exclude]
+fn is_excluded(schema: &RawSchema) -> bool {
+    schema.option.as_ref()
+        .and_then(|o| o.openapi.as_ref().and_then(|opt| opt.exclude))
+        .unwrap_or(false)
+}
+
+/// Whether a property is of an excluded type, or of an array or a map of it, so it is left out as well
+fn refers_excluded_ty(ctx: &Ctxt, schema: &RawSchema) -> bool {
+    if let Some(nested) = schema.items.as_ref().or(schema.values.as_ref()) {
+        return refers_excluded_ty(ctx, nested);
+    }
+    schema.ty.as_ref()
+        .and_then(|ty| get_schema_by_name(ctx, ty))
+        .is_some_and(is_excluded)
+}
+
+/// Copy the validation constraints of a property into its schema object
+fn apply_constraints(obj: &mut SchemaObject, schema: &RawSchema) {
+    let option = match &schema.option {
+        Some(option) => option,
+        None => return,
+    };
+    obj.minimum = option.min;
+    obj.maximum = option.max;
+    if obj.items.is_some() {
+        obj.min_items = option.min_len;
+        obj.max_items = option.max_len;
+    } else {
+        obj.min_length = option.min_len;
+        obj.max_length = option.max_len;
+    }
+    obj.pattern = option.pattern.clone();
+    match option.format {
+        Some(RawSchemaPropertyFormat::Email) => obj.format = Some("email".to_string()),
+        Some(RawSchemaPropertyFormat::Url) => obj.format = Some("uri".to_string()),
+        None => {}
+    }
+}
+
 impl OpenAPIGenerator {
 
+    /// The field_case of the generator, else the wire case of the spec
     fn get_case(&self, ctx: &Ctxt) -> Case {
         ctx.spec.option.as_ref()
             .and_then(|go| go.generator.as_ref())
             .and_then(|gen| gen.openapi.as_ref())
             .and_then(|opt| opt.field_case.as_ref())
-            .map_or(Case::Snake, |c| match c {
+            .map(|c| match c {
                 cronus_spec::Case::Snake => Case::Snake,
                 cronus_spec::Case::Camel => Case::Camel,
+                cronus_spec::Case::Pascal => Case::Pascal,
+                cronus_spec::Case::Kebab => Case::Kebab,
             })
+            .or_else(|| utils::get_wire_case(ctx))
+            .unwrap_or(Case::Snake)
     }
 
     /// Return the type name
@@ -159,11 +264,22 @@ impl OpenAPIGenerator {
         let case = self.get_case(ctx);
         let type_name: String;
         if let Some(ty) = &override_ty {
-            type_name = ty.to_case(Case::UpperCamel);
+            type_name = utils::get_prefixed_ty_name(ty);
         }
         else if schema.items.is_some() {
             return Ok(SchemaType::Arr(Box::new(self.generate_schema_with_ignore(ctx, override_ty, schema.items.as_ref().unwrap(), None)?)));
         }
+        // a map is an object with additionalProperties
+        else if let Some(values) = &schema.values {
+            if utils::get_map_keys(schema) != "string" {
+                bail!("Dictionary's key has to be 'string'")
+            }
+            let values_ty = match values.ty.as_deref() {
+                Some("Value" | "serde_json::Value") => None,
+                _ => Some(Box::new(self.generate_schema_with_ignore(ctx, None, values, None)?)),
+            };
+            return Ok(SchemaType::Dict(values_ty));
+        }
         else {
             type_name = schema.ty.as_ref().unwrap().clone();
         }
@@ -178,26 +294,6 @@ impl OpenAPIGenerator {
             return Ok(SchemaType::Basic(ty));
         }
 
-        // if type name is map<xxx,xxx>
-        if type_name.starts_with("map<") {
-            let (left_ty, right_ty) = parse_map_type(&type_name);
-            if left_ty != "string" {
-                bail!("Dictionary's key has to be 'string'")
-            }
-
-            let right_ty = if let Some(ty) = spec_ty_to_openapi_builtin_ty(right_ty) {
-                Some(SchemaType::Basic(ty))
-            } 
-            else if right_ty == "Value" || right_ty == "serde_json::Value" {
-                None
-            }
-            else {
-                Some(SchemaType::Ref(right_ty.into()))
-            };
-
-            return Ok(SchemaType::Dict(right_ty.and_then(|t| Some(Box::new(t)))));
-
-        }
 
      
 
@@ -205,6 +301,19 @@ impl OpenAPIGenerator {
             return Ok(SchemaType::Ref(type_name));
         }
 
+        // a declared type (type Timestamp) is described by its openapi mapping
+        if let Some(alias) = get_type_alias(ctx, &type_name) {
+            let target = alias.openapi.as_ref();
+            let so = SchemaObject {
+                type_: Some(target.and_then(|t| t.ty.clone()).unwrap_or_else(|| "string".to_string())),
+                format: target.and_then(|t| t.format.clone()),
+                ..Default::default()
+            };
+            let alias_name = utils::get_prefixed_ty_name(&type_name);
+            self.generated_schemas.borrow_mut().insert(alias_name.clone(), so);
+            return Ok(SchemaType::Ref(alias_name));
+        }
+
         // if it is referenced to a custom type, find and return
         if let Some(ref_schema) = get_schema_by_name(&ctx, &type_name) {
             // check whether schema is a type referencing another user type
@@ -213,28 +322,45 @@ impl OpenAPIGenerator {
             }
         }
 
-        let required: Option<Vec<String>> = schema.properties.as_ref().map(|props| {
-            props
-                .iter()
-                .filter_map(|(key, value)| {
-                    if value.option.as_ref()
-                        .and_then(|o| o.openapi.as_ref().and_then(| opt| opt.exclude))
-                        .unwrap_or(false) {
-                            return None; // skip properties with openapi option
-                        }
-                    if let Some(ignore_props) = ignore_props {
-                        if ignore_props.contains(key) {
-                            return None;
-                        }
+        // taken before the properties, which refer to it by $ref if the schema is recursive
+        self.generated_schemas.borrow_mut().insert(type_name.clone(), SchemaObject::default());
+
+        // the fields of the spread types are part of the schema itself
+        let schema = &utils::resolve_schema_spreads(ctx, schema)?;
+
+        let mut required: Option<Vec<String>> = None;
+        let mut properties: Option<HashMap<String, SchemaObject>> = None;
+        if let Some(props) = &schema.properties {
+            let required = required.get_or_insert_with(Vec::new);
+            let properties = properties.get_or_insert_with(HashMap::new);
+            for (key, value) in props {
+                if is_excluded(value) || refers_excluded_ty(ctx, value) {
+                        continue; // skip properties with openapi option
                     }
-                    if value.required.unwrap_or(false) {
-                        Some(key.clone())
-                    } else {
-                        None
+                if let Some(ignore_props) = ignore_props {
+                    if ignore_props.contains(key) {
+                        continue;
                     }
-                })
-                .collect()
-        });
+                }
+
+                // the name of the property on the wire
+                let name = utils::get_json_name(value).map_or_else(|| key.to_case(case), str::to_string);
+                let default = utils::get_default_value(ctx, value)?;
+                // a property with a default value can be left out
+                if value.required.unwrap_or(false) && default.is_none() {
+                    required.push(name.clone());
+                }
+
+                let obj = self.generate_schema_with_ignore(ctx, None, value, None)?.to_schema_object();
+                let mut obj = set_default(obj, default.as_ref().map(utils::default_value_to_json));
+                apply_constraints(&mut obj, value);
+                if value.nullable.unwrap_or(false) {
+                    obj = make_nullable(obj);
+                }
+                obj = apply_property_modifiers(obj, value);
+                properties.insert(name, *obj);
+            }
+        }
 
         let items = if schema.items.is_some() {
             Some(self.generate_schema_with_ignore(ctx, None, schema.items.as_ref().unwrap(), None)?.to_schema_object())
@@ -243,33 +369,11 @@ impl OpenAPIGenerator {
         };
 
         // Ok, now we need to create a components-schemas for this custom schema
-        let so = SchemaObject {
+        let mut so = SchemaObject {
             type_: schema.ty.clone(),
             format: None, // Add logic for format if needed
             items,
-            properties: schema.properties.as_ref().map(|props| {
-                props
-                    .iter()
-                    .filter_map(|(key, value)| {
-                        if value.option.as_ref()
-                        .and_then(|o| o.openapi.as_ref().and_then(| opt| opt.exclude))
-                        .unwrap_or(false) {
-                            return None; // skip properties with openapi option
-                        }
-
-                        if let Some(ignore_props) = ignore_props {
-                            if ignore_props.contains(key) {
-                                return None;
-                            }
-                        }
-                        // FIXME: proper handle result
-                        let obj =  self.generate_schema_with_ignore(ctx, None, value, None).unwrap().to_schema_object();
-
-                        Some((key.to_case(case), *obj))
-                    })
-                    .collect()
-            })
-            ,
+            properties,
             required: required.and_then(|arr| if arr.is_empty() { None } else {Some(arr)}),
             enum_: schema.enum_items.as_ref().map(|enum_items| {
                 enum_items.iter().map(|item| item.name.to_case(Case::UpperSnake)).collect()
@@ -279,12 +383,33 @@ impl OpenAPIGenerator {
             any_of: None, // Add logic for anyOf if needed
             not: None,    // Add logic for not if needed
             description: schema.option.as_ref().and_then(|o| o.description.clone()),
+            deprecated: utils::get_schema_deprecated(schema).map(|_| true),
             default: None, // Add logic for default if needed
             ref_: None,
             nullable: None,
-            additional_properties: None
+            additional_properties: None,
+            ..Default::default()
         };
 
+        // inheritance is expressed as allOf [parents..., own fields]
+        let parents = get_schema_parents(schema);
+        if !parents.is_empty() {
+            let mut all_of = Vec::new();
+            for (_, parent) in parents {
+                let parent_ty = self.generate_schema_with_ignore(ctx, None, &RawSchema::new(schema.def_loc.clone(), parent), None)?;
+                all_of.push(*parent_ty.to_schema_object());
+            }
+            let description = so.description.take();
+            let deprecated = so.deprecated.take();
+            all_of.push(so);
+            so = SchemaObject {
+                all_of: Some(all_of),
+                description,
+                deprecated,
+                ..Default::default()
+            };
+        }
+
         self.generated_schemas.borrow_mut().insert(type_name.clone(), so);
 
         Ok(SchemaType::Ref(type_name))
@@ -334,56 +459,56 @@ let case = self.get_case(ctx);
 
 
         // parameters include path and query parameters
-        let parameters = method.req.as_ref().map(|req| {
-            req.properties
-                .as_ref()
-                .unwrap_or(&HashMap::new())
-                .iter()
-                .filter_map(|(key, schema)| {
-                    if schema.option.as_ref()
-                        .and_then(|o| o.openapi.as_ref().and_then(| opt| opt.exclude))
-                        .unwrap_or(false) {
-                            return None; // skip properties with openapi option
-                        }
-                        
-                    let is_path_var = path_params.as_ref().is_some_and(|path_params| path_params.contains(key));
-                    if is_path_var {
-                        return Some(ParameterObject {
-                            name: key.to_case(case),
-                            in_: "path".to_string(),
-                            description: schema.option.as_ref().and_then(|d| d.description.clone()),
-                            required: true, // For the path parameter, required should be True
-
-                            // FIXME: proper handle result
-
-                            schema: *self.generate_schema_with_ignore(ctx, None, schema,  None).unwrap().to_schema_object()
-                        })
+        let mut parameters: Option<Vec<ParameterObject>> = None;
+        if let Some(req) = &method.req {
+            let parameters = parameters.get_or_insert_with(Vec::new);
+            for (key, schema) in req.properties.iter().flatten() {
+                if is_excluded(schema) || refers_excluded_ty(ctx, schema) {
+                        continue; // skip properties with openapi option
                     }
-                    let prop_is_query = schema.option.as_ref().and_then(|option| {
-                        option.rest.as_ref().and_then(|rest_option|  {
-                            rest_option.query
-                        })
-                    }).unwrap_or(false);
-
-                    let is_query_var = http_method == "get" || prop_is_query;
-                            
-                    if is_query_var {
-                        query_params.insert(key.clone());
-                        Some(ParameterObject {
-                            name: key.to_case(case),
-                            in_: "query".to_string(),
-                            description: schema.option.as_ref().and_then(|d| d.description.clone()),
-                            required: schema.required.unwrap_or(false),
-                            // FIXME: proper handle result
+                    
+                let is_path_var = path_params.as_ref().is_some_and(|path_params| path_params.contains(key));
+                if is_path_var {
+                    parameters.push(ParameterObject {
+                        name: key.to_case(case),
+                        in_: "path".to_string(),
+                        description: schema.option.as_ref().and_then(|d| d.description.clone()),
+                        required: true, // For the path parameter, required should be True
+                        schema: {
+                            let mut param_schema = *self.generate_schema_with_ignore(ctx, None, schema,  None)?.to_schema_object();
+                            apply_constraints(&mut param_schema, schema);
+                            param_schema
+                        },
+                        deprecated: utils::get_schema_deprecated(schema).map(|_| true),
+                    });
+                    continue;
+                }
+                let prop_is_query = schema.option.as_ref().and_then(|option| {
+                    option.rest.as_ref().and_then(|rest_option|  {
+                        rest_option.query
+                    })
+                }).unwrap_or(false);
 
-                            schema: *self.generate_schema_with_ignore(ctx, None, schema,  None).unwrap().to_schema_object()
-                        })
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<ParameterObject>>()
-        });
+                let is_query_var = http_method == "get" || prop_is_query;
+                        
+                if is_query_var {
+                    query_params.insert(key.clone());
+                    let default = utils::get_default_value(ctx, schema)?;
+                    let param_schema = self.generate_schema_with_ignore(ctx, None, schema,  None)?.to_schema_object();
+                    let mut param_schema = *set_default(param_schema, default.as_ref().map(utils::default_value_to_json));
+                    apply_constraints(&mut param_schema, schema);
+                    parameters.push(ParameterObject {
+                        name: utils::get_json_name(schema).map_or_else(|| key.to_case(case), str::to_string),
+                        in_: "query".to_string(),
+                        description: schema.option.as_ref().and_then(|d| d.description.clone()),
+                        // a query parameter with a default value can be left out
+                        required: schema.required.unwrap_or(false) && default.is_none(),
+                        schema: param_schema,
+                        deprecated: utils::get_schema_deprecated(schema).map(|_| true),
+                    });
+                }
+            }
+        }
     
         // For the response, the type should be created in the components-schemas,
         // and use $ref in the response type
@@ -392,6 +517,10 @@ let case = self.get_case(ctx);
             responses: {
                 let mut response_map = HashMap::new();
                 let response_schema = match &method.res {
+                    // a named type is referred to as is
+                    Some(res) if utils::is_named_ty(res) => {
+                        *(self.generate_schema_with_ignore(ctx, None, res, None)?.to_schema_object())
+                    },
                     Some(res) => {
                         let res_ty = get_response_name(ctx, name);
                         *(self.generate_schema_with_ignore(ctx, Some(res_ty), res, None)?.to_schema_object())
@@ -405,18 +534,30 @@ let case = self.get_case(ctx);
                     },
                 };
 
+                // a stream method sends its responses as server-sent events
+                let content_type = if utils::is_stream_method(method) { "text/event-stream" } else { "application/json" };
                 response_map.insert(
                     "200".to_string(),
                     ResponseObject {
                         description: "Successful response".to_string(),
                         content: Some(HashMap::from([(
-                            "application/json".to_string(),
+                            content_type.to_string(),
                             MediaTypeObject {
                                 schema: Some(response_schema),
                             },
                         )])),
                     },
                 );
+
+                // a response for the status code of each declared error, the ones sharing a status code are described together
+                for (error_name, error) in utils::get_method_errors(ctx, method)? {
+                    let description = error.option.as_ref()
+                        .and_then(|option| option.description.clone())
+                        .unwrap_or_else(|| error_name.to_string());
+                    response_map.entry(error.status().to_string())
+                        .and_modify(|response: &mut ResponseObject| response.description = format!("{}, {}", response.description, description))
+                        .or_insert(ResponseObject { description, content: None });
+                }
                 response_map
             },
         };
@@ -426,6 +567,21 @@ let case = self.get_case(ctx);
                 return None;
             }
 
+            // a named type is referred to as a whole, along with its fields given in the path or the query
+            if utils::is_named_ty(req) {
+                let named = RawSchema { properties: None, ..req.clone() };
+                return Some(RequestBodyObject {
+                    description: None,
+                    content: HashMap::from([(
+                        "application/json".to_string(),
+                        MediaTypeObject {
+                            schema: Some(*self.generate_schema_with_ignore(ctx, None, &named, None).unwrap().to_schema_object()),
+                        },
+                    )]),
+                    required: Some(true),
+                })
+            }
+
             return Some(RequestBodyObject {
                 description: None, // Add description if needed
                 content: {
@@ -455,7 +611,8 @@ let case = self.get_case(ctx);
             parameters,
             request_body: request_body.unwrap_or(None),
             responses,
-            tags: None
+            tags: None,
+            deprecated: utils::get_method_deprecated(method).map(|_| true),
         })
     }
 }
@@ -564,4 +721,571 @@ mod tests {
         Ok(())
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_openapi_default_value() -> Result<()> {
+        let api_file: &'static str = r#"
+        enum Status {
+            Active
+        }
+        struct Hello {
+            a: int = 10
+            b: Status = Active
+            c: string
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = OpenAPIGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("openapi.yaml").unwrap();
+        let doc: OpenApiDocument = serde_yaml::from_str(file_content)?;
+
+        let hello = doc.components.as_ref().unwrap().schemas.as_ref().unwrap().get("Hello").unwrap();
+        let props = hello.properties.as_ref().unwrap();
+        assert_eq!(props.get("a").unwrap().default, Some(serde_json::Value::from(10)));
+        let b = props.get("b").unwrap();
+        assert_eq!(b.default, Some(serde_json::Value::from("ACTIVE")));
+        // a default next to a $ref would be ignored
+        assert_eq!(b.ref_, None);
+        assert_eq!(b.all_of.as_ref().unwrap()[0].ref_.as_deref(), Some("#/components/schemas/Status"));
+        assert_eq!(hello.required, Some(vec!["c".to_string()]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openapi_named_method_io() -> Result<()> {
+        let api_file: &'static str = r#"
+        struct Todo {
+            id: string
+            title: string
+        }
+        usecase todo {
+            [rest.path = "todos"]
+            [rest.method = "post"]
+            createTodo Todo -> Todo
+
+            [rest.path = "todos"]
+            [rest.method = "get"]
+            listTodos -> Todo[]
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = OpenAPIGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("openapi.yaml").unwrap();
+        let doc: OpenApiDocument = serde_yaml::from_str(file_content)?;
+
+        let path = doc.paths.get("/todos").unwrap();
+        let post_op = path.post.as_ref().unwrap();
+        let request_schema = post_op.request_body.as_ref().unwrap().content.get("application/json").unwrap().schema.as_ref().unwrap();
+        assert_eq!(request_schema.ref_.as_deref(), Some("#/components/schemas/Todo"));
+        let response_schema = post_op.responses.responses.get("200").unwrap().content.as_ref().unwrap().get("application/json").unwrap().schema.as_ref().unwrap();
+        assert_eq!(response_schema.ref_.as_deref(), Some("#/components/schemas/Todo"));
+
+        let get_op = path.get.as_ref().unwrap();
+        let response_schema = get_op.responses.responses.get("200").unwrap().content.as_ref().unwrap().get("application/json").unwrap().schema.as_ref().unwrap();
+        assert_eq!(response_schema.items.as_ref().unwrap().ref_.as_deref(), Some("#/components/schemas/Todo"));
+
+        // no wrapper is generated for the named types
+        let schemas = doc.components.as_ref().unwrap().schemas.as_ref().unwrap();
+        assert_eq!(schemas.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openapi_readonly_writeonly() -> Result<()> {
+        let api_file: &'static str = r#"
+        struct Owner {
+            name: string
+        }
+        struct User {
+            [readonly] id: string
+            [writeonly] password: string
+            [readonly] owner: Owner
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = OpenAPIGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("openapi.yaml").unwrap();
+        let doc: OpenApiDocument = serde_yaml::from_str(file_content)?;
+
+        let user = doc.components.as_ref().unwrap().schemas.as_ref().unwrap().get("User").unwrap();
+        let props = user.properties.as_ref().unwrap();
+        assert_eq!((props.get("id").unwrap().read_only, props.get("id").unwrap().write_only), (Some(true), None));
+        assert_eq!((props.get("password").unwrap().read_only, props.get("password").unwrap().write_only), (None, Some(true)));
+        // the siblings of a $ref are ignored
+        let owner = props.get("owner").unwrap();
+        assert_eq!(owner.read_only, Some(true));
+        assert_eq!(owner.all_of.as_ref().unwrap()[0].ref_.as_deref(), Some("#/components/schemas/Owner"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openapi_deprecated() -> Result<()> {
+        let api_file: &'static str = r#"
+        [deprecated = "use TodoV2"]
+        struct Todo {
+            [deprecated]
+            owner?: User
+            title: string
+        }
+        struct User {
+            name: string
+        }
+
+        [deprecated]
+        [rest.path = "todos"]
+        usecase Todo {
+            [rest.method = "get"]
+            listTodos {
+                [deprecated = "use cursor"]
+                page?: int
+            } -> Todo[]
+        }
+
+        usecase User {
+            [rest.method = "get"]
+            [rest.path = "users"]
+            [deprecated = "use searchUsers"]
+            listUsers -> User[]
+
+            [rest.method = "get"]
+            [rest.path = "users/:id"]
+            getUser {
+                id: string
+            } -> User
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = OpenAPIGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("openapi.yaml").unwrap();
+        let doc: OpenApiDocument = serde_yaml::from_str(file_content)?;
+
+        let schemas = doc.components.as_ref().unwrap().schemas.as_ref().unwrap();
+        let todo = schemas.get("Todo").unwrap();
+        assert_eq!(todo.deprecated, Some(true));
+        let properties = todo.properties.as_ref().unwrap();
+        // the siblings of a $ref are ignored, so the reference is wrapped into allOf
+        let owner = properties.get("owner").unwrap();
+        assert_eq!(owner.deprecated, Some(true));
+        assert_eq!(owner.all_of.as_ref().unwrap()[0].ref_.as_deref(), Some("#/components/schemas/User"));
+        assert_eq!(properties.get("title").unwrap().deprecated, None);
+        assert_eq!(schemas.get("User").unwrap().deprecated, None);
+
+        let list_todos = doc.paths.get("/todos").unwrap().get.as_ref().unwrap();
+        assert_eq!(list_todos.deprecated, Some(true));
+        assert_eq!(list_todos.parameters.as_ref().unwrap()[0].deprecated, Some(true));
+        assert_eq!(doc.paths.get("/users").unwrap().get.as_ref().unwrap().deprecated, Some(true));
+        assert_eq!(doc.paths.get("/users/{id}").unwrap().get.as_ref().unwrap().deprecated, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openapi_recursive_schemas() -> Result<()> {
+        let api_file: &'static str = r#"
+        struct Node {
+            children: Node[]
+        }
+        struct A {
+            b: B
+        }
+        struct B {
+            a?: A
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = OpenAPIGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("openapi.yaml").unwrap();
+        let doc: OpenApiDocument = serde_yaml::from_str(file_content)?;
+
+        let schemas = doc.components.as_ref().unwrap().schemas.as_ref().unwrap();
+        let prop = |ty: &str, name: &str| schemas.get(ty).unwrap().properties.as_ref().unwrap().get(name).unwrap().clone();
+        assert_eq!(prop("Node", "children").items.unwrap().ref_.as_deref(), Some("#/components/schemas/Node"));
+        assert_eq!(prop("A", "b").ref_.as_deref(), Some("#/components/schemas/B"));
+        assert_eq!(prop("B", "a").ref_.as_deref(), Some("#/components/schemas/A"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openapi_wire_case() -> Result<()> {
+        let api_file: &'static str = r#"
+        #[wire_case = "camel"]
+        struct User {
+            created_at: string
+            [json = "e-mail"] email: string
+        }
+
+        usecase user {
+            [rest.method = "get"]
+            [rest.path = "users"]
+            listUsers {
+                page_size?: int
+                [json = "q"] search_text?: string
+            } -> {
+                users: User[]
+            }
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = OpenAPIGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("openapi.yaml").unwrap();
+        let doc: OpenApiDocument = serde_yaml::from_str(file_content)?;
+
+        let user = doc.components.as_ref().unwrap().schemas.as_ref().unwrap().get("User").unwrap();
+        let mut props: Vec<&String> = user.properties.as_ref().unwrap().keys().collect();
+        props.sort();
+        assert_eq!(props, vec!["createdAt", "e-mail"]);
+        let mut required = user.required.clone().unwrap();
+        required.sort();
+        assert_eq!(required, vec!["createdAt".to_string(), "e-mail".to_string()]);
+
+        let operation = doc.paths.get("/users").unwrap().get.as_ref().unwrap();
+        let mut params: Vec<&str> = operation.parameters.iter().flatten().map(|param| param.name.as_str()).collect();
+        params.sort();
+        assert_eq!(params, vec!["pageSize", "q"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openapi_nullable() -> Result<()> {
+        let api_file: &'static str = r#"
+        struct Owner {
+            name: string
+        }
+        struct Hello {
+            a?: string | null
+            b: string | null
+            c: Owner | null
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = OpenAPIGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("openapi.yaml").unwrap();
+        let doc: OpenApiDocument = serde_yaml::from_str(file_content)?;
+
+        let hello = doc.components.as_ref().unwrap().schemas.as_ref().unwrap().get("Hello").unwrap();
+        let props = hello.properties.as_ref().unwrap();
+        assert_eq!(props.get("a").unwrap().nullable, Some(true));
+        assert_eq!(props.get("b").unwrap().nullable, Some(true));
+        let c = props.get("c").unwrap();
+        assert_eq!(c.nullable, Some(true));
+        assert_eq!(c.all_of.as_ref().unwrap()[0].ref_.as_deref(), Some("#/components/schemas/Owner"));
+        let mut required = hello.required.clone().unwrap();
+        required.sort();
+        assert_eq!(required, vec!["b".to_string(), "c".to_string()]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openapi_constraints() -> Result<()> {
+        let api_file: &'static str = r#"
+        struct Hello {
+            [min = 1]
+            [max = 100]
+            a: int
+            [max_len = 20]
+            [pattern = "^[a-z]+$"]
+            b: string
+            [min_len = 1]
+            c: string[]
+            [format = "url"]
+            d: string
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = OpenAPIGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("openapi.yaml").unwrap();
+        let doc: OpenApiDocument = serde_yaml::from_str(file_content)?;
+
+        let hello = doc.components.as_ref().unwrap().schemas.as_ref().unwrap().get("Hello").unwrap();
+        let props = hello.properties.as_ref().unwrap();
+        assert_eq!(props.get("a").unwrap().minimum, Some(1.0));
+        assert_eq!(props.get("a").unwrap().maximum, Some(100.0));
+        assert_eq!(props.get("b").unwrap().max_length, Some(20));
+        assert_eq!(props.get("b").unwrap().pattern, Some("^[a-z]+$".to_string()));
+        assert_eq!(props.get("c").unwrap().min_items, Some(1));
+        assert_eq!(props.get("c").unwrap().min_length, None);
+        assert_eq!(props.get("d").unwrap().format, Some("uri".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openapi_declared_type() -> Result<()> {
+        let api_file: &'static str = r#"
+        [@openapi.format = "date-time"]
+        type Timestamp
+        struct Hello {
+            a: Timestamp
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = OpenAPIGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("openapi.yaml").unwrap();
+        let doc: OpenApiDocument = serde_yaml::from_str(file_content)?;
+
+        let schemas = doc.components.as_ref().unwrap().schemas.as_ref().unwrap();
+        let timestamp = schemas.get("Timestamp").unwrap();
+        assert_eq!(timestamp.type_, Some("string".to_string()));
+        assert_eq!(timestamp.format, Some("date-time".to_string()));
+        let props = schemas.get("Hello").unwrap().properties.as_ref().unwrap();
+        assert_eq!(props.get("a").unwrap().ref_, Some("#/components/schemas/Timestamp".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openapi_scalar_types() -> Result<()> {
+        let api_file: &'static str = r#"
+        struct Hello {
+            a: i64
+            b: datetime
+            c: bytes
+            d: any
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = OpenAPIGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("openapi.yaml").unwrap();
+        let doc: OpenApiDocument = serde_yaml::from_str(file_content)?;
+
+        let hello = doc.components.as_ref().unwrap().schemas.as_ref().unwrap().get("Hello").unwrap();
+        let props = hello.properties.as_ref().unwrap();
+        assert_eq!(props.get("a").unwrap().type_, Some("integer".to_string()));
+        assert_eq!(props.get("a").unwrap().format, Some("int64".to_string()));
+        assert_eq!(props.get("b").unwrap().format, Some("date-time".to_string()));
+        assert_eq!(props.get("c").unwrap().format, Some("byte".to_string()));
+        assert_eq!(props.get("d").unwrap().type_, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openapi_inheritance() -> Result<()> {
+        let api_file: &'static str = r#"
+        struct User {
+            name: string
+        }
+        struct Timestamps {
+            created_at: string
+        }
+        struct Admin : User {
+            ...Timestamps
+            level: int
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = OpenAPIGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("openapi.yaml").unwrap();
+        let doc: OpenApiDocument = serde_yaml::from_str(file_content)?;
+
+        let admin = doc.components.as_ref().unwrap().schemas.as_ref().unwrap().get("Admin").unwrap();
+        let all_of = admin.all_of.as_ref().unwrap();
+        assert_eq!(all_of.len(), 2);
+        assert_eq!(all_of[0].ref_, Some("#/components/schemas/User".to_string()));
+        let own_props = all_of[1].properties.as_ref().unwrap();
+        assert!(own_props.contains_key("level"));
+        assert!(own_props.contains_key("created_at"));
+        assert!(!own_props.contains_key("name"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openapi_declared_errors() -> Result<()> {
+        let api_file: &'static str = r#"
+        [rest.status = 404]
+        [description = "The todo does not exist"]
+        error NotFound
+        [rest.status = 409]
+        error Conflict
+        [rest.status = 409]
+        error Duplicated
+
+        usecase todo {
+            [rest.method = "post"]
+            [rest.path = "todo"]
+            create_todo {
+                title: string
+            } errors(NotFound, Conflict, Duplicated)
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = OpenAPIGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("openapi.yaml").unwrap();
+        let doc: OpenApiDocument = serde_yaml::from_str(file_content)?;
+
+        let responses = &doc.paths.get("/todo").unwrap().post.as_ref().unwrap().responses.responses;
+        assert_eq!(responses.get("404").unwrap().description, "The todo does not exist");
+        assert_eq!(responses.get("409").unwrap().description, "Conflict, Duplicated");
+        assert!(responses.contains_key("200"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openapi_stream() -> Result<()> {
+        let api_file: &'static str = r#"
+        usecase todo {
+            [rest.method = "get"]
+            [rest.path = "todo"]
+            watch_todos {} -> stream {
+                title: string
+            }
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = OpenAPIGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("openapi.yaml").unwrap();
+        let doc: OpenApiDocument = serde_yaml::from_str(file_content)?;
+
+        let responses = &doc.paths.get("/todo").unwrap().get.as_ref().unwrap().responses.responses;
+        let content = responses.get("200").unwrap().content.as_ref().unwrap();
+        assert!(content.contains_key("text/event-stream"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openapi_namespaces() -> Result<()> {
+        let api_file: &'static str = r#"
+        struct Item {
+            id: string
+        }
+        namespace billing {
+            struct Item {
+                price: int
+            }
+            struct Invoice {
+                item: Item
+                items: map<string, Item>
+            }
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = OpenAPIGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("openapi.yaml").unwrap();
+        let doc: OpenApiDocument = serde_yaml::from_str(file_content)?;
+
+        let schemas = doc.components.as_ref().unwrap().schemas.as_ref().unwrap();
+        assert!(schemas.contains_key("Item"));
+        assert!(schemas.contains_key("BillingItem"));
+        let props = schemas.get("BillingInvoice").unwrap().properties.as_ref().unwrap();
+        assert_eq!(props.get("item").unwrap().ref_, Some("#/components/schemas/BillingItem".to_string()));
+        let items = props.get("items").unwrap().additional_properties.as_ref().unwrap();
+        assert_eq!(items.ref_, Some("#/components/schemas/BillingItem".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_openapi_type_options() -> Result<()> {
+        let api_file: &'static str = r#"
+        [description = "A todo item"]
+        struct Todo {
+            title: string
+            secret: Secret
+            secrets: Secret[]
+        }
+
+        [openapi.exclude]
+        struct Secret {
+            key: string
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = OpenAPIGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("openapi.yaml").unwrap();
+        let doc: OpenApiDocument = serde_yaml::from_str(file_content)?;
+
+        let schemas = doc.components.as_ref().unwrap().schemas.as_ref().unwrap();
+        assert!(!schemas.contains_key("Secret"));
+        let todo = schemas.get("Todo").unwrap();
+        assert_eq!(todo.description.as_deref(), Some("A todo item"));
+        let props = todo.properties.as_ref().unwrap();
+        assert_eq!(props.len(), 1);
+        assert!(props.contains_key("title"));
+
+        Ok(())
+    }
+
+}
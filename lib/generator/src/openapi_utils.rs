@@ -112,6 +112,9 @@ pub struct OperationObject {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -123,8 +126,11 @@ pub struct ParameterObject {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     pub required: bool,
-    pub schema: SchemaObject
-    // Other fields like schema, allowEmptyValue, deprecated, etc. can be added here
+    pub schema: SchemaObject,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<bool>,
+    // Other fields like allowEmptyValue, etc. can be added here
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -182,13 +188,13 @@ pub struct SchemaObject {
     #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
     pub enum_: Option<Vec<String>>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "allOf", skip_serializing_if = "Option::is_none")]
     pub all_of: Option<Vec<SchemaObject>>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "oneOf", skip_serializing_if = "Option::is_none")]
     pub one_of: Option<Vec<SchemaObject>>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "anyOf", skip_serializing_if = "Option::is_none")]
     pub any_of: Option<Vec<SchemaObject>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -200,11 +206,41 @@ pub struct SchemaObject {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub default: Option<serde_json::Value>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<f64>,
+
+    #[serde(rename = "minLength", skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<u64>,
+
+    #[serde(rename = "maxLength", skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<u64>,
+
+    #[serde(rename = "minItems", skip_serializing_if = "Option::is_none")]
+    pub min_items: Option<u64>,
+
+    #[serde(rename = "maxItems", skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+
     #[serde(rename = "$ref", skip_serializing_if = "Option::is_none")]
     pub ref_: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub nullable: Option<bool>
+    pub nullable: Option<bool>,
+
+    #[serde(rename = "readOnly", skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+
+    #[serde(rename = "writeOnly", skip_serializing_if = "Option::is_none")]
+    pub write_only: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<bool>,
 }
 
 impl Default for SchemaObject {
@@ -222,8 +258,18 @@ impl Default for SchemaObject {
             not: Default::default(),
             description: Default::default(),
             default: Default::default(),
+            minimum: Default::default(),
+            maximum: Default::default(),
+            min_length: Default::default(),
+            max_length: Default::default(),
+            min_items: Default::default(),
+            max_items: Default::default(),
+            pattern: Default::default(),
             ref_: Default::default(),
             nullable: Default::default(),
+            read_only: Default::default(),
+            write_only: Default::default(),
+            deprecated: Default::default(),
             additional_properties: Default::default()
         }
     }
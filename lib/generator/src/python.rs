@@ -1,23 +1,29 @@
 use std::{any::type_name, cell::RefCell, collections::HashSet, fmt::format, path::PathBuf};
 
 use convert_case::{Case, Casing};
-use cronus_spec::{RawSchema, PythonGeneratorOption};
+use cronus_spec::{RawError, RawSchema, PythonGeneratorOption};
 
 use crate::{
-    utils::{self, get_path_from_optional_parent, get_request_name, get_response_name, get_schema_by_name, get_usecase_name, spec_ty_to_py_builtin_ty, spec_ty_to_rust_builtin_ty}, Ctxt, Generator
+    utils::{self, get_path_from_optional_parent, get_request_name, get_response_name, get_schema_by_name, get_schema_parents, get_schema_properties, get_usecase_name, spec_ty_to_py_builtin_ty, spec_ty_to_rust_builtin_ty}, Ctxt, Generator
 };
 use tracing::{self, debug, span, Level};
 use anyhow::{Ok, Result};
 
 pub struct PythonGenerator {
-    generated_tys: RefCell<HashSet<String>>
+    generated_tys: RefCell<HashSet<String>>,
+    /// classes whose definition is in progress, a reference to one of them is a forward reference
+    defining_tys: RefCell<HashSet<String>>,
+    /// classes waiting for a parent in progress, which refers to them
+    deferred_tys: RefCell<Vec<(String, RawSchema)>>
 }
 
 
 impl PythonGenerator {
     pub fn new() -> Self {
         Self {
-            generated_tys: Default::default()
+            generated_tys: Default::default(),
+            defining_tys: Default::default(),
+            deferred_tys: Default::default()
         }
     }
 }
@@ -31,13 +37,21 @@ impl Generator for PythonGenerator {
         
         let common_imports = vec![
             "from abc import ABC, abstractmethod", 
+            "from collections.abc import AsyncIterator, Iterator",
             "from dataclasses import dataclass", 
             "from typing import Optional",
             "from enum import Enum"
         ];
-        let common_imports_str = common_imports.join("\n") + "\n";
+        let common_imports_str = common_imports.into_iter().chain(utils::PY_SCALAR_IMPORTS).collect::<Vec<_>>().join("\n") + "\n";
         ctx.append_file(self.name(), &self.dst(ctx), &common_imports_str);
 
+        // imports of the declared types
+        let alias_imports: Vec<String> = utils::get_alias_imports(ctx, &utils::get_spec_tys(ctx), |alias| alias.python.as_ref())
+            .iter()
+            .map(|import| format!("{}\n", import))
+            .collect();
+        ctx.append_file(self.name(), &self.dst(ctx), &alias_imports.concat());
+
         // custom uses
         match self.get_gen_option(ctx) {
             Some(rust_gen) => {
@@ -60,7 +74,14 @@ impl Generator for PythonGenerator {
     }
 
     fn generate_schema(&self, ctx: &Ctxt, schema_name:&str, schema: &RawSchema) -> Result<()> {
-        self.generate_struct(ctx, schema, Some(schema_name.to_owned()), None);
+        self.generate_struct(ctx, schema, Some(schema_name.to_owned()), None)?;
+        Ok(())
+    }
+
+    /// Generate the exception of a declared error, raised by the usecase implementations
+    fn generate_error(&self, ctx: &Ctxt, error_name: &str, _error: &RawError) -> Result<()> {
+        let result = format!("class {}(Exception):\n  pass\n\n", error_name.to_case(Case::UpperCamel));
+        ctx.append_file(self.name(), &self.dst(ctx), &result);
         Ok(())
     }
 
@@ -95,6 +116,9 @@ impl Generator for PythonGenerator {
             _ => {}
         }
         result += &format!("class {}(ABC):\n", trait_name);
+        if let Some(deprecated) = utils::get_usecase_deprecated(usecase) {
+            result += &utils::py_docstring(&utils::deprecation_text(deprecated), "  ");
+        }
         for (method_name, method) in &usecase.methods {
             result += "  @abstractmethod\n";
             // handle async fn
@@ -121,8 +145,14 @@ impl Generator for PythonGenerator {
             result += "(self";
 
             if let Some(req) = &method.req {
-                let request_ty = get_request_name(ctx, method_name);
-                self.generate_struct(ctx, &req, Some(request_ty.clone()), None);
+                // a named type is used as is, otherwise the request class is generated
+                let request_ty = if utils::is_named_ty(req) {
+                    self.generate_struct(ctx, req, None, None)?
+                } else {
+                    let request_ty = get_request_name(ctx, method_name);
+                    self.generate_struct(ctx, req, Some(request_ty.clone()), None)?;
+                    request_ty
+                };
                 result += ", request: ";
                 result += &request_ty;
             }
@@ -131,13 +161,24 @@ impl Generator for PythonGenerator {
             let mut result_type: String = "None".to_string();
 
             if let Some(res) = &method.res {
-                let response_ty = get_response_name(ctx, method_name);
-                self.generate_struct(ctx, &res, Some(response_ty.clone()), None);
-                result_type = response_ty;
+                result_type = if utils::is_named_ty(res) {
+                    self.generate_struct(ctx, res, None, None)?
+                } else {
+                    let response_ty = get_response_name(ctx, method_name);
+                    self.generate_struct(ctx, res, Some(response_ty.clone()), None)?;
+                    response_ty
+                };
+            }
+            // a stream method is a generator of its responses
+            if utils::is_stream_method(method) {
+                result_type = format!("{}[{}]", if has_async { "AsyncIterator" } else { "Iterator" }, result_type);
             }
 
             result += &format!(" -> {}", result_type);
             result += ":\n";
+            if let Some(deprecated) = utils::get_method_deprecated(method) {
+                result += &utils::py_docstring(&utils::deprecation_text(deprecated), "    ");
+            }
             result += "    pass\n";
         }
 
@@ -166,18 +207,24 @@ impl PythonGenerator {
         schema: &RawSchema,
         override_ty: Option<String>,
         root_schema_ty: Option<String>
-    ) -> String {
+    ) -> Result<String> {
         let type_name: String;
 
         // find out the correct type name
         if let Some(ty) = &override_ty {
-            type_name = ty.to_case(Case::UpperCamel);
+            type_name = utils::get_prefixed_ty_name(ty);
         }
         else if schema.items.is_some() {
 
-            type_name = self.generate_struct(ctx, schema.items.as_ref().unwrap(), None, root_schema_ty.clone());
+            type_name = self.generate_struct(ctx, schema.items.as_ref().unwrap(), None, root_schema_ty.clone())?;
 
-            return format!("list[{}]", type_name).to_owned()
+            return Ok(format!("list[{}]", type_name))
+        }
+        else if let Some(values) = &schema.values {
+            let keys = RawSchema::new(schema.def_loc.clone(), utils::get_map_keys(schema).to_string());
+            let key_ty = self.generate_struct(ctx, &keys, None, root_schema_ty.clone())?;
+            let value_ty = self.generate_struct(ctx, values, None, root_schema_ty.clone())?;
+            return Ok(format!("dict[{},{}]", key_ty, value_ty))
         }
         else {
             type_name = schema.ty.as_ref().unwrap().clone();
@@ -191,17 +238,16 @@ impl PythonGenerator {
         let _enter = span.enter();
 
         // if type name belongs to built-in type, return directly
-        if let Some(ty) = spec_ty_to_py_builtin_ty(&type_name) {
-            return ty;
+        if let Some(ty) = spec_ty_to_py_builtin_ty(ctx, &type_name) {
+            return Ok(ty);
         }
 
         if self.generated_tys.borrow().contains(&type_name) {
-            if let Some(root_schema_ty) = root_schema_ty  {
-                if  root_schema_ty == type_name {
-                    return format!("'{type_name}'")
-                }
+            // the class is not defined yet, it refers to itself directly or through other classes
+            if self.defining_tys.borrow().contains(&type_name) {
+                return Ok(format!("'{type_name}'"))
             }
-            return type_name;
+            return Ok(type_name);
         }
 
 
@@ -210,7 +256,7 @@ impl PythonGenerator {
         if let Some(ref_schema) = get_schema_by_name(&ctx, &type_name) {
             // check whether schema is a type referencing another user type
             if schema.properties.is_none() && schema.enum_items.is_none() && schema.items.is_none() {
-                return self.generate_struct(ctx, ref_schema, Some(type_name.to_string()), Some(type_name.to_string()));
+                return self.generate_struct(ctx, ref_schema, Some(type_name.to_string()), root_schema_ty);
             }
         }
 
@@ -219,56 +265,111 @@ impl PythonGenerator {
         // if it is a enum type, generate the enum definition
         if let Some(enum_items) = &schema.enum_items {
             let mut enum_def = format!("class {}(str, Enum):\n", type_name);
+            if let Some(doc) = py_doc(schema) {
+                enum_def += &utils::py_docstring(&doc, "  ");
+            }
             for item in enum_items {
+                if let Some(deprecated) = utils::get_enum_item_deprecated(item) {
+                    enum_def += &utils::description_to_comment(&utils::deprecation_text(deprecated), "  # ");
+                }
                 enum_def += &format!("  {} = '{}'\n", item.name.to_case(Case::UpperSnake), item.name.to_case(Case::UpperSnake));
             }
             ctx.append_file(self.name(), &self.dst(ctx), &enum_def);
-            return type_name;
+            return Ok(type_name);
+        }
+
+        // the parents have to be defined before the inheriting class, a parent in progress refers to it
+        let defining_parent = get_schema_parents(schema).into_iter()
+            .any(|(_, parent)| self.defining_tys.borrow().contains(&utils::get_prefixed_ty_name(&parent)));
+        self.defining_tys.borrow_mut().insert(type_name.clone());
+        if defining_parent {
+            self.deferred_tys.borrow_mut().push((type_name.clone(), schema.clone()));
+            return Ok(format!("'{type_name}'"))
+        }
+        let mut parent_tys = Vec::new();
+        for (_, parent) in get_schema_parents(schema) {
+            parent_tys.push(self.generate_struct(ctx, &RawSchema::new(schema.def_loc.clone(), parent), None, None)?);
         }
 
-        let mut result = format!("@dataclass\nclass {}:\n",  type_name).to_string();
+        let mut result = if parent_tys.is_empty() {
+            format!("@dataclass\nclass {}:\n",  type_name)
+        } else {
+            // keyword only, otherwise required fields cannot follow the optional fields of the parents
+            format!("@dataclass(kw_only=True)\nclass {}({}):\n",  type_name, parent_tys.join(", "))
+        };
+        if let Some(doc) = py_doc(schema) {
+            result += &utils::py_docstring(&doc, "  ");
+        }
 
         let mut required_fields =  Vec::new();
         let mut optional_fields =  Vec::new(); 
-        if let Some(properties) = &schema.properties {
-            for (prop_name, prop_schema) in properties {
-                let snaked_prop_name = prop_name.to_case(Case::Snake);
-                let mut field = String::new();
-                field += "  ";
-                field += &snaked_prop_name;
-                field += ": ";
-
-                let optional = match prop_schema.required {
-                    Some(req) => !req,
-                    None => false
-                };
-
-                let prop_ty = self.generate_struct(ctx, &prop_schema, None, Some(type_name.clone()));
+        let properties = get_schema_properties(ctx, schema)?;
+        utils::check_ident_collisions(&type_name, properties.keys(), utils::py_ident)?;
+        for (prop_name, prop_schema) in &properties {
+            let mut field = String::new();
+            if let Some(deprecated) = utils::get_schema_deprecated(prop_schema) {
+                field += &utils::description_to_comment(&utils::deprecation_text(deprecated), "  # ");
+            }
+            field += "  ";
+            field += &utils::py_ident(prop_name);
+            field += ": ";
+
+            let optional = match prop_schema.required {
+                Some(req) => !req,
+                None => false
+            };
+
+            let mut prop_ty = self.generate_struct(ctx, prop_schema, None, Some(type_name.clone()))?;
+            let default = utils::get_default_value(ctx, prop_schema)?;
+            // a nullable property without a default value still has to be given
+            let nullable = prop_schema.nullable.unwrap_or(false);
+            if nullable || (optional && default.is_none()) {
+                prop_ty = format!("Optional[{}]", prop_ty);
+            }
 
-                if optional {
-                    field += &format!("Optional[{}] = None", prop_ty);
+            if let Some(default) = &default {
+                field += &format!("{} = {}", prop_ty, utils::default_value_to_py_literal(default));
+            } else if optional {
+                field += &format!("{} = None", prop_ty);
 
-                } else {
-                    field += &prop_ty;
-                }
-                field += "\n";
+            } else {
+                field += &prop_ty;
+            }
+            field += "\n";
 
-                if optional {
-                    optional_fields.push(field);
-                } else {
-                    required_fields.push(field);
-                }
+            // fields with a default value have to follow the required ones
+            if optional || default.is_some() {
+                optional_fields.push(field);
+            } else {
+                required_fields.push(field);
             }
         }
+        if required_fields.is_empty() && optional_fields.is_empty() {
+            result += "  pass\n";
+        }
         result += required_fields.join("").as_str();
         result += optional_fields.join("").as_str();
+        self.defining_tys.borrow_mut().remove(&type_name);
 
 
         ctx.append_file(self.name(), &self.dst(ctx), &result);
 
+        // the classes whose parents are all defined now
+        let ready: Vec<(String, RawSchema)> = {
+            let defining_tys = self.defining_tys.borrow();
+            let (ready, deferred) = self.deferred_tys.take().into_iter().partition(|(_, deferred)| {
+                get_schema_parents(deferred).into_iter().all(|(_, parent)| !defining_tys.contains(&utils::get_prefixed_ty_name(&parent)))
+            });
+            *self.deferred_tys.borrow_mut() = deferred;
+            ready
+        };
+        for (deferred_name, deferred) in ready {
+            self.generated_tys.borrow_mut().remove(&deferred_name);
+            self.defining_tys.borrow_mut().remove(&deferred_name);
+            self.generate_struct(ctx, &deferred, Some(deferred_name), None)?;
+        }
 
-
-        type_name
+        Ok(type_name)
     }
 
     fn get_gen_option<'a>(&self, ctx: &'a Ctxt) -> Option<&'a PythonGeneratorOption> {
@@ -304,9 +405,18 @@ impl PythonGenerator {
 }
 
 
+/// The doc of a class, its description followed by its deprecation
+fn py_doc(schema: &RawSchema) -> Option<String> {
+    let doc: Vec<String> = utils::get_schema_description(schema).map(str::to_string).into_iter()
+        .chain(utils::get_schema_deprecated(schema).map(utils::deprecation_text))
+        .collect();
+    if doc.is_empty() { None } else { Some(doc.join("\n\n")) }
+}
+
+
 #[cfg(test)]
 mod test {
-    use std::path::PathBuf;
+    use std::{path::PathBuf, process::Command};
 
     use cronus_parser::api_parse;
 
@@ -335,6 +445,162 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn py_scalar_types() -> Result<()>{
+        let api_file: &'static str = r#"
+        struct hello {
+            a: i64
+            b: f64
+            c: datetime
+            d: uuid
+            e: map<string,decimal>
+            f: any
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = PythonGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("python");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("generated.py").unwrap();
+
+        assert!(file_content.contains("from datetime import date, datetime, timedelta"));
+        assert!(file_content.contains("a: int"));
+        assert!(file_content.contains("b: float"));
+        assert!(file_content.contains("c: datetime"));
+        assert!(file_content.contains("d: UUID"));
+        assert!(file_content.contains("e: dict[str,Decimal]"));
+        assert!(file_content.contains("f: Any"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn py_deprecated() -> Result<()>{
+        let api_file: &'static str = r#"
+        [description = "A todo"]
+        [deprecated = "use TodoV2"]
+        struct Todo {
+            [deprecated]
+            tag: string
+        }
+        enum Status {
+            Open
+            [deprecated = "use Open"]
+            Active
+        }
+        usecase Todo {
+            [deprecated = "use listTodosV2"]
+            listTodos -> Todo[]
+
+            [deprecated = "use listTodosV2\nor \"searchTodos\""]
+            countTodos -> int
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = PythonGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("python");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("generated.py").unwrap();
+
+        assert!(file_content.contains("class Todo:\n  \"\"\"\n  A todo\n\n  Deprecated: use TodoV2\n  \"\"\"\n"), "{}", file_content);
+        assert!(file_content.contains("  # Deprecated: no longer supported.\n  tag: str\n"), "{}", file_content);
+        assert!(file_content.contains("  # Deprecated: use Open\n  ACTIVE = 'ACTIVE'\n"), "{}", file_content);
+        assert!(file_content.contains("-> list[Todo]:\n    \"\"\"Deprecated: use listTodosV2\"\"\"\n    pass\n"), "{}", file_content);
+        assert!(file_content.contains("-> int:\n    \"\"\"\n    Deprecated: use listTodosV2\n    or \\\"searchTodos\\\"\n    \"\"\"\n    pass\n"), "{}", file_content);
+        assert_importable(file_content)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn py_recursive_struct() -> Result<()>{
+        let api_file: &'static str = r#"
+        struct Node {
+            children: Node[]
+        }
+        struct A {
+            b: B
+        }
+        struct B {
+            a?: A
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = PythonGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("python");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("generated.py").unwrap();
+
+        assert!(file_content.contains("  children: list['Node']\n"), "{}", file_content);
+        // the class defined first refers to the other one by a forward reference
+        assert!(file_content.contains("  b: 'B'\n") != file_content.contains("  a: Optional['A'] = None\n"), "{}", file_content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn py_default_value() -> Result<()>{
+        let api_file: &'static str = r#"
+        enum Status {
+            Active
+        }
+        struct hello {
+            a: int = 10
+            b?: Status = Active
+            c: string = "say \"hi\"\n"
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = PythonGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("python");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("generated.py").unwrap();
+
+        assert!(file_content.find("a: int = 10").is_some());
+        assert!(file_content.find("b: Status = Status.ACTIVE").is_some());
+        assert!(file_content.contains(r#"c: str = "say \"hi\"\n""#), "{}", file_content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn py_inherited_struct() -> Result<()>{
+        let api_file: &'static str = r#"
+        struct User {
+            name: string
+        }
+        struct Admin : User {
+            level: int
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = PythonGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("python");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("generated.py").unwrap();
+
+        assert!(file_content.find("class Admin(User):").is_some());
+        // the parent is defined before the inheriting class
+        assert!(file_content.find("class User:").unwrap() < file_content.find("class Admin(User):").unwrap());
+
+        Ok(())
+    }
+
     #[test]
     fn py_async_def() -> Result<()>{
         let api_file: &'static str = r#"
@@ -356,5 +622,52 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn py_forward_refs() -> Result<()>{
+        let api_file: &'static str = r#"
+        struct A {
+            m: map<string, A>
+            n?: map<Status, A[]>
+        }
+        enum Status {
+            Active
+        }
+        struct Base {
+            me: Self1
+        }
+        struct Self1 : Base {}
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = PythonGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("python");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("generated.py").unwrap();
+
+        assert!(file_content.contains("  m: dict[str,'A']\n"), "{}", file_content);
+        assert!(file_content.contains("  me: 'Self1'\n"), "{}", file_content);
+        assert!(file_content.contains("class Self1(Base):"), "{}", file_content);
+        assert!(file_content.find("class Base:").unwrap() < file_content.find("class Self1(Base):").unwrap());
 
-}
\ No newline at end of file
+        assert_importable(file_content)
+    }
+
+    /// Check that the generated module can be imported
+    fn assert_importable(file_content: &str) -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("cronus_py_{}_{}", std::thread::current().name().unwrap_or("test").replace("::", "_"), std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("generated.py"), file_content)?;
+        let output = Command::new("python3")
+            .args(["-c", "import generated"])
+            .current_dir(&dir)
+            .output();
+        std::fs::remove_dir_all(&dir)?;
+        let output = output?;
+        assert!(output.status.success(), "{}\n{}", String::from_utf8_lossy(&output.stderr), file_content);
+        Ok(())
+    }
+
+
+}
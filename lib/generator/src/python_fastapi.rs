@@ -1,14 +1,15 @@
 use anyhow::bail;
 use convert_case::{Case, Casing};
 use cronus_spec::{
-    PythonFastApiGeneratorOption, RawSchema, RawUsecaseMethod, RawUsecaseMethodRestOption,
+    PythonFastApiGeneratorOption, RawSchema, RawSchemaPropertyFormat, RawUsecaseMethod,
+    RawUsecaseMethodRestOption,
 };
 use std::{any::type_name, cell::RefCell, collections::HashSet, fmt::format, path::PathBuf};
 
 use crate::{
     utils::{
         self, get_path_from_optional_parent, get_request_name, get_response_name,
-        get_schema_by_name, get_usecase_name, spec_ty_to_py_builtin_ty, spec_ty_to_rust_builtin_ty,
+        get_schema_by_name, get_schema_parents, get_schema_properties, get_usecase_name, spec_ty_to_py_builtin_ty, spec_ty_to_rust_builtin_ty,
     },
     Ctxt, Generator,
 };
@@ -19,6 +20,10 @@ pub struct PythonFastApiGenerator {
     generated_tys: RefCell<HashSet<String>>,
     // imported types from usecase, to avoid duplicate imports
     imported_tys: RefCell<HashSet<String>>,
+    // models whose definition is in progress, a reference to one of them is a forward reference
+    defining_tys: RefCell<HashSet<String>>,
+    // models waiting for a parent in progress, which refers to them
+    deferred_tys: RefCell<Vec<(String, RawSchema)>>,
     // This keeps track of the get_ctx's context fields and types used in the generated code
     // used to generate ctx dataclass
     used_ctx_fields: RefCell<HashSet<(String, String)>>,
@@ -32,6 +37,8 @@ impl PythonFastApiGenerator {
         Self {
             generated_tys: Default::default(),
             imported_tys: Default::default(),
+            defining_tys: Default::default(),
+            deferred_tys: Default::default(),
             used_ctx_fields: RefCell::new(HashSet::new()),
             mentioned_routers: RefCell::new(HashSet::new()),
         }
@@ -58,8 +65,9 @@ impl Generator for PythonFastApiGenerator {
         let get_ctx_import = &format!("from {get_ctx_from} import get_ctx");
         let mut common_imports = vec![
             get_ctx_import,
-            "from pydantic import BaseModel",
-            "from fastapi import FastAPI, APIRouter, Depends, Request, status",
+            "from pydantic import AnyUrl, Base64Bytes, BaseModel, ConfigDict, EmailStr, Field",
+            "from fastapi import FastAPI, APIRouter, Depends, HTTPException, Path, Query, Request, status",
+            "from fastapi.responses import StreamingResponse",
             "from typing import Optional",
             "from dataclasses import dataclass", 
         ];
@@ -76,6 +84,11 @@ impl Generator for PythonFastApiGenerator {
             None => {}
         }
 
+        common_imports.extend(utils::PY_SCALAR_IMPORTS);
+
+        // imports of the declared types
+        let alias_imports = utils::get_alias_imports(ctx, &utils::get_spec_tys(ctx), |alias| alias.python.as_ref());
+        common_imports.extend(alias_imports.iter().map(String::as_str));
 
         let common_imports_str = common_imports.join("\n") + "\n";
         ctx.append_file(self.name(), &self.dst(ctx), &common_imports_str);
@@ -83,7 +96,7 @@ impl Generator for PythonFastApiGenerator {
     }
 
     fn generate_schema(&self, ctx: &Ctxt, schema_name: &str, schema: &RawSchema) -> Result<()> {
-        self.generate_struct(ctx, schema, Some(schema_name.to_owned()), None);
+        self.generate_struct(ctx, schema, Some(schema_name.to_owned()), None)?;
         Ok(())
     }
     
@@ -168,6 +181,10 @@ def include_routers(app: FastAPI) -> FastAPI:
             None => {
             }
         };
+        // the routes of a deprecated usecase are deprecated in the openapi of fastapi
+        if utils::get_usecase_deprecated(usecase).is_some() {
+            router_args.push("deprecated=True".to_string());
+        }
 
         let router_args_str = if router_args.len() != 0 {
             router_args.join(", ")
@@ -214,7 +231,11 @@ def include_routers(app: FastAPI) -> FastAPI:
             if !translated_path.is_empty() && !translated_path.starts_with("/") {
                 translated_path = format!("/{translated_path}");
             }
-            result += &format!("@{}.{}('{}')\n", router_var, rest.method, translated_path);
+            if utils::get_method_deprecated(method).is_some() {
+                result += &format!("@{}.{}('{}', deprecated=True)\n", router_var, rest.method, translated_path);
+            } else {
+                result += &format!("@{}.{}('{}')\n", router_var, rest.method, translated_path);
+            }
             if has_async {
                 result += "async ";
             }
@@ -222,6 +243,10 @@ def include_routers(app: FastAPI) -> FastAPI:
             result += &method_name.to_case(Case::Snake);
             result += "(";
             
+            // the request with the fields of its spread types copied in
+            let method = &utils::resolve_method_spreads(ctx, method)?;
+            utils::check_readonly_params(method)?;
+
             let mut arg_strs: Vec<String> = vec![];
             let mut default_arg_strs: Vec<String> = vec![];
 
@@ -262,8 +287,17 @@ def include_routers(app: FastAPI) -> FastAPI:
 
             // prepare body if http method is not get
 
+            // a named request class (createTodo NewTodo) is created from the body and the parameters as is
+            let request_ty = match &method.req {
+                Some(req) if utils::is_named_ty(req) => {
+                    if !utils::is_struct_ty(ctx, req) {
+                        bail!("the input of method '{}' has to be a struct to be bound from the request", method_name)
+                    }
+                    utils::get_prefixed_ty_name(req.ty.as_ref().unwrap())
+                },
+                _ => get_request_name(ctx, method_name),
+            };
             if let Some(req) = &method.req {
-                let request_ty = get_request_name(ctx, method_name);
                 py_imports.insert(request_ty.clone());
 
                 if rest.method != "get" {
@@ -276,63 +310,105 @@ def include_routers(app: FastAPI) -> FastAPI:
                             cloned_req.properties.as_mut().unwrap().remove(param);
                         }
                     }
+                    // a readonly property is not read from the body
+                    cloned_req.properties.as_mut().unwrap().retain(|_, prop| !utils::is_readonly(prop));
 
                     let mut need_generate_body = false;
+                    let mut has_excluded = false;
 
                     for (prop_name, prop_schema) in cloned_req.properties.as_ref().unwrap() {
                         if prop_schema.option.as_ref()
                             .and_then(|o| o.python_fastapi.as_ref().and_then(| opt| opt.exclude))
                             .unwrap_or(false) {
                             // skip properties if exclude is set
+                            has_excluded = true;
                             continue;
                         }
                         need_generate_body = true;
-                        break
                     }
-                    
-                    if need_generate_body {
+
+                    // the named input is the body as a whole, its model is bound as is
+                    let is_whole_body = utils::is_named_ty(req) && !has_excluded
+                        && cloned_req.properties.as_ref().unwrap().len() == req.properties.as_ref().unwrap().len();
+
+                    if need_generate_body && is_whole_body {
+                        let body_ty = self.generate_struct(ctx, req, None, None)?;
+                        arg_strs.push(format!("body: {}", body_ty));
+                    } else if need_generate_body {
                        
                         let body_ty = format!("{}Body", method_name.to_case(Case::UpperCamel));
                         if cloned_req.properties.as_ref().unwrap().len() != 0 {
-                            self.generate_struct(ctx, &cloned_req, Some(body_ty.clone()), None);
+                            self.generate_struct(ctx, &cloned_req, Some(body_ty.clone()), None)?;
                             arg_strs.push(format!("body: {}", body_ty));
 
                         }
                     }
                 } else {
-                    req.properties.as_ref().unwrap().into_iter().for_each(|(prop_name, prop_schema)| {
+                    for (prop_name, prop_schema) in req.properties.as_ref().unwrap() {
                         if prop_schema.option.as_ref()
                         .and_then(|o| o.python_fastapi.as_ref().and_then(| opt| opt.exclude))
                         .unwrap_or(false) {
                     // skip properties if exclude is set
-                    return;
+                    continue;
                         }
 
                         if let Some(path_params) = path_params.as_ref() {
                             if path_params.contains(prop_name) {
-                                return;
+                                continue;
                             }
                             
 
                         }
 
-                        let ty = self.generate_struct(ctx, prop_schema, None, None);
-                        if prop_schema.required.unwrap_or(false) {
-                            arg_strs.push(format!("{}: {}", prop_name.to_case(Case::Snake), ty));
+                        let ty = self.generate_struct(ctx, prop_schema, None, None)?;
+                        let mut constraints = get_field_constraints(prop_schema);
+                        // the query parameter is named as the property on the wire
+                        if let Some(alias) = get_field_alias(ctx, prop_name, prop_schema) {
+                            constraints.push(alias);
+                        }
+                        if utils::get_schema_deprecated(prop_schema).is_some() {
+                            constraints.push("deprecated=True".to_string());
+                        }
+                        if let Some(default) = utils::get_default_value(ctx, prop_schema)? {
+                            // absent query parameter falls back to the default value of the property
+                            let default = utils::default_value_to_py_literal(&default);
+                            if constraints.is_empty() {
+                                default_arg_strs.push(format!("{}: {} = {}", utils::py_ident(prop_name), ty, default));
+                            } else {
+                                default_arg_strs.push(format!("{}: {} = Query({}, {})", utils::py_ident(prop_name), ty, default, constraints.join(", ")));
+                            }
+
+                        } else if prop_schema.required.unwrap_or(false) {
+                            if constraints.is_empty() {
+                                arg_strs.push(format!("{}: {}", utils::py_ident(prop_name), ty));
+                            } else {
+                                // fastapi treats a Query without default as required
+                                default_arg_strs.push(format!("{}: {} = Query({})", utils::py_ident(prop_name), ty, constraints.join(", ")));
+                            }
+
+                        } else if constraints.is_empty() {
+                            default_arg_strs.push(format!("{}: Optional[{}] = None", utils::py_ident(prop_name), ty));
 
                         } else {
-                            default_arg_strs.push(format!("{}: Optional[{}] = None", prop_name.to_case(Case::Snake), ty));
+                            default_arg_strs.push(format!("{}: Optional[{}] = Query(None, {})", utils::py_ident(prop_name), ty, constraints.join(", ")));
 
                         }
-                    });
+                    }
                 }
             }
 
             // handle path parameters
-            match get_method_path_names_and_tys(method)? {
+            match get_method_path_names_and_tys(ctx, method)? {
                 Some((props, tys)) => {
                     for (i, prop) in props.iter().enumerate() {
-                        arg_strs.push(format!("{}: {}", prop.to_case(Case::Snake), tys[i]));
+                        let ident = utils::py_ident(prop);
+                        let snaked_prop = prop.to_case(Case::Snake);
+                        if ident != snaked_prop {
+                            // the path has the name of the property, which is a keyword in python
+                            default_arg_strs.push(format!("{}: {} = Path(alias={})", ident, tys[i], utils::string_literal(&snaked_prop)));
+                        } else {
+                            arg_strs.push(format!("{}: {}", ident, tys[i]));
+                        }
                     }
                 }
                 None => {}
@@ -363,11 +439,19 @@ def include_routers(app: FastAPI) -> FastAPI:
             // generate response
             if let Some(res) = &method.res {
                 has_res = true;
-                let response_ty = get_response_name(ctx, method_name);
-                self.generate_struct(ctx, &res, Some(response_ty.clone()), None);
-                result_type = response_ty;
+                result_type = if utils::is_named_ty(res) {
+                    self.generate_struct(ctx, res, None, None)?
+                } else {
+                    let response_ty = get_response_name(ctx, method_name);
+                    self.generate_struct(ctx, res, Some(response_ty.clone()), None)?;
+                    response_ty
+                };
                 
             } 
+            let is_stream = utils::is_stream_method(method);
+            if is_stream {
+                result_type = "StreamingResponse".to_string();
+            }
 
             result += &format!(" -> {}", result_type);
 
@@ -422,26 +506,33 @@ def include_routers(app: FastAPI) -> FastAPI:
                         continue;
                     }
                     let mut field = String::new();
-                    field += &prop_name.to_case(Case::Snake);
+                    field += &utils::py_ident(prop_name);
                     field += "=";
 
+                    // a readonly property is left to the usecase
+                    if utils::is_readonly(prop_schema) {
+                        field += "None";
+                        request_fields.push(field);
+                        continue;
+                    }
+
                     // if property is in body, use body.xxx
                     // otherwise, use xxx directly
                     let mut in_path = false;
                     if let Some(path_params) = path_params.as_ref() {
                         if path_params.contains(prop_name)  {
-                            field += &prop_name.to_case(Case::Snake);
+                            field += &utils::py_ident(prop_name);
                             in_path = true;
                         }
                     }
 
                     if !in_path {
                         if rest.method == "get" {
-                            field += &prop_name.to_case(Case::Snake);
+                            field += &utils::py_ident(prop_name);
                         }
                         else {
                             field += "body.";
-                            field += &prop_name.to_case(Case::Snake);
+                            field += &utils::py_ident(prop_name);
                         }
                     }
 
@@ -455,24 +546,20 @@ def include_routers(app: FastAPI) -> FastAPI:
 
             if request_fields.len() != 0 {
                 result += "  request = ";
-                result += &get_request_name(ctx, method_name);
+                result += &request_ty;
                 result += "(";
                 result += &request_fields.join(",\n    ");
                 result += ")\n";
             }
 
+            let mut call_stmt = String::new();
             if has_res {
-                result += "  return"
+                call_stmt += "return ";
             }
 
             // assign request body
             if has_async {
-                if has_res {
-                    result += " await ";
-                } else {
-                    result += "  await ";
-
-                }
+                call_stmt += "await ";
             }
             let call_usecase = format!("ctx.{}.{}({})", trait_name, method_name.to_case(Case::Snake), if request_fields.len() != 0 {
                 "request"
@@ -482,8 +569,38 @@ def include_routers(app: FastAPI) -> FastAPI:
             let usecase_ty = get_usecase_name(ctx, &trait_name);
             py_imports.insert(usecase_ty.clone());
             self.used_ctx_fields.borrow_mut().insert((trait_name.to_string(), usecase_ty));
-            result += &call_usecase;
-            result += "\n";
+            call_stmt += &call_usecase;
+
+            // the declared errors are responded with their status codes
+            let errors = utils::get_method_errors(ctx, method)?;
+            if is_stream {
+                // the responses are sent as server-sent events, a declared error as the event named error
+                let (def, for_loop) = if has_async { ("async def", "async for") } else { ("def", "for") };
+                let mut indent = "    ";
+                result += &format!("  {} events():\n", def);
+                if !errors.is_empty() {
+                    result += "    try:\n";
+                    indent = "      ";
+                }
+                result += &format!("{}{} item in {}:\n", indent, for_loop, call_usecase);
+                // the item is sent by the aliases of its fields, as the json responses are
+                result += &format!("{}  yield f\"data: {{item.model_dump_json(by_alias=True)}}\\n\\n\"\n", indent);
+                for (error_name, _) in errors {
+                    let error_ty = error_name.to_case(Case::UpperCamel);
+                    result += &format!("    except {} as err:\n      yield f\"event: error\\ndata: {{err}}\\n\\n\"\n", error_ty);
+                    py_imports.insert(error_ty);
+                }
+                result += "  return StreamingResponse(events(), media_type=\"text/event-stream\")\n";
+            } else if errors.is_empty() {
+                result += &format!("  {}\n", call_stmt);
+            } else {
+                result += &format!("  try:\n    {}\n", call_stmt);
+                for (error_name, error) in errors {
+                    let error_ty = error_name.to_case(Case::UpperCamel);
+                    result += &format!("  except {} as err:\n    raise HTTPException(status_code={}, detail=str(err))\n", error_ty, error.status());
+                    py_imports.insert(error_ty);
+                }
+            }
         }
 
         // handle imports first
@@ -499,8 +616,16 @@ def include_routers(app: FastAPI) -> FastAPI:
                     bail!("python_fastapi usecase_from option is not set");
                 },
             };
-            let imports_str = format!("from {} import {}\n", usecase_from, py_imports.into_iter().collect::<Vec<String>>().join(", "));
-            ctx.append_file(self.name(), &self.dst(ctx), &imports_str);
+            // a model defined in this module is not shadowed by the type of the usecase
+            let generated_tys = self.generated_tys.borrow();
+            let imported_tys = self.imported_tys.borrow();
+            let py_imports: Vec<String> = py_imports.into_iter()
+                .filter(|ty| !generated_tys.contains(ty) || imported_tys.contains(ty))
+                .collect();
+            if !py_imports.is_empty() {
+                let imports_str = format!("from {} import {}\n", usecase_from, py_imports.join(", "));
+                ctx.append_file(self.name(), &self.dst(ctx), &imports_str);
+            }
         }
 
         ctx.append_file(self.name(), &self.dst(ctx), &result);
@@ -542,7 +667,41 @@ fn turn_cronus_path_to_fastapi_path(path: &str, var_case: Case) -> String {
     result
 }
 
+/// Get the pydantic alias of a property named differently on the wire, e.g. alias="createdAt" or alias="from"
+fn get_field_alias(ctx: &Ctxt, prop_name: &str, schema: &RawSchema) -> Option<String> {
+    let wire_name = utils::get_wire_name(ctx, prop_name, schema).unwrap_or_else(|| prop_name.to_case(Case::Snake));
+    if wire_name != utils::py_ident(prop_name) {
+        Some(format!("alias={}", utils::string_literal(&wire_name)))
+    } else {
+        None
+    }
+}
+
+/// Get the pydantic Field arguments for the constraints of a property, e.g. ge=1
+fn get_field_constraints(schema: &RawSchema) -> Vec<String> {
+    let mut constraints = Vec::new();
+    if let Some(option) = &schema.option {
+        if let Some(min) = option.min {
+            constraints.push(format!("ge={}", utils::constraint_number_to_literal(min)));
+        }
+        if let Some(max) = option.max {
+            constraints.push(format!("le={}", utils::constraint_number_to_literal(max)));
+        }
+        if let Some(min_len) = option.min_len {
+            constraints.push(format!("min_length={}", min_len));
+        }
+        if let Some(max_len) = option.max_len {
+            constraints.push(format!("max_length={}", max_len));
+        }
+        if let Some(pattern) = &option.pattern {
+            constraints.push(format!("pattern={}", utils::string_literal(pattern)));
+        }
+    }
+    constraints
+}
+
 fn get_method_path_names_and_tys(
+    ctx: &Ctxt,
     method: &RawUsecaseMethod,
 ) -> Result<Option<(Vec<String>, Vec<String>)>> {
     let path_params = utils::get_path_params(method);
@@ -563,11 +722,11 @@ fn get_method_path_names_and_tys(
 
                 let ty: String;
 
-                if prop_schema.items.is_some() {
-                    bail!("array property cannot be used as path variable")
+                if prop_schema.items.is_some() || prop_schema.values.is_some() {
+                    bail!("array or map property cannot be used as path variable")
                 }
 
-                if let Some(t) = utils::spec_ty_to_py_builtin_ty(prop_schema.ty.as_ref().unwrap()) {
+                if let Some(t) = utils::spec_ty_to_py_builtin_ty(ctx, prop_schema.ty.as_ref().unwrap()) {
                     ty = t;
                 } else {
                     ty = prop_schema.ty.as_ref().unwrap().clone();
@@ -592,21 +751,26 @@ impl PythonFastApiGenerator {
         schema: &RawSchema,
         override_ty: Option<String>,
         root_schema_ty: Option<String>,
-    ) -> String {
+    ) -> Result<String> {
         let type_name: String;
 
         // find out the correct type name
         if let Some(ty) = &override_ty {
-            type_name = ty.to_case(Case::UpperCamel);
+            type_name = utils::get_prefixed_ty_name(ty);
         } else if schema.items.is_some() {
             type_name = self.generate_struct(
                 ctx,
                 schema.items.as_ref().unwrap(),
                 None,
                 root_schema_ty.clone(),
-            );
-
-            return format!("list[{}]", type_name).to_owned();
+            )?;
+
+            return Ok(format!("list[{}]", type_name));
+        } else if let Some(values) = &schema.values {
+            let keys = RawSchema::new(schema.def_loc.clone(), utils::get_map_keys(schema).to_string());
+            let key_ty = self.generate_struct(ctx, &keys, None, root_schema_ty.clone())?;
+            let value_ty = self.generate_struct(ctx, values, None, root_schema_ty.clone())?;
+            return Ok(format!("dict[{},{}]", key_ty, value_ty));
         } else {
             type_name = schema.ty.as_ref().unwrap().clone();
         }
@@ -617,17 +781,16 @@ impl PythonFastApiGenerator {
         let _enter = span.enter();
 
         // if type name belongs to built-in type, return directly
-        if let Some(ty) = spec_ty_to_py_builtin_ty(&type_name) {
-            return ty;
+        if let Some(ty) = spec_ty_to_py_builtin_ty(ctx, &type_name) {
+            return Ok(ty);
         }
 
         if self.generated_tys.borrow().contains(&type_name) {
-            if let Some(root_schema_ty) = root_schema_ty {
-                if root_schema_ty == type_name {
-                    return type_name;
-                }
+            // the model is not defined yet, it refers to itself directly or through other models
+            if self.defining_tys.borrow().contains(&type_name) {
+                return Ok(format!("'{type_name}'"));
             }
-            return type_name;
+            return Ok(type_name);
         }
 
         // if it is referenced to a custom type, find and return
@@ -653,10 +816,10 @@ impl PythonFastApiGenerator {
                 let usecase_from: &str = match gen_opt {
                     Some(gen_opt) => match &gen_opt.usecase_from {
                         Some(usecase_from) => usecase_from.as_ref(),
-                        None => panic!("python_fastapi usecase_from option is not set")
+                        None => bail!("python_fastapi usecase_from option is not set")
                     },
                     None => {
-                        panic!("python_fastapi usecase_from option is not set");
+                        bail!("python_fastapi usecase_from option is not set");
                     },
                 };
                 let imports_str = format!("from {} import {}\n", usecase_from, type_name);
@@ -664,61 +827,142 @@ impl PythonFastApiGenerator {
 
             }
             
-            return type_name;
+            return Ok(type_name);
+        }
+
+        // the parents have to be defined before the inheriting model, a parent in progress refers to it
+        let defining_parent = get_schema_parents(schema).into_iter()
+            .any(|(_, parent)| self.defining_tys.borrow().contains(&utils::get_prefixed_ty_name(&parent)));
+        self.defining_tys.borrow_mut().insert(type_name.clone());
+        if defining_parent {
+            self.deferred_tys.borrow_mut().push((type_name.clone(), schema.clone()));
+            return Ok(format!("'{type_name}'"));
+        }
+        let mut parent_tys = Vec::new();
+        for (_, parent) in get_schema_parents(schema) {
+            parent_tys.push(self.generate_struct(ctx, &RawSchema::new(schema.def_loc.clone(), parent), None, None)?);
+        }
+        if parent_tys.is_empty() {
+            parent_tys.push("BaseModel".to_string());
         }
 
-        let mut result = format!("class {}(BaseModel):\n", type_name).to_string();
+        let mut result = format!("class {}({}):\n", type_name, parent_tys.join(", "));
+        if let Some(deprecated) = utils::get_schema_deprecated(schema) {
+            result += &utils::py_docstring(&utils::deprecation_text(deprecated), "  ");
+        }
+        let mut has_alias = false;
         let mut required_fields: Vec<String> = Vec::new();
         let mut optional_fields: Vec<String> = Vec::new();
-        if let Some(properties) = &schema.properties {
-            for (prop_name, prop_schema) in properties {
-
-                if prop_schema.option.as_ref()
-                        .and_then(|o| o.python_fastapi.as_ref().and_then(| opt| opt.exclude))
-                        .unwrap_or(false) {
-                    // skip properties if exclude is set
-                    continue;
-                        }
-                let mut field = String::new();
-                let snaked_prop_name = prop_name.to_case(Case::Snake);
-                field += "  ";
-                field += &snaked_prop_name;
-                field += ": ";
-
-                let optional = match prop_schema.required {
-                    Some(req) => !req,
-                    None => false,
-                };
+        let properties = get_schema_properties(ctx, schema)?;
+        utils::check_ident_collisions(&type_name, properties.keys(), utils::py_ident)?;
+        for (prop_name, prop_schema) in &properties {
+
+            if prop_schema.option.as_ref()
+                    .and_then(|o| o.python_fastapi.as_ref().and_then(| opt| opt.exclude))
+                    .unwrap_or(false) {
+                // skip properties if exclude is set
+                continue;
+                    }
+            let mut field = String::new();
+            if let Some(deprecated) = utils::get_schema_deprecated(prop_schema) {
+                field += &utils::description_to_comment(&utils::deprecation_text(deprecated), "  # ");
+            }
+            field += "  ";
+            field += &utils::py_ident(prop_name);
+            field += ": ";
 
-                let prop_ty =
-                    self.generate_struct(ctx, &prop_schema, None, Some(type_name.clone()));
+            let optional = match prop_schema.required {
+                Some(req) => !req,
+                None => false,
+            };
 
-                if optional {
-                    field += &format!("Optional[{}] = None", prop_ty);
-                } else {
-                    field += &prop_ty;
+            let mut prop_ty =
+                self.generate_struct(ctx, prop_schema, None, Some(type_name.clone()))?;
+            let default = utils::get_default_value(ctx, prop_schema)?;
+            let format = prop_schema.option.as_ref().and_then(|option| option.format);
+            if prop_ty == "bytes" {
+                // bytes are base64 encoded on the wire
+                prop_ty = "Base64Bytes".to_string();
+            } else if prop_ty == "str" {
+                match format {
+                    Some(RawSchemaPropertyFormat::Email) => prop_ty = "EmailStr".to_string(),
+                    Some(RawSchemaPropertyFormat::Url) => prop_ty = "AnyUrl".to_string(),
+                    None => {}
                 }
-                field += "\n";
+            }
 
-                if optional {
-                    optional_fields.push(field);
-                } else {
-                    required_fields.push(field);
-                }
+            // a nullable property without a default value still has to be given
+            if prop_schema.nullable.unwrap_or(false) || (optional && default.is_none()) {
+                prop_ty = format!("Optional[{}]", prop_ty);
             }
+            field += &prop_ty;
 
-            if required_fields.len() != 0 {
-                result += &required_fields.join("");
+            let default_expr = if let Some(default) = &default {
+                Some(utils::default_value_to_py_literal(default))
+            } else if optional {
+                Some("None".to_string())
+            } else {
+                None
+            };
+
+            let mut constraints = get_field_constraints(prop_schema);
+            if let Some(alias) = get_field_alias(ctx, prop_name, prop_schema) {
+                constraints.push(alias);
+                has_alias = true;
+            }
+            // a writeonly property is left out of the responses
+            if utils::is_writeonly(prop_schema) {
+                constraints.push("exclude=True".to_string());
             }
-            if optional_fields.len() != 0 {
-                result += &optional_fields.join("");
+            if !constraints.is_empty() {
+                let args: Vec<String> = default_expr.into_iter().chain(constraints).collect();
+                field += &format!(" = Field({})", args.join(", "));
+            } else if let Some(default_expr) = default_expr {
+                field += &format!(" = {}", default_expr);
             }
-            result += "\n";
+            field += "\n";
+
+            if optional || default.is_some() {
+                optional_fields.push(field);
+            } else {
+                required_fields.push(field);
+            }
+        }
+
+        // the fields are read and written by their alias, but can still be given by name
+        if has_alias {
+            result += "  model_config = ConfigDict(populate_by_name=True)\n";
+        }
+        if required_fields.is_empty() && optional_fields.is_empty() {
+            result += "  pass\n";
+        }
+        if !required_fields.is_empty() {
+            result += &required_fields.join("");
+        }
+        if !optional_fields.is_empty() {
+            result += &optional_fields.join("");
         }
+        result += "\n";
+        self.defining_tys.borrow_mut().remove(&type_name);
 
         ctx.append_file(self.name(), &self.dst(ctx), &result);
 
-        type_name
+        // the models whose parents are all defined now
+        let ready: Vec<(String, RawSchema)> = {
+            let defining_tys = self.defining_tys.borrow();
+            let (ready, deferred) = self.deferred_tys.take().into_iter().partition(|(_, deferred)| {
+                get_schema_parents(deferred).into_iter().all(|(_, parent)| !defining_tys.contains(&utils::get_prefixed_ty_name(&parent)))
+            });
+            *self.deferred_tys.borrow_mut() = deferred;
+            ready
+        };
+        for (deferred_name, deferred) in ready {
+            self.generated_tys.borrow_mut().remove(&deferred_name);
+            self.defining_tys.borrow_mut().remove(&deferred_name);
+            self.generate_struct(ctx, &deferred, Some(deferred_name), None)?;
+        }
+
+        Ok(type_name)
     }
 
     fn get_gen_option<'a>(&self, ctx: &'a Ctxt) -> Option<&'a PythonFastApiGeneratorOption> {
@@ -795,4 +1039,299 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn py_declared_errors() -> Result<()> {
+        let api_file: &'static str = r#"
+        #[@python_fastapi.get_ctx_from = "ctx"]
+        #[@python_fastapi.usecase_from = "usecases"]
+        [rest.status = 404]
+        error NotFound
+
+        usecase todo {
+            [rest.method = "get"]
+            [rest.path = ":id"]
+            get_todo {
+                id: string
+            } -> {
+                title: string
+            } errors(NotFound)
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = PythonFastApiGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("generated.py").unwrap();
+        assert!(file_content.contains("  try:\n    return ctx.todo.get_todo(request)\n  except NotFound as err:\n    raise HTTPException(status_code=404, detail=str(err))\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn py_readonly_writeonly() -> Result<()> {
+        let api_file: &'static str = r#"
+        #[@python_fastapi.get_ctx_from = "ctx"]
+        #[@python_fastapi.usecase_from = "usecases"]
+        struct User {
+            [readonly] id: string
+            [writeonly] password: string
+        }
+
+        usecase user {
+            [rest.method = "post"]
+            createUser User -> User
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = PythonFastApiGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("generated.py").unwrap();
+        assert!(file_content.contains("  password: str = Field(exclude=True)\n"), "{}", file_content);
+        assert!(file_content.contains("class CreateUserBody(BaseModel):\n  password: str = Field(exclude=True)\n\n"), "{}", file_content);
+        assert!(file_content.contains("id=None"), "{}", file_content);
+        assert!(file_content.contains("password=body.password"), "{}", file_content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn py_deprecated() -> Result<()> {
+        let api_file: &'static str = r#"
+        #[@python_fastapi.get_ctx_from = "ctx"]
+        #[@python_fastapi.usecase_from = "usecases"]
+        [deprecated = "use TodoV2"]
+        struct Todo {
+            title: string
+        }
+
+        [deprecated]
+        [rest.path = "todos"]
+        usecase todo {
+            [rest.method = "get"]
+            listTodos {
+                [deprecated = "use cursor"]
+                page?: int
+            } -> Todo[]
+        }
+
+        usecase user {
+            [rest.method = "delete"]
+            [deprecated = "use archiveUser"]
+            deleteUser {
+                id: string
+            }
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = PythonFastApiGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("generated.py").unwrap();
+        assert!(file_content.contains("class Todo(BaseModel):\n  \"\"\"Deprecated: use TodoV2\"\"\"\n"), "{}", file_content);
+        assert!(file_content.contains("todo_router = APIRouter(prefix='/todos', deprecated=True)\n"), "{}", file_content);
+        assert!(file_content.contains("page: Optional[int] = Query(None, deprecated=True)"), "{}", file_content);
+        assert!(file_content.contains("@user_router.delete('', deprecated=True)\n"), "{}", file_content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn py_pattern() -> Result<()> {
+        let api_file: &'static str = r#"
+        #[@python_fastapi.get_ctx_from = "ctx"]
+        #[@python_fastapi.usecase_from = "usecases"]
+        struct Todo {
+            [pattern = "^\d+\"é$"]
+            code: string
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = PythonFastApiGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("generated.py").unwrap();
+        // a python string, not a raw string with rust escapes
+        assert!(file_content.contains(r#"pattern="^\\d+\"é$""#), "{}", file_content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn py_wire_case() -> Result<()> {
+        let api_file: &'static str = r#"
+        #[@python_fastapi.get_ctx_from = "ctx"]
+        #[@python_fastapi.usecase_from = "usecases"]
+        #[wire_case = "camel"]
+        struct User {
+            name: string
+            created_at: string
+        }
+
+        usecase user {
+            [rest.method = "get"]
+            listUsers {
+                [json = "q"] search_text?: string
+            } -> {
+                users: User[]
+            }
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = PythonFastApiGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("generated.py").unwrap();
+        assert!(file_content.contains("class User(BaseModel):\n  model_config = ConfigDict(populate_by_name=True)\n"), "{}", file_content);
+        assert!(file_content.contains("  created_at: str = Field(alias=\"createdAt\")\n"), "{}", file_content);
+        assert!(file_content.contains("  name: str\n"), "{}", file_content);
+        assert!(file_content.contains("search_text: Optional[str] = Query(None, alias=\"q\")"), "{}", file_content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn py_keyword_properties() -> Result<()> {
+        let api_file: &'static str = r#"
+        #[@python_fastapi.get_ctx_from = "ctx"]
+        #[@python_fastapi.usecase_from = "usecases"]
+        struct Item {
+            from: string
+        }
+
+        usecase item {
+            [rest.method = "get"]
+            [rest.path = "items/:class"]
+            listItems {
+                class: string
+            } -> {
+                items: Item[]
+            }
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = PythonFastApiGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("generated.py").unwrap();
+        assert!(file_content.contains("  from_: str = Field(alias=\"from\")\n"), "{}", file_content);
+        assert!(file_content.contains("class_: str = Path(alias=\"class\")"), "{}", file_content);
+        assert!(file_content.contains("request = ListItemsRequest(class_=class_)"), "{}", file_content);
+
+        Ok(())
+    }
+
+    #[test]
+    fn py_colliding_properties() -> Result<()> {
+        let api_file: &'static str = r#"
+        #[@python_fastapi.get_ctx_from = "ctx"]
+        #[@python_fastapi.usecase_from = "usecases"]
+        struct Item {
+            fooBar: string
+            foo_bar: string
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = PythonFastApiGenerator::new();
+        let err = run_generator(&g, &ctx).unwrap_err();
+        assert!(err.to_string().contains("properties 'fooBar' and 'foo_bar' of 'Item' are both named 'foo_bar'"), "{}", err);
+
+        Ok(())
+    }
+
+    #[test]
+    fn py_stream() -> Result<()> {
+        let api_file: &'static str = r#"
+        #[@python_fastapi.get_ctx_from = "ctx"]
+        #[@python_fastapi.usecase_from = "usecases"]
+        #[@python_fastapi.async]
+        #[wire_case = "camel"]
+        error Gone
+
+        usecase todo {
+            [rest.method = "get"]
+            watch_todos {
+                a: string
+            } -> stream {
+                title: string
+                created_at: string
+            } errors(Gone)
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = PythonFastApiGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("generated.py").unwrap();
+        assert!(file_content.contains("-> StreamingResponse:"));
+        assert!(file_content.contains("  created_at: str = Field(alias=\"createdAt\")\n"), "{}", file_content);
+        assert!(file_content.contains(r#"  async def events():
+    try:
+      async for item in ctx.todo.watch_todos(request):
+        yield f"data: {item.model_dump_json(by_alias=True)}\n\n"
+    except Gone as err:
+      yield f"event: error\ndata: {err}\n\n"
+  return StreamingResponse(events(), media_type="text/event-stream")
+"#));
+
+        Ok(())
+    }
+
+    #[test]
+    fn py_named_body() -> Result<()> {
+        let api_file: &'static str = r#"
+        #[@python_fastapi.get_ctx_from = "ctx"]
+        #[@python_fastapi.usecase_from = "usecases"]
+        struct NewTodo {
+            title: string
+        }
+
+        struct Todo {
+            id: string
+            title: string
+        }
+
+        usecase todo {
+            [rest.method = "post"]
+            createTodo NewTodo -> Todo
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = PythonFastApiGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("generated.py").unwrap();
+        assert!(file_content.contains("def create_todo(body: NewTodo, ctx = Depends(get_ctx)) -> Todo:"), "{}", file_content);
+        assert!(!file_content.contains("CreateTodoBody"), "{}", file_content);
+        assert!(file_content.contains("from usecases import TodoUsecase\n"), "{}", file_content);
+
+        Ok(())
+    }
 }
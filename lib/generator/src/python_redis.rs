@@ -1,4 +1,4 @@
-use crate::{utils::{get_path_from_optional_parent, get_request_name, get_usecase_name}, Ctxt, Generator};
+use crate::{utils::{self, get_path_from_optional_parent, get_request_name, get_usecase_name}, Ctxt, Generator};
 use anyhow::{ bail, Result};
 use convert_case::Casing;
 use cronus_spec::{PythonFastApiGeneratorOption, PythonRedisGeneratorOption};
@@ -74,6 +74,16 @@ class {service_name}RedisSender({service_name}):
     )
 }
 
+/// The class of the request of a method, a named type (createTodo NewTodo) is used as is
+fn get_request_class(ctx: &crate::Ctxt, method_name: &str, method: &cronus_spec::RawUsecaseMethod) -> Option<String> {
+    match &method.req {
+        Some(req) if utils::is_named_ty(req) => {
+            utils::is_struct_ty(ctx, req).then(|| utils::get_prefixed_ty_name(req.ty.as_ref().unwrap()))
+        },
+        _ => Some(get_request_name(ctx, method_name)),
+    }
+}
+
 fn async_receiver_str(ctx: &crate::Ctxt, service_name:&str, methods: &Vec<(&String, &cronus_spec::RawUsecaseMethod, &cronus_spec::RawUsecaseMethodRedisOption)>) -> String {
     let mut method_strs = String::new();
     let mut listen_methods = vec![];
@@ -88,7 +98,11 @@ fn async_receiver_str(ctx: &crate::Ctxt, service_name:&str, methods: &Vec<(&Stri
         });
         let listen_method_name = format!("_listen_{method_name}");
         
-        let request_ty = get_request_name(ctx, &method_name);
+        // the request class is created from the task, a named scalar or list is the task itself
+        let create_request = match get_request_class(ctx, &method_name, method) {
+            Some(request_ty) => format!("{request_ty}(**task_data)"),
+            None => "task_data".to_string(),
+        };
 
         listen_methods.push(listen_method_name.clone());
         let method_str = format!(
@@ -102,7 +116,7 @@ fn async_receiver_str(ctx: &crate::Ctxt, service_name:&str, methods: &Vec<(&Stri
                 )
                 if task_item:
                     task_data = json.loads(task_item)
-                    request = {request_ty}(**task_data)
+                    request = {create_request}
                     await self._service.{method_name}(request)
                     await self._redis.lrem(
                         "{ack_queue_name}", 1, task_item
@@ -208,8 +222,7 @@ impl Generator for PythonRedisGenerator {
         let mut types_import_from_interfaces = vec![service_name.clone()];
         for (method_name, method, option) in &redis_methods {
             if method.req.is_some() {
-                let request_ty = get_request_name(ctx, method_name);
-                types_import_from_interfaces.push(request_ty);
+                types_import_from_interfaces.extend(get_request_class(ctx, method_name, method));
             }
         }
         let import_items = types_import_from_interfaces.join(", ");
@@ -1,23 +1,35 @@
-use std::{any::type_name, cell::RefCell, collections::HashSet, fmt::format, path::PathBuf};
+use std::{any::type_name, cell::RefCell, collections::{BTreeMap, HashSet}, fmt::format, path::PathBuf};
 
 use convert_case::{Case, Casing};
-use cronus_spec::{RawSchema, RustGeneratorOption};
+use cronus_spec::{RawDeprecated, RawError, RawSchema, RawSchemaPropertyFormat, RustGeneratorOption};
 
 use crate::{
-    utils::{self, get_path_from_optional_parent, get_request_name, get_response_name, get_schema_by_name, get_usecase_name, spec_ty_to_rust_builtin_ty}, Ctxt, Generator
+    utils::{self, get_path_from_optional_parent, get_request_name, get_response_name, get_schema_by_name, get_schema_parents, get_schema_properties, get_usecase_name, spec_ty_to_rust_builtin_ty, Scalar}, Ctxt, Generator
 };
 use tracing::{self, debug, span, Level};
-use anyhow::{Ok, Result};
+use anyhow::{bail, Ok, Result};
 
 pub struct RustGenerator {
-    generated_tys: RefCell<HashSet<String>>
+    generated_tys: RefCell<HashSet<String>>,
+    /// generated structs with a Default impl
+    default_tys: RefCell<HashSet<String>>,
+    /// generated types of each namespace, written as its module at last
+    namespace_tys: RefCell<BTreeMap<String, String>>,
+    /// whether an optional and nullable property needs the deserialize_some helper
+    uses_deserialize_some: RefCell<bool>,
+    /// properties boxed to break a cycle of structs, (struct, property)
+    boxed_props: RefCell<HashSet<(String, String)>>
 }
 
 
 impl RustGenerator {
     pub fn new() -> Self {
         Self {
-            generated_tys: Default::default()
+            generated_tys: Default::default(),
+            default_tys: Default::default(),
+            namespace_tys: Default::default(),
+            uses_deserialize_some: Default::default(),
+            boxed_props: Default::default()
         }
     }
 }
@@ -28,7 +40,9 @@ impl Generator for RustGenerator {
     }
 
     fn before_all(&self, ctx: &Ctxt) -> Result<()> {
-        
+        // a Vec is on the heap already
+        *self.boxed_props.borrow_mut() = utils::get_recursive_props(ctx, |prop| prop.items.is_some())?;
+
         let common_uses = vec!["use serde::{Deserialize, Serialize};","use async_trait::async_trait;"];
         let common_uses_str = common_uses.join("\n") + "\n";
         ctx.append_file(self.name(), &self.dst(ctx), &common_uses_str);
@@ -50,12 +64,46 @@ impl Generator for RustGenerator {
             None => {},
         }
 
+        // uses of the declared types
+        let alias_uses: Vec<String> = utils::get_alias_imports(ctx, &utils::get_spec_tys(ctx), |alias| alias.rust.as_ref())
+            .iter()
+            .map(|u| format!("use {};\n", u))
+            .collect();
+        ctx.append_file(self.name(), &self.dst(ctx), &alias_uses.concat());
+
         Ok(())
 
     }
 
     fn generate_schema(&self, ctx: &Ctxt, schema_name:&str, schema: &RawSchema) -> Result<()> {
-        self.generate_struct(ctx, schema, Some(schema_name.to_owned()), None);
+        self.generate_struct(ctx, schema, Some(schema_name.to_owned()), None)?;
+        Ok(())
+    }
+
+    /// Generate the module of each namespace, e.g. billing.Invoice is in
+    ///
+    /// pub mod billing {
+    ///   use super::*;
+    ///   pub struct Invoice { ... }
+    /// }
+    fn after_all(&self, ctx: &Ctxt) -> Result<()> {
+        if *self.uses_deserialize_some.borrow() {
+            // a present field is Some even if it is null, an absent one is None by its serde default
+            let helper = "fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>\nwhere\n  T: Deserialize<'de>,\n  D: serde::Deserializer<'de>,\n{\n  Deserialize::deserialize(deserializer).map(Some)\n}\n";
+            ctx.append_file(self.name(), &self.dst(ctx), helper);
+        }
+        for (namespace, tys) in self.namespace_tys.borrow().iter() {
+            let mut result = format!("pub mod {} {{\n  use super::*;\n", namespace);
+            for line in tys.lines() {
+                if !line.is_empty() {
+                    result += "  ";
+                }
+                result += line;
+                result += "\n";
+            }
+            result += "}\n";
+            ctx.append_file(self.name(), &self.dst(ctx), &result);
+        }
         Ok(())
     }
 
@@ -75,6 +123,13 @@ impl Generator for RustGenerator {
         let default_error_ty: &str = "Box<dyn std::error::Error>";
         let mut result = String::new();
 
+        // the methods with declared errors return the error enum of the usecase
+        let error_ty = utils::get_usecase_error_name(ctx, name);
+        let errors = utils::get_usecase_errors(ctx, usecase)?;
+        if !errors.is_empty() {
+            result += &self.generate_error_enum(&error_ty, &errors);
+        }
+
         // handle async trait
         match self.get_gen_option(ctx) {
             Some(rust_gen) => {
@@ -89,8 +144,21 @@ impl Generator for RustGenerator {
             },
             _ => {}
         }
+        // a method taking or returning a deprecated type
+        let refers_deprecated = usecase.methods.values()
+            .flat_map(|method| method.req.iter().chain(method.res.iter()))
+            .any(|schema| utils::refers_deprecated_ty(ctx, schema));
+        if refers_deprecated {
+            result += "#[allow(deprecated)]\n";
+        }
+        if let Some(deprecated) = utils::get_usecase_deprecated(usecase) {
+            result += &format!("{}\n", deprecated_attr(deprecated));
+        }
         result += &format!("pub trait {} {{\n", trait_name);
         for (method_name, method) in &usecase.methods {
+            if let Some(deprecated) = utils::get_method_deprecated(method) {
+                result += &format!("  {}\n", deprecated_attr(deprecated));
+            }
 
             // handle async fn 
             match self.get_gen_option(ctx) {
@@ -111,8 +179,14 @@ impl Generator for RustGenerator {
             result += "(&self";
 
             if let Some(req) = &method.req {
-                let request_ty = get_request_name(ctx, method_name);
-                self.generate_struct(ctx, &req, Some(request_ty.clone()), None);
+                // a named type is used as is, otherwise the request struct is generated
+                let request_ty = if utils::is_named_ty(req) {
+                    self.generate_struct(ctx, req, None, None)?
+                } else {
+                    let request_ty = get_request_name(ctx, method_name);
+                    self.generate_struct(ctx, req, Some(request_ty.clone()), None)?;
+                    request_ty
+                };
                 result += ", request: ";
                 result += &request_ty;
             }
@@ -122,9 +196,13 @@ impl Generator for RustGenerator {
             let mut result_f_type: Option<String> = Some(default_error_ty.to_string());
             
             if let Some(res) = &method.res {
-                let response_ty = get_response_name(ctx, method_name);
-                self.generate_struct(ctx, &res, Some(response_ty.clone()), None);
-                result_t_type = response_ty;
+                result_t_type = if utils::is_named_ty(res) {
+                    self.generate_struct(ctx, res, None, None)?
+                } else {
+                    let response_ty = get_response_name(ctx, method_name);
+                    self.generate_struct(ctx, res, Some(response_ty.clone()), None)?;
+                    response_ty
+                };
             } 
 
             // handle result false type
@@ -142,11 +220,20 @@ impl Generator for RustGenerator {
                 },
                 _ => {}
             }
+            if !utils::get_method_errors(ctx, method)?.is_empty() {
+                result_f_type = Some(error_ty.clone());
+            }
 
-            if result_f_type.is_some() {
-                result += &format!(" -> Result<{}, {}>", result_t_type, result_f_type.unwrap());
+            let result_ty = match result_f_type {
+                Some(result_f_type) => format!("Result<{}, {}>", result_t_type, result_f_type),
+                None => format!("Result<{}>", result_t_type),
+            };
+
+            // a stream method yields its responses one by one
+            if utils::is_stream_method(method) {
+                result += &format!(" -> std::pin::Pin<Box<dyn futures::Stream<Item = {}> + Send>>", result_ty);
             } else {
-                result += &format!(" -> Result<{}>", result_t_type);
+                result += &format!(" -> {}", result_ty);
             }
 
             result += ";\n";
@@ -165,8 +252,27 @@ impl Generator for RustGenerator {
 }
 
 impl RustGenerator {
-    
 
+    /// Generate the error enum of a usecase, a variant with the message for each declared error
+    ///
+    /// The errors which are not declared are kept by the Other variant.
+    fn generate_error_enum(&self, error_ty: &str, errors: &[(&str, &RawError)]) -> String {
+        let variants: Vec<String> = errors.iter().map(|(name, _)| name.to_case(Case::UpperCamel)).collect();
+        let mut result = format!("#[derive(Debug)]\npub enum {} {{\n", error_ty);
+        for variant in &variants {
+            result += &format!("  {}(String),\n", variant);
+        }
+        result += "  Other(Box<dyn std::error::Error + Send + Sync>),\n}\n\n";
+
+        result += &format!("impl std::fmt::Display for {} {{\n", error_ty);
+        result += "  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {\n    match self {\n";
+        for variant in &variants {
+            result += &format!("      Self::{}(message) => write!(f, \"{}: {{}}\", message),\n", variant, variant);
+        }
+        result += "      Self::Other(err) => std::fmt::Display::fmt(err, f),\n    }\n  }\n}\n\n";
+        result += &format!("impl std::error::Error for {} {{}}\n\n", error_ty);
+        result
+    }
 
  
     /// Generate the Rust struct definition
@@ -177,18 +283,24 @@ impl RustGenerator {
         schema: &RawSchema,
         override_ty: Option<String>,
         root_schema_ty: Option<String>
-    ) -> String {
+    ) -> Result<String> {
         let type_name: String;
 
         // find out the correct type name
         if let Some(ty) = &override_ty {
-            type_name = ty.to_case(Case::UpperCamel);
+            type_name = utils::get_rust_ty_path(ty);
         }
         else if schema.items.is_some() {
 
-            type_name = self.generate_struct(ctx, schema.items.as_ref().unwrap(), None, root_schema_ty.clone());
+            type_name = self.generate_struct(ctx, schema.items.as_ref().unwrap(), None, root_schema_ty.clone())?;
 
-            return format!("Vec<{}>", type_name).to_owned()
+            return Ok(format!("Vec<{}>", type_name))
+        }
+        else if let Some(values) = &schema.values {
+            let keys = utils::get_map_keys(schema);
+            let key_ty = spec_ty_to_rust_builtin_ty(ctx, keys).unwrap_or(utils::get_rust_ty_path(keys));
+            let value_ty = self.generate_struct(ctx, values, None, root_schema_ty.clone())?;
+            return Ok(format!("HashMap<{},{}>", key_ty, value_ty))
         }
         else {
             type_name = schema.ty.as_ref().unwrap().clone();
@@ -202,17 +314,12 @@ impl RustGenerator {
         let _enter = span.enter();
 
         // if type name belongs to built-in type, return directly
-        if let Some(ty) = spec_ty_to_rust_builtin_ty(&type_name) {
-            return ty;
+        if let Some(ty) = spec_ty_to_rust_builtin_ty(ctx, &type_name) {
+            return Ok(ty);
         }
 
         if self.generated_tys.borrow().contains(&type_name) {
-            if let Some(root_schema_ty) = root_schema_ty  {
-                if  root_schema_ty == type_name {
-                    return format!("Box<{type_name}>")
-                }
-            }
-            return type_name;
+            return Ok(type_name);
         }
 
 
@@ -221,16 +328,28 @@ impl RustGenerator {
         if let Some(ref_schema) = get_schema_by_name(&ctx, &type_name) {
             // check whether schema is a type referencing another user type
             if schema.properties.is_none() && schema.enum_items.is_none() && schema.items.is_none() {
-                return self.generate_struct(ctx, ref_schema, Some(type_name.to_string()), Some(type_name.to_string()));
+                return self.generate_struct(ctx, ref_schema, Some(type_name.to_string()), root_schema_ty);
             }
         }
+        // the type is declared by its name in the module of its namespace
+        let local_name = type_name.rsplit("::").next().unwrap().to_string();
+        let properties = get_schema_properties(ctx, schema)?;
+        // properties with a default value get a Default impl instead of the derived one, as do the parents of their struct
+        let has_defaults = needs_default_impl(ctx, schema)?;
+
         let mut attrs: Vec<String> = vec![];
 
         if let Some(gen_opt) = self.get_gen_option(ctx) {
-            let default_derive = match &gen_opt.default_derive {
+            let mut default_derive = match &gen_opt.default_derive {
                 Some(default_derive) => default_derive.clone(),
                 None => vec!["Debug", "Clone", "Serialize", "Deserialize", "PartialEq", "Eq"].iter().map(|s|s.to_string()).collect(),
             };
+            if has_defaults || schema.enum_items.is_some() {
+                default_derive.retain(|derive| derive != "Default");
+            }
+            if contains_float(ctx, schema, &mut HashSet::new())? {
+                default_derive.retain(|derive| derive != "Eq");
+            }
 
             let no_default_derive = match gen_opt.no_default_derive {
                 Some(no_default) => {
@@ -240,6 +359,9 @@ impl RustGenerator {
             };
 
             if !no_default_derive {
+                if default_derive.iter().any(|derive| derive == "Default") {
+                    self.default_tys.borrow_mut().insert(type_name.clone());
+                }
                 let derive_attr = format!("#[derive({})]", default_derive.join(", "));
                 attrs.push(derive_attr);
         
@@ -266,62 +388,276 @@ impl RustGenerator {
             None => {},
         }
         self.generated_tys.borrow_mut().insert(type_name.clone());
+        let doc = utils::get_schema_description(schema).map_or(String::new(), |description| utils::description_to_comment(description, "/// "));
+        if let Some(deprecated) = utils::get_schema_deprecated(schema) {
+            attrs.push(deprecated_attr(deprecated));
+        }
 
-        let mut result = format!("{}\npub struct {} {{\n", attrs.join("\n"), type_name).to_string();
+        // if it is a enum type, generate the enum definition
+        if let Some(enum_items) = &schema.enum_items {
+            // items are upper snake cased on the wire, same as the other generators
+            attrs.push("#[serde(rename_all = \"SCREAMING_SNAKE_CASE\")]".to_string());
+            let mut result = format!("{}{}\npub enum {} {{\n", doc, attrs.join("\n"), local_name);
+            for item in enum_items {
+                if let Some(deprecated) = utils::get_enum_item_deprecated(item) {
+                    result += &format!("  {}\n", deprecated_attr(deprecated));
+                }
+                result += &format!("  {},\n", item.name.to_case(Case::UpperCamel));
+            }
+            result += "}\n";
+            self.append_ty(ctx, schema, &result);
+            return Ok(type_name);
+        }
 
+        // the fields are named in the wire case of the spec, a json option renames a single one
+        if let Some(wire_case) = utils::get_serde_wire_case(ctx) {
+            attrs.push(format!("#[serde(rename_all = \"{}\")]", wire_case));
+        }
 
-        if let Some(properties) = &schema.properties {
-            for (prop_name, prop_schema) in properties {
+        // checked with the validator crate if a constraint is in it
+        if utils::schema_has_constraints(ctx, schema)? {
+            attrs.push("#[derive(validator::Validate)]".to_string());
+        }
 
-                let mut attrs: Vec<String> = vec![];
-                match &prop_schema.option {
-                    Some(option) => {
-                        match &option.rust {
-                            Some(rust_opt) => {
-                                match &rust_opt.attrs {
-                                    Some(custom_attrs) => {
-                                        attrs.extend(custom_attrs.iter().map(|attr| format!("#[{}]", attr).to_string()) );
-                                    },
-                                    None => {},
-                                }
-                            },
-                            None => {}
-                        }
-                    },
-                    None => {},
-                }
+        // a field of a deprecated type, or a deprecated parent flattened into the struct
+        let refers_deprecated = properties.values().any(|prop| utils::refers_deprecated_ty(ctx, prop))
+            || get_schema_parents(schema).into_iter().any(|(_, parent)| utils::refers_deprecated_ty(ctx, &RawSchema::new(schema.def_loc.clone(), parent)));
+        if refers_deprecated {
+            attrs.push("#[allow(deprecated)]".to_string());
+        }
+
+        // bytes are base64 encoded on the wire, with the serde_as of serde_with
+        if properties.values().any(|prop| base64_serde_as(prop).is_some()) {
+            attrs.insert(0, "#[serde_with::serde_as]".to_string());
+        }
 
-                if !attrs.is_empty() {
-                    result += &format!("  {}\n", attrs.join("\n"));
+        let mut result = format!("{}{}\npub struct {} {{\n", doc, attrs.join("\n"), local_name).to_string();
+        // field initializers of the Default impl, None if a field has no default
+        let mut default_fields: Option<Vec<String>> = Some(vec![]);
+        let mut default_fns = String::new();
+        let mut pattern_statics = String::new();
+
+        // the parents are flattened into the struct
+        for (field_name, parent) in get_schema_parents(schema) {
+            let parent_schema = RawSchema::new(schema.def_loc.clone(), parent);
+            let parent_ty = self.generate_struct(ctx, &parent_schema, None, Some(type_name.clone()))?;
+            result += "  #[serde(flatten)]\n";
+            if utils::schema_has_constraints(ctx, &parent_schema)? {
+                result += "  #[validate(nested)]\n";
+            }
+            result += &format!("  pub {}: {},\n", field_name, parent_ty);
+            if !self.default_tys.borrow().contains(&parent_ty) {
+                if has_defaults {
+                    bail!("struct '{}' has default values, but its parent '{}' has no Default to fill the flattened field", type_name, parent_ty)
                 }
+                default_fields = None;
+            }
+            if let Some(fields) = default_fields.as_mut() {
+                fields.push(format!("{}: Default::default()", field_name));
+            }
+        }
 
-                result += "  pub ";
-                result += prop_name;
-                result += ": ";
+        for (prop_name, prop_schema) in &properties {
+
+            let mut attrs: Vec<String> = vec![];
+            match &prop_schema.option {
+                Some(option) => {
+                    match &option.rust {
+                        Some(rust_opt) => {
+                            match &rust_opt.attrs {
+                                Some(custom_attrs) => {
+                                    attrs.extend(custom_attrs.iter().map(|attr| format!("#[{}]", attr).to_string()) );
+                                },
+                                None => {},
+                            }
+                        },
+                        None => {}
+                    }
+                },
+                None => {},
+            }
 
-                let optional = match prop_schema.required {
-                    Some(req) => !req,
-                    None => false
-                };
+            let default = utils::get_default_value(ctx, prop_schema)?;
+            let default_fn = format!("default_{}_{}", local_name.to_case(Case::Snake), prop_name.to_case(Case::Snake));
+            if default.is_some() {
+                attrs.push(format!("#[serde(default = \"{}\")]", default_fn));
+            }
 
-                let prop_ty = self.generate_struct(ctx, &prop_schema, None, Some(type_name.clone()));
+            // a property with a default value is always there
+            let optional = match prop_schema.required {
+                Some(req) => !req && default.is_none(),
+                None => false
+            };
+            let nullable = prop_schema.nullable.unwrap_or(false);
+            // an absent property is None and a null one is Some(None)
+            if optional && nullable {
+                attrs.push("#[serde(default, skip_serializing_if = \"Option::is_none\", deserialize_with = \"deserialize_some\")]".to_string());
+                *self.uses_deserialize_some.borrow_mut() = true;
+            }
 
-                if optional {
-                    result += &format!("Option<{}>", prop_ty);
+            let mut prop_ty = self.generate_struct(ctx, prop_schema, None, Some(type_name.clone()))?;
+            let is_default_ty = prop_schema.items.is_some() || prop_schema.values.is_some() || spec_ty_to_rust_builtin_ty(ctx, prop_schema.ty.as_ref().unwrap()).is_some() || self.default_tys.borrow().contains(&prop_ty);
+            // a struct holding itself, directly or through other structs, would be infinitely sized
+            if override_ty.as_ref().is_some_and(|ty| self.boxed_props.borrow().contains(&(ty.clone(), prop_name.clone()))) {
+                prop_ty = format!("Box<{}>", prop_ty);
+            }
+            // a readonly field is left to its default when deserialized, None if its type has no default
+            let readonly_option = utils::is_readonly(prop_schema) && !optional && !nullable && default.is_none() && !is_default_ty;
+            let wrap_option = |ty: String| {
+                let ty = if nullable || readonly_option { format!("Option<{}>", ty) } else { ty };
+                if optional { format!("Option<{}>", ty) } else { ty }
+            };
 
-                } else {
-                    result += &prop_ty;
+            // a keyword is a raw identifier, which serde names without the r#, or is renamed back (self_)
+            let ident = utils::rust_ident(prop_name);
+            if let Some(json) = utils::get_json_name(prop_schema) {
+                attrs.push(format!("#[serde(rename = \"{}\")]", json));
+            } else if !ident.starts_with("r#") && ident != *prop_name {
+                let wire_name = utils::get_wire_name(ctx, prop_name, prop_schema).unwrap_or_else(|| prop_name.clone());
+                attrs.push(format!("#[serde(rename = \"{}\")]", wire_name));
+            }
+
+            // a readonly property is never read from requests, a writeonly one never sent back
+            if utils::is_readonly(prop_schema) {
+                attrs.push("#[serde(skip_deserializing)]".to_string());
+            }
+            if utils::is_writeonly(prop_schema) {
+                attrs.push("#[serde(skip_serializing)]".to_string());
+            }
+
+            if let Some(deprecated) = utils::get_schema_deprecated(prop_schema) {
+                attrs.push(deprecated_attr(deprecated));
+            }
+
+            if let Some(serde_as) = base64_serde_as(prop_schema) {
+                attrs.push(format!("#[serde_as(as = \"{}\")]", wrap_option(serde_as.to_string())));
+            }
+
+            let (validations, pattern_static) = self.generate_field_validations(ctx, &local_name, prop_name, prop_schema)?;
+            if !validations.is_empty() {
+                attrs.push(format!("#[validate({})]", validations.join(", ")));
+            }
+            pattern_statics += &pattern_static;
+
+            if !attrs.is_empty() {
+                result += &format!("  {}\n", attrs.join("\n"));
+            }
+
+            result += "  pub ";
+            result += &ident;
+            result += ": ";
+            result += &wrap_option(prop_ty.clone());
+            result += ",\n";
+
+            if let Some(default) = &default {
+                let mut literal = utils::default_value_to_rust_literal(default);
+                if (prop_ty == "f32" || prop_ty == "f64") && !literal.contains('.') {
+                    // an integer literal does not coerce to a float
+                    literal += ".0";
                 }
-                result += ",\n";
+                if nullable {
+                    literal = format!("Some({})", literal);
+                }
+                default_fns += &format!("fn {}() -> {} {{\n  {}\n}}\n", default_fn, wrap_option(prop_ty.clone()), literal);
+                if let Some(fields) = default_fields.as_mut() {
+                    fields.push(format!("{}: {}()", ident, default_fn));
+                }
+            } else if optional || nullable || readonly_option || is_default_ty {
+                if let Some(fields) = default_fields.as_mut() {
+                    fields.push(format!("{}: Default::default()", ident));
+                }
+            } else {
+                default_fields = None;
             }
         }
 
         result += "}\n";
-        ctx.append_file(self.name(), &self.dst(ctx), &result);
+        result += &default_fns;
+        result += &pattern_statics;
+
+        if has_defaults {
+            if let Some(fields) = default_fields {
+                // the impl sets the deprecated fields as well
+                if properties.values().any(|prop| utils::get_schema_deprecated(prop).is_some()) {
+                    result += "#[allow(deprecated)]\n";
+                }
+                result += &format!("impl Default for {} {{\n  fn default() -> Self {{\n    Self {{\n", local_name);
+                for field in fields {
+                    result += &format!("      {},\n", field);
+                }
+                result += "    }\n  }\n}\n";
+                self.default_tys.borrow_mut().insert(type_name.clone());
+            }
+        }
+        self.append_ty(ctx, schema, &result);
+
+
+
+        Ok(type_name)
+    }
+
+    /// Append a generated type to the file, or to the module of its namespace
+    fn append_ty(&self, ctx: &Ctxt, schema: &RawSchema, result: &str) {
+        match &schema.namespace {
+            Some(namespace) => {
+                *self.namespace_tys.borrow_mut().entry(namespace.to_case(Case::Snake)).or_default() += result;
+            },
+            None => ctx.append_file(self.name(), &self.dst(ctx), result),
+        }
+    }
+
+    /// Generate the validator crate rules of a field, e.g. range(min = 1), and the static regex of its pattern
+    fn generate_field_validations(&self, ctx: &Ctxt, type_name: &str, prop_name: &str, prop_schema: &RawSchema) -> Result<(Vec<String>, String)> {
+        let mut validations = Vec::new();
+        let mut pattern_static = String::new();
+
+        if let Some(option) = &prop_schema.option {
+            let bounds = |min: Option<String>, max: Option<String>| {
+                let mut bounds = Vec::new();
+                if let Some(min) = min {
+                    bounds.push(format!("min = {}", min));
+                }
+                if let Some(max) = max {
+                    bounds.push(format!("max = {}", max));
+                }
+                bounds.join(", ")
+            };
 
+            if option.min.is_some() || option.max.is_some() {
+                validations.push(format!("range({})", bounds(
+                    option.min.map(utils::constraint_number_to_literal),
+                    option.max.map(utils::constraint_number_to_literal)
+                )));
+            }
+            if option.min_len.is_some() || option.max_len.is_some() {
+                validations.push(format!("length({})", bounds(
+                    option.min_len.map(|len| len.to_string()),
+                    option.max_len.map(|len| len.to_string())
+                )));
+            }
+            if let Some(pattern) = &option.pattern {
+                let static_name = format!("{}_{}_PATTERN", type_name, prop_name).to_case(Case::UpperSnake);
+                pattern_static = format!(
+                    "static {}: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| regex::Regex::new({:?}).unwrap());\n",
+                    static_name, pattern
+                );
+                validations.push(format!("regex(path = *{})", static_name));
+            }
+            match option.format {
+                Some(RawSchemaPropertyFormat::Email) => validations.push("email".to_string()),
+                Some(RawSchemaPropertyFormat::Url) => validations.push("url".to_string()),
+                None => {}
+            }
+        }
 
+        // a field of a validated struct type is validated along
+        let element_schema = prop_schema.items.as_deref().unwrap_or(prop_schema);
+        let is_custom_ty = element_schema.ty.as_ref().is_some_and(|ty| get_schema_by_name(ctx, ty).is_some());
+        if is_custom_ty && utils::schema_has_constraints(ctx, prop_schema)? {
+            validations.push("nested".to_string());
+        }
 
-        type_name
+        Ok((validations, pattern_static))
     }
 
     fn get_gen_option<'a>(&self, ctx: &'a Ctxt) -> Option<&'a RustGeneratorOption> {
@@ -356,6 +692,70 @@ impl RustGenerator {
 }
 
 
+/// Whether a struct gets a Default impl, for its default values or for a struct inheriting it which has some
+fn needs_default_impl(ctx: &Ctxt, schema: &RawSchema) -> Result<bool> {
+    if get_schema_properties(ctx, schema)?.values().any(|prop| prop.default.is_some()) {
+        return Ok(true);
+    }
+    for child in ctx.spec.ty.iter().flat_map(|tys| tys.values()) {
+        let inherits = get_schema_parents(child).into_iter()
+            .any(|(_, parent)| get_schema_by_name(ctx, &parent).is_some_and(|parent| std::ptr::eq(parent, schema)));
+        if inherits && needs_default_impl(ctx, child)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Whether a type holds a float somewhere, a float does not implement Eq
+fn contains_float(ctx: &Ctxt, schema: &RawSchema, visiting: &mut HashSet<String>) -> Result<bool> {
+    if let Some(nested) = schema.items.as_ref().or(schema.values.as_ref()) {
+        return contains_float(ctx, nested, visiting);
+    }
+    if let Some(ty) = &schema.ty {
+        if schema.properties.is_none() {
+            if matches!(Scalar::parse(ty), Some(Scalar::F32 | Scalar::F64)) {
+                return Ok(true);
+            }
+            // a reference to another type, recursive types are checked once
+            return match get_schema_by_name(ctx, ty) {
+                Some(ref_schema) if ref_schema.alias.is_none() && visiting.insert(ty.clone()) => contains_float(ctx, ref_schema, visiting),
+                _ => Ok(false),
+            };
+        }
+    }
+    for (_, parent) in get_schema_parents(schema) {
+        if contains_float(ctx, &RawSchema::new(schema.def_loc.clone(), parent), visiting)? {
+            return Ok(true);
+        }
+    }
+    for prop in get_schema_properties(ctx, schema)?.values() {
+        if contains_float(ctx, prop, visiting)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// The serde_as type of a bytes property, which is base64 encoded on the wire
+/// The attribute of a deprecated item, the note is what to use instead
+fn deprecated_attr(deprecated: &RawDeprecated) -> String {
+    match deprecated.note() {
+        Some(note) => format!("#[deprecated(note = {:?})]", note),
+        None => "#[deprecated]".to_string(),
+    }
+}
+
+fn base64_serde_as(schema: &RawSchema) -> Option<&'static str> {
+    let is_bytes = |schema: &RawSchema| schema.ty.as_deref().and_then(Scalar::parse) == Some(Scalar::Bytes);
+    match &schema.items {
+        Some(items) if is_bytes(items) => Some("Vec<serde_with::base64::Base64>"),
+        Some(_) => None,
+        None if is_bytes(schema) => Some("serde_with::base64::Base64"),
+        None => None,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::path::PathBuf;
@@ -480,6 +880,395 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn validation_constraints() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        struct Address {
+            [min_len = 1]
+            city: string
+        }
+        struct hello {
+            [min = 1]
+            [max = 100]
+            count: u32
+            [pattern = "^[a-z]+$"]
+            name: string
+            [format = "email"]
+            email?: string
+            address: Address
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+
+        assert!(file_content.contains("#[derive(validator::Validate)]"));
+        assert!(file_content.contains("#[validate(range(min = 1, max = 100))]"));
+        assert!(file_content.contains("#[validate(regex(path = *HELLO_NAME_PATTERN))]"));
+        assert!(file_content.contains("static HELLO_NAME_PATTERN: std::sync::LazyLock<regex::Regex>"));
+        assert!(file_content.contains("#[validate(email)]"));
+        assert!(file_content.contains("#[validate(length(min = 1))]"));
+        assert!(file_content.contains("#[validate(nested)]"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn declared_type() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        [@rust.type = "DateTime<Utc>"]
+        [@rust.import = "chrono::{DateTime, Utc}"]
+        type Timestamp
+        type Opaque
+        struct hello {
+            created_at: Timestamp
+            history: map<string,Timestamp>
+            other?: Opaque
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+
+        assert!(file_content.contains("use chrono::{DateTime, Utc};"));
+        assert!(file_content.contains("pub created_at: DateTime<Utc>"));
+        assert!(file_content.contains("pub history: HashMap<String,DateTime<Utc>>"));
+        assert!(file_content.contains("pub other: Option<Opaque>"));
+        assert!(!file_content.contains("struct Timestamp"));
+        assert!(!file_content.contains("struct Opaque"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn scalar_types() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        struct Point {
+            x: f64 = 1
+        }
+        struct Shape {
+            points: Point[]
+        }
+        struct hello {
+            id: uuid
+            created_at: datetime
+            size: u64
+            data: bytes
+            thumbnail?: bytes
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+
+        assert!(file_content.contains("fn default_point_x() -> f64 {\n  1.0\n}"));
+        // a float rules out Eq, for the types holding it as well
+        assert!(file_content.contains("#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]\npub struct Shape"));
+        assert!(file_content.contains("#[serde_with::serde_as]\n#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]\npub struct Hello"));
+        assert!(file_content.contains("pub id: uuid::Uuid"));
+        assert!(file_content.contains("pub created_at: chrono::DateTime<chrono::Utc>"));
+        assert!(file_content.contains("pub size: u64"));
+        assert!(file_content.contains("#[serde_as(as = \"serde_with::base64::Base64\")]"));
+        assert!(file_content.contains("#[serde_as(as = \"Option<serde_with::base64::Base64>\")]"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn declared_errors() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        [rest.status = 404]
+        error NotFound
+        error Conflict
+
+        usecase todo {
+            get_todo {
+                id: string
+            } errors(NotFound)
+            create_todo {
+                title: string
+            } errors(Conflict)
+            list_todos
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+
+        assert!(file_content.contains("pub enum TodoUsecaseError {\n  Conflict(String),\n  NotFound(String),\n  Other(Box<dyn std::error::Error + Send + Sync>),\n}"));
+        assert!(file_content.contains("impl std::error::Error for TodoUsecaseError {}"));
+        assert!(file_content.contains("fn get_todo(&self, request: GetTodoRequest) -> Result<(), TodoUsecaseError>;"));
+        // a method without declared errors keeps the default error type
+        assert!(file_content.contains("fn list_todos(&self) -> Result<(), Box<dyn std::error::Error>>;"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn stream_method() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        usecase todo {
+            watch_todos {} -> stream {
+                title: string
+            }
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+
+        assert!(file_content.contains("fn watch_todos(&self, request: WatchTodosRequest) -> std::pin::Pin<Box<dyn futures::Stream<Item = Result<WatchTodosResponse, Box<dyn std::error::Error>>> + Send>>;"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn named_method_io() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        struct Todo {
+            title: string
+        }
+        namespace billing {
+            struct Invoice {
+                total: int
+            }
+        }
+        usecase todo {
+            createTodo Todo -> Todo
+            listTodos -> Todo[]
+            pay billing.Invoice -> string
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+
+        assert!(file_content.contains("fn create_todo(&self, request: Todo) -> Result<Todo, Box<dyn std::error::Error>>;"), "{}", file_content);
+        assert!(file_content.contains("fn list_todos(&self) -> Result<Vec<Todo>, Box<dyn std::error::Error>>;"), "{}", file_content);
+        assert!(file_content.contains("fn pay(&self, request: billing::Invoice) -> Result<String, Box<dyn std::error::Error>>;"), "{}", file_content);
+        assert!(!file_content.contains("CreateTodoRequest"));
+        assert!(!file_content.contains("ListTodosResponse"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn namespaces() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        struct Item {
+            id: string
+        }
+        namespace billing {
+            struct Item {
+                price: int
+                next: Item
+            }
+            struct Invoice {
+                items: map<string, Item[]>
+            }
+        }
+        usecase shop {
+            pay {
+                invoice: billing.Invoice
+                item: Item
+            }
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+
+        assert!(file_content.contains("pub mod billing {\n  use super::*;\n"));
+        assert!(file_content.contains("  pub struct Item {\n"));
+        assert!(file_content.contains("  pub next: Box<billing::Item>,\n"));
+        assert!(file_content.contains("  pub items: HashMap<String,Vec<billing::Item>>,\n"));
+        assert!(file_content.contains("pub invoice: billing::Invoice,\n"));
+        assert!(file_content.contains("pub item: Item,\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn nested_type_expr() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        struct Todo {
+            tags: string[][]
+            by_user: map<string, Todo[]>
+            scores: map<string, map<string, f64>>
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+
+        assert!(file_content.contains("pub tags: Vec<Vec<String>>"));
+        assert!(file_content.contains("pub by_user: HashMap<String,Vec<Todo>>"));
+        assert!(file_content.contains("pub scores: HashMap<String,HashMap<String,f64>>"));
+        // the float in the nested map rules out Eq
+        assert!(!file_content.contains(", Eq)]\npub struct Todo"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn default_value() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        enum Status {
+            Active
+            Inactive
+        }
+        struct hello {
+            count: u32 = 10
+            name?: string = "abc"
+            status: Status = Active
+            tags: string[]
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+
+        assert!(file_content.find("pub enum Status").is_some());
+        assert!(file_content.find("#[serde(default = \"default_hello_count\")]").is_some());
+        assert!(file_content.find("fn default_hello_count() -> u32 {\n  10\n}").is_some());
+        assert!(file_content.find("pub name: String").is_some());
+        assert!(file_content.find("fn default_hello_status() -> Status {\n  Status::Active\n}").is_some());
+        assert!(file_content.find("impl Default for Hello").is_some());
+        assert!(file_content.find("tags: Default::default()").is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn inherited_default_value() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        struct Base {
+            name: string
+        }
+        struct Todo : Base {
+            count: int = 10
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+
+        // the parent gets a Default impl to fill the flattened field
+        assert!(file_content.contains("impl Default for Base {"), "{}", file_content);
+        assert!(file_content.contains("impl Default for Todo {"), "{}", file_content);
+        assert!(file_content.contains("      base: Default::default(),\n"), "{}", file_content);
+        assert!(file_content.contains("      count: default_todo_count(),\n"), "{}", file_content);
+
+        let api_file: &'static str = r#"
+        struct Owner {
+            name: string
+        }
+        struct Base {
+            owner: Owner
+        }
+        struct Todo : Base {
+            count: int = 10
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let err = run_generator(&RustGenerator::new(), &ctx).unwrap_err();
+        assert!(err.to_string().contains("struct 'Todo' has default values, but its parent 'Base' has no Default to fill the flattened field"), "{}", err);
+
+        Ok(())
+    }
+
+    #[test]
+    fn inherited_and_spread_struct() -> Result<()>{
+        let api_file: &'static str = r#"
+        struct User {
+            name: string
+        }
+        struct Timestamps {
+            created_at: string
+        }
+        struct Admin : User {
+            ...Timestamps
+            level: u32
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+
+        assert!(file_content.find("#[serde(flatten)]\n  pub user: User").is_some());
+        assert!(file_content.find("created_at: String").is_some());
+        assert!(file_content.find("level: u32").is_some());
+
+        Ok(())
+    }
+
     #[test]
     fn custom_uses() -> Result<()>{
         let api_file: &'static str = r#"
@@ -510,4 +1299,203 @@ mod test {
         Ok(())
         
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn type_options() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        [description = "A todo item"]
+        [rust.attrs = ("serde(deny_unknown_fields)")]
+        struct Todo {
+            title: string
+        }
+        [description = "The status"]
+        enum Status {
+            Open
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+        assert!(file_content.contains("/// A todo item\n#[derive("), "{}", file_content);
+        assert!(file_content.contains("#[serde(deny_unknown_fields)]\npub struct Todo {"), "{}", file_content);
+        assert!(file_content.contains("/// The status\n"), "{}", file_content);
+        Ok(())
+    }
+
+    #[test]
+    fn nullable_properties() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        struct UpdateTodo {
+            title?: string
+            note?: string | null
+            owner: string | null
+            count: int | null = 1
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+        assert!(file_content.contains("pub title: Option<String>,"), "{}", file_content);
+        assert!(file_content.contains("#[serde(default, skip_serializing_if = \"Option::is_none\", deserialize_with = \"deserialize_some\")]\n  pub note: Option<Option<String>>,"), "{}", file_content);
+        assert!(file_content.contains("pub owner: Option<String>,"), "{}", file_content);
+        assert!(file_content.contains("pub count: Option<i32>,"), "{}", file_content);
+        assert!(file_content.contains("fn default_update_todo_count() -> Option<i32> {\n  Some(1)\n}"), "{}", file_content);
+        assert!(file_content.contains("fn deserialize_some<'de, T, D>"), "{}", file_content);
+        Ok(())
+    }
+
+    #[test]
+    fn readonly_writeonly_properties() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        struct User {
+            [readonly] id: string
+            [writeonly] password: string
+            [readonly] owner: Owner
+        }
+        struct Owner {
+            name: string
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+        assert!(file_content.contains("#[serde(skip_deserializing)]\n  pub id: String,"), "{}", file_content);
+        assert!(file_content.contains("#[serde(skip_serializing)]\n  pub password: String,"), "{}", file_content);
+        // Owner has no Default, the field is None when deserialized
+        assert!(file_content.contains("#[serde(skip_deserializing)]\n  pub owner: Option<Owner>,"), "{}", file_content);
+        Ok(())
+    }
+
+    #[test]
+    fn wire_case_and_json_rename() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        #[wire_case = "camel"]
+        struct User {
+            created_at: string
+            [json = "e-mail"] email: string
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+        assert!(file_content.contains("#[serde(rename_all = \"camelCase\")]\npub struct User {"), "{}", file_content);
+        assert!(file_content.contains("#[serde(rename = \"e-mail\")]\n  pub email: String,"), "{}", file_content);
+        Ok(())
+    }
+
+    #[test]
+    fn keyword_properties() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        struct Item {
+            type: string
+            self: string
+            async?: bool
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+        assert!(file_content.contains("  pub r#type: String,"), "{}", file_content);
+        assert!(file_content.contains("  pub r#async: Option<bool>,"), "{}", file_content);
+        assert!(file_content.contains("#[serde(rename = \"self\")]\n  pub self_: String,"), "{}", file_content);
+        Ok(())
+    }
+
+    #[test]
+    fn recursive_structs() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        struct Node {
+            parent?: Node
+            children: Node[]
+        }
+        struct X {
+            y: Y
+        }
+        struct Y {
+            x: X
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+        assert!(file_content.contains("  pub parent: Option<Box<Node>>,"), "{}", file_content);
+        assert!(file_content.contains("  pub children: Vec<Node>,"), "{}", file_content);
+        // one box breaks the cycle
+        assert!(file_content.contains("  pub y: Y,"), "{}", file_content);
+        assert!(file_content.contains("  pub x: Box<X>,"), "{}", file_content);
+        Ok(())
+    }
+
+    #[test]
+    fn deprecated() -> Result<()>{
+        let api_file: &'static str = r#"
+        #[@rust.file = "types.rs"]
+        [deprecated = "use TodoV2"]
+        struct Todo {
+            [deprecated]
+            tag: string = "none"
+        }
+        enum Status {
+            Open
+            [deprecated = "use Open"]
+            Active
+        }
+        [deprecated = "use TodoV2Usecase"]
+        usecase Todo {
+            [deprecated = "use listTodosV2"]
+            listTodos -> Todo[]
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs("rust");
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("types.rs").unwrap();
+        assert!(file_content.contains("#[deprecated(note = \"use TodoV2\")]\npub struct Todo {"), "{}", file_content);
+        assert!(file_content.contains("#[deprecated]\n  pub tag: String,"), "{}", file_content);
+        assert!(file_content.contains("#[allow(deprecated)]\nimpl Default for Todo {"), "{}", file_content);
+        assert!(file_content.contains("  Open,\n  #[deprecated(note = \"use Open\")]\n  Active,"), "{}", file_content);
+        assert!(file_content.contains("#[allow(deprecated)]\n#[deprecated(note = \"use TodoV2Usecase\")]\npub trait TodoUsecase {"), "{}", file_content);
+        assert!(file_content.contains("  #[deprecated(note = \"use listTodosV2\")]\n  fn list_todos(&self)"), "{}", file_content);
+        Ok(())
+    }
+}
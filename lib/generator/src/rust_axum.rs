@@ -2,7 +2,7 @@ use std::{cell::RefCell, collections::HashMap, hash::Hash, path::PathBuf};
 
 use anyhow::{ bail, Ok, Result};
 use convert_case::{Case, Casing};
-use cronus_spec::{RawUsecase, RawUsecaseMethod, RawUsecaseMethodRestOption, RustAxumGeneratorOption};
+use cronus_spec::{RawError, RawSchema, RawUsecase, RawUsecaseMethod, RawUsecaseMethodRestOption, RustAxumGeneratorOption};
 use tracing::{span, Level};
 
 use crate::{utils::{self, get_path_from_optional_parent, get_request_name, get_usecase_name}, Ctxt, Generator};
@@ -34,12 +34,20 @@ impl Generator for RustAxumGenerator {
     }
 
     fn generate_usecase(&self, ctx: &Ctxt, usecase_name: &str, usecase: &cronus_spec::RawUsecase) -> anyhow::Result<()> {
+        // the declared errors of the usecase are responded with their status codes
+        let errors = utils::get_usecase_errors(ctx, usecase)?;
+        if !errors.is_empty() {
+            ctx.append_file(self.name(), &self.dst(ctx), &gen_error_into_response(&utils::get_usecase_error_name(ctx, usecase_name), &errors));
+        }
+
 
         for (method_name, method) in &usecase.methods {
             match method.option {
                 Some(ref option) => {
                     if let Some(rest) = &option.rest {
-                        self.generate_method(ctx, usecase_name,usecase,&method_name, method, rest)?;
+                        let method = &utils::resolve_method_spreads(ctx, method)?;
+                        utils::check_readonly_params(method)?;
+                        self.generate_method(ctx, usecase_name,usecase,method_name, method, rest)?;
                     }
                 },
                 None => {},
@@ -58,20 +66,39 @@ impl Generator for RustAxumGenerator {
 
 }
 
-fn gen_method_query_struct(method: &RawUsecaseMethod, query_type:&str) -> Option<String> {
+fn gen_method_query_struct(ctx: &Ctxt, method: &RawUsecaseMethod, query_type:&str) -> Result<Option<String>> {
     let mut query_params: Vec<String> = Vec::new();
+    let mut default_fns = String::new();
     if let Some(req) = &method.req {
         if let Some(properties) = &req.properties {
             for (name, schema) in properties {
                 if let Some(option) = &schema.option {
                     if let Some(rest_option) = &option.rest {
                         if rest_option.query.unwrap_or(false) {
-                            let ty = if let Some(t) = utils::spec_ty_to_rust_builtin_ty(schema.ty.as_ref().unwrap()) {
+                            let Some(spec_ty) = &schema.ty else {
+                                bail!("array or map property '{}' cannot be used as query parameter", name)
+                            };
+                            let ty = if let Some(t) = utils::spec_ty_to_rust_builtin_ty(ctx, spec_ty) {
                                 t
                             } else {
-                               schema.ty.as_ref().unwrap().clone()
+                               spec_ty.clone()
+                            };
+                            // the query parameter is named as the property on the wire
+                            let ident = utils::rust_ident(name);
+                            let wire_name = utils::get_wire_name(ctx, name, schema).unwrap_or_else(|| name.clone());
+                            let rename = if wire_name != ident.trim_start_matches("r#") {
+                                format!("#[serde(rename = \"{}\")]\n    ", wire_name)
+                            } else {
+                                String::new()
                             };
-                            query_params.push(format!("pub {}: {}", name, ty));
+                            // absent query parameter falls back to the default value of the property
+                            if let Some(default) = utils::get_default_value(ctx, schema)? {
+                                let default_fn = format!("default_{}_{}", query_type.to_case(Case::Snake), name.to_case(Case::Snake));
+                                default_fns += &format!("fn {}() -> {} {{\n  {}\n}}\n", default_fn, ty, utils::default_value_to_rust_literal(&default));
+                                query_params.push(format!("{}#[serde(default = \"{}\")]\n    pub {}: {}", rename, default_fn, ident, ty));
+                            } else {
+                                query_params.push(format!("{}pub {}: {}", rename, ident, ty));
+                            }
                         }
                     }
                 }
@@ -82,17 +109,48 @@ fn gen_method_query_struct(method: &RawUsecaseMethod, query_type:&str) -> Option
     if !query_params.is_empty() {
         let struct_def = format!(
             "#[derive(Debug, Serialize, Deserialize)]\n\
-            pub struct {} {{\n    {}\n}}\n",
+            pub struct {} {{\n    {}\n}}\n{}",
             query_type,
-            query_params.join(",\n    ")
+            query_params.join(",\n    "),
+            default_fns
         );
-        Some(struct_def)
+        Ok(Some(struct_def))
     } else {
-        None
+        Ok(None)
     }
 }
 
-fn get_method_path_names_and_tys(method: &RawUsecaseMethod) -> Result<Option<(Vec<String>, Vec<String>)>> {
+/// Generate the IntoResponse of the error enum of a usecase, mapping each declared error to its status code
+///
+/// The body of the response, {"code": ..., "message": ...}, is the data of the error event of a stream method as well.
+fn gen_error_into_response(error_ty: &str, errors: &[(&str, &RawError)]) -> String {
+    let mut arms = String::new();
+    for (name, error) in errors {
+        arms += &format!("            {}::{}(_) => (StatusCode::from_u16({}).unwrap(), \"{}\"),\n", error_ty, name.to_case(Case::UpperCamel), error.status(), name);
+    }
+    format!(r#"
+impl {error_ty} {{
+    fn status_and_body(&self) -> (StatusCode, serde_json::Value) {{
+        let (status, code) = match self {{
+{arms}            {error_ty}::Other(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Internal"),
+        }};
+        let mut err_obj = serde_json::Map::new();
+        err_obj.insert("code".to_owned(), serde_json::Value::from(code));
+        err_obj.insert("message".to_owned(), serde_json::Value::from(self.to_string()));
+        (status, serde_json::Value::Object(err_obj))
+    }}
+}}
+
+impl IntoResponse for {error_ty} {{
+    fn into_response(self) -> axum::response::Response {{
+        let (status, body) = self.status_and_body();
+        (status, Json(body)).into_response()
+    }}
+}}
+"#)
+}
+
+fn get_method_path_names_and_tys(ctx: &Ctxt, method: &RawUsecaseMethod) -> Result<Option<(Vec<String>, Vec<String>)>> {
     let path_params = utils::get_path_params(method);
     let mut struct_fields: Vec<String> = Vec::new();
     let mut struct_tys: Vec<String> = Vec::new();
@@ -104,11 +162,11 @@ fn get_method_path_names_and_tys(method: &RawUsecaseMethod) -> Result<Option<(Ve
 
                 let ty: String;
 
-                if prop_schema.items.is_some() {
-                    bail!("array property cannot be used as path variable")
+                if prop_schema.items.is_some() || prop_schema.values.is_some() {
+                    bail!("array or map property cannot be used as path variable")
                 }
 
-                if let Some(t) = utils::spec_ty_to_rust_builtin_ty(prop_schema.ty.as_ref().unwrap()) {
+                if let Some(t) = utils::spec_ty_to_rust_builtin_ty(ctx, prop_schema.ty.as_ref().unwrap()) {
                     ty = t;
                 } else {
                     ty = prop_schema.ty.as_ref().unwrap().clone();
@@ -126,6 +184,23 @@ fn get_method_path_names_and_tys(method: &RawUsecaseMethod) -> Result<Option<(Ve
 
 }
 
+/// The rust type of the request of a method, a named type (createTodo NewTodo) is used as is
+fn get_method_request_ty(ctx: &Ctxt, method_name: &str, req: &RawSchema) -> String {
+    if utils::is_named_ty(req) {
+        get_rust_ty(ctx, req)
+    } else {
+        get_request_name(ctx, method_name)
+    }
+}
+
+fn get_rust_ty(ctx: &Ctxt, schema: &RawSchema) -> String {
+    match (&schema.items, &schema.ty) {
+        (Some(items), _) => format!("Vec<{}>", get_rust_ty(ctx, items)),
+        (None, Some(ty)) => utils::spec_ty_to_rust_builtin_ty(ctx, ty).unwrap_or(utils::get_rust_ty_path(ty)),
+        (None, None) => unreachable!(),
+    }
+}
+
 fn geenerate_usecase_method_query_type(usecase_name:&str, method_name: &str) -> String {
     format!("{}_{}_Query", usecase_name, method_name,).to_case(convert_case::Case::UpperCamel)
 }
@@ -149,18 +224,34 @@ impl RustAxumGenerator {
     /// Ex. PathExtractor(post_id): PathExtractor<Uuid>
     fn generate_method(&self, ctx: &Ctxt, usecase_name:&str, usecase: &RawUsecase, method_name: &str, method: &cronus_spec::RawUsecaseMethod, rest: &cronus_spec::RawUsecaseMethodRestOption) -> Result<()> {
 
-        let mut result = "pub async fn ".to_owned();
+        // only the fields of a struct can be filled in from the path or the query
+        if let Some(req) = &method.req {
+            if utils::is_named_ty(req) && !utils::is_struct_ty(ctx, req) && (rest.method == "get" || utils::get_path_params(method).is_some()) {
+                bail!("the input of method '{}' has to be a struct to be bound from the path or the query", method_name)
+            }
+        }
+
+        let mut result = String::new();
+        // a deprecated endpoint is still served, and the deprecated fields of a request still filled in
+        let sets_deprecated_fields = method.req.iter()
+            .flat_map(|req| req.properties.iter().flatten())
+            .any(|(_, prop)| utils::get_schema_deprecated(prop).is_some());
+        if utils::get_method_deprecated(method).is_some() || utils::get_usecase_deprecated(usecase).is_some() || sets_deprecated_fields {
+            result += "#[allow(deprecated)]\n";
+        }
+        result += "pub async fn ";
         let fn_name = method_name.to_case(convert_case::Case::Snake);
         result += &fn_name;
         result += &format!("(State(state): State<std::sync::Arc<Usecases>>");
 
         let mut has_path_or_query  = false;
         // handle path parameters
-        match get_method_path_names_and_tys(method)? {
+        match get_method_path_names_and_tys(ctx, method)? {
             Some((props, tys)) => {
                 result += ", ";
+                let idents: Vec<String> = props.iter().map(|prop| utils::rust_ident(prop)).collect();
                 result += &format!(" axum::extract::Path(({})): axum::extract::Path<({})>",
-                    props.join(","),
+                    idents.join(","),
                     tys.join(",")
                 );
                 has_path_or_query = true;
@@ -170,7 +261,7 @@ impl RustAxumGenerator {
 
         // handle query parameters
         let query_ty = geenerate_usecase_method_query_type(usecase_name, method_name);
-        match gen_method_query_struct(method, &query_ty) {
+        match gen_method_query_struct(ctx, method, &query_ty)? {
             Some(query_struct) => {
                 // add struct definition to file
                 ctx.append_file(self.name(), &self.dst(ctx), &query_struct);
@@ -180,7 +271,8 @@ impl RustAxumGenerator {
             None => {},
         }
 
-        if method.req.is_some() {
+        let request_ty = method.req.as_ref().map(|req| get_method_request_ty(ctx, method_name, req));
+        if let Some(request_ty) = &request_ty {
             // If method request has some and method is not get
             if rest.method != "get" {
                 if has_path_or_query {
@@ -189,7 +281,7 @@ impl RustAxumGenerator {
                 } else {
                     result += ", Json(request): Json<";
                 }
-                result += &get_request_name(ctx, method_name);
+                result += request_ty;
                 result += ">"
             }
 
@@ -199,21 +291,29 @@ impl RustAxumGenerator {
 
         }
 
-        result += ") -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {\n";
-        if method.req.is_some() && rest.method == "get" {
+        // the error enum of the usecase is a response of its own
+        let has_errors = !utils::get_method_errors(ctx, method)?.is_empty();
+        let (err_ty, into_err) = if has_errors {
+            ("axum::response::Response", ".into_response()")
+        } else {
+            ("(StatusCode, Json<serde_json::Value>)", "")
+        };
+        result += &format!(") -> Result<impl IntoResponse, {}> {{\n", err_ty);
+        if let (Some(request_ty), "get") = (&request_ty, rest.method.as_str()) {
             // creating the request by our own
-            result += &format!("let request = {} {{\n", &get_request_name(ctx, method_name));
+            result += &format!("let request = {} {{\n", request_ty);
 
         }
 
         // handle request's path & query assignment
         // request.xxx = xxx
-        match get_method_path_names_and_tys(method)? {
+        match get_method_path_names_and_tys(ctx, method)? {
             Some((props, tys)) => {
                 if rest.method != "get" {
                     let stmts:Vec<String> = props.iter()
                     .map(|prop| {
                         let required = method.req.as_ref().unwrap().properties.as_ref().unwrap().get(prop).unwrap().required.unwrap_or(false);
+                        let prop = utils::rust_ident(prop);
     
                         if required {
                             format!("request.{} = {};", prop, prop).to_string()
@@ -227,7 +327,7 @@ impl RustAxumGenerator {
                 } else {
                     for prop in &props {
                         // using the short hand style to assign object prop (since our path name and prop name are the same)
-                        result += &format!("{},\n", prop)
+                        result += &format!("{},\n", utils::rust_ident(prop))
                     }
                 }
 
@@ -240,11 +340,21 @@ impl RustAxumGenerator {
             Some(params) => {
                 if rest.method != "get" {
                     let stmts:Vec<String> = params.iter()
-                    .map(|prop| format!("request.{} = Some(query.{});", prop, prop).to_string())
+                    .map(|prop| {
+                        let prop_schema = method.req.as_ref().unwrap().properties.as_ref().unwrap().get(prop).unwrap();
+                        let prop = utils::rust_ident(prop);
+                        // a property with a default value is not an Option in the request
+                        if prop_schema.required.unwrap_or(false) || prop_schema.default.is_some() {
+                            format!("request.{} = query.{};", prop, prop)
+                        } else {
+                            format!("request.{} = Some(query.{});", prop, prop)
+                        }
+                    })
                     .collect();
                     result += &stmts.join("\n");
                 } else {
                     for prop in params.iter() {
+                        let prop = utils::rust_ident(prop);
                         result += &format!("{}:query.{},\n", prop, prop);
                     }
                 }
@@ -257,19 +367,59 @@ impl RustAxumGenerator {
             result += "};\n";
         }
 
+        // reject the request if it breaks the constraints of its properties
+        if let Some(req) = &method.req {
+            if utils::schema_has_constraints(ctx, req)? {
+                result += &format!(r#"
+    if let Err(err) = validator::Validate::validate(&request) {{
+        let mut err_obj = serde_json::Map::new();
+        err_obj.insert("message".to_owned(), serde_json::Value::from(err.to_string()));
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(serde_json::Value::Object(err_obj))){});
+    }}
+"#, into_err);
+            }
+        }
+
         let req_var = if method.req.is_some() { "request" } else { ""};
-        result += &format!(r#"
-    match state.{}.{}({}).await {{
-        Ok(res) => {{
-            Ok(Json(res))
-        }},
-        Err(err) => {{
+        let err_arm = if has_errors {
+            "Err(err) => Err(err.into_response()),"
+        } else {
+            r#"Err(err) => {
             let mut err_obj = serde_json::Map::new();
             err_obj.insert("message".to_owned(), serde_json::Value::from(err.to_string()));
             Err((StatusCode::BAD_REQUEST, Json(serde_json::Value::Object(err_obj))))
+        },"#
+        };
+        if utils::is_stream_method(method) && has_errors {
+            // the items fail with the error enum of the usecase, which is sent as the body of its error response
+            result += &format!(r#"
+    let stream = state.{}.{}({}).await;
+    let events = futures::StreamExt::map(stream, |item: Result<_, {}>| match item {{
+        Ok(res) => axum::response::sse::Event::default().json_data(res),
+        Err(err) => axum::response::sse::Event::default().event("error").json_data(err.status_and_body().1),
+    }});
+    Ok(axum::response::sse::Sse::new(events).keep_alive(axum::response::sse::KeepAlive::default()))
+"#, usecase_name.to_case(Case::Snake), fn_name, req_var, utils::get_usecase_error_name(ctx, usecase_name));
+        } else if utils::is_stream_method(method) {
+            // the responses are sent as server-sent events, an error as the event named error
+            result += &format!(r#"
+    let stream = state.{}.{}({}).await;
+    let events = futures::StreamExt::map(stream, |item| match item {{
+        Ok(res) => axum::response::sse::Event::default().json_data(res),
+        Err(err) => Ok(axum::response::sse::Event::default().event("error").data(err.to_string())),
+    }});
+    Ok(axum::response::sse::Sse::new(events).keep_alive(axum::response::sse::KeepAlive::default()))
+"#, usecase_name.to_case(Case::Snake), fn_name, req_var);
+        } else {
+            result += &format!(r#"
+    match state.{}.{}({}).await {{
+        Ok(res) => {{
+            Ok(Json(res))
         }},
+        {}
         }}
-    "#, usecase_name.to_case(Case::Snake), fn_name, req_var);
+    "#, usecase_name.to_case(Case::Snake), fn_name, req_var, err_arm);
+        }
         result += "}\n";
         ctx.append_file(self.name(), &self.dst(ctx), &result);
 
@@ -362,6 +512,9 @@ use axum::{
         .for_each(|(name, usecase)|{
             // usecase that contains at least one method that is open to REST
             let usecase_name = get_usecase_name(ctx, name);
+            if utils::get_usecase_deprecated(usecase).is_some() {
+                result += "  #[allow(deprecated)]\n";
+            }
             result += &format!("  pub {}: std::sync::Arc<dyn {} + Send + Sync>,\n", name.to_case(Case::Snake), usecase_name);
         });
 
@@ -432,6 +585,168 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_axum_named_request() -> Result<()> {
+        let api_file: &'static str = r#"
+        struct Todo {
+            id: string
+            title: string
+        }
+        usecase todo {
+            [rest.path = "todos/:id"]
+            [rest.method = "put"]
+            updateTodo Todo -> Todo
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustAxumGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("handler.rs").unwrap();
+        assert!(file_content.contains("Json(mut request): Json<Todo>"), "{}", file_content);
+        assert!(file_content.contains("request.id = id;"), "{}", file_content);
+        Ok(())
+    }
+
+    #[test]
+    fn test_axum_query_default() -> Result<()> {
+        let api_file: &'static str = r#"
+        usecase abc {
+            [rest.path = "abcd"]
+            [rest.method = "get"]
+            list_abcd {
+                [rest.query]
+                limit: u32 = 20
+            } -> {
+                b: string
+            }
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustAxumGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("handler.rs").unwrap();
+        assert!(file_content.contains("#[serde(default = \"default_abc_list_abcd_query_limit\")]"));
+        assert!(file_content.contains("fn default_abc_list_abcd_query_limit() -> u32 {\n  20\n}"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_axum_validate_request() -> Result<()> {
+        let api_file: &'static str = r#"
+        usecase abc {
+            [rest.path = "abcd"]
+            [rest.method = "post"]
+            create_abcd {
+                [max_len = 10]
+                a: string
+            } -> {
+                b: string
+            }
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustAxumGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("handler.rs").unwrap();
+        assert!(file_content.contains("validator::Validate::validate(&request)"));
+        assert!(file_content.contains("StatusCode::UNPROCESSABLE_ENTITY"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_axum_declared_errors() -> Result<()> {
+        let api_file: &'static str = r#"
+        [rest.status = 404]
+        error NotFound
+
+        usecase abc {
+            [rest.path = "abcd"]
+            [rest.method = "post"]
+            create_abcd {
+                a: string
+            } -> {
+                b: string
+            } errors(NotFound)
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustAxumGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("handler.rs").unwrap();
+        assert!(file_content.contains("impl IntoResponse for AbcUsecaseError {"));
+        assert!(file_content.contains("AbcUsecaseError::NotFound(_) => (StatusCode::from_u16(404).unwrap(), \"NotFound\"),"));
+        assert!(file_content.contains("-> Result<impl IntoResponse, axum::response::Response> {"));
+        assert!(file_content.contains("Err(err) => Err(err.into_response()),"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_axum_stream() -> Result<()> {
+        let api_file: &'static str = r#"
+        usecase abc {
+            [rest.path = "abcd"]
+            [rest.method = "get"]
+            watch_abcd {} -> stream {
+                b: string
+            }
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustAxumGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("handler.rs").unwrap();
+        assert!(file_content.contains("let stream = state.abc.watch_abcd(request).await;"));
+        assert!(file_content.contains("Ok(axum::response::sse::Sse::new(events)"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_axum_stream_errors() -> Result<()> {
+        let api_file: &'static str = r#"
+        [rest.status = 404]
+        error NotFound
+
+        usecase abc {
+            [rest.path = "abcd"]
+            [rest.method = "get"]
+            watch_abcd {} -> stream {
+                b: string
+            } errors(NotFound)
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let g = RustAxumGenerator::new();
+        run_generator(&g, &ctx)?;
+        let gfs = ctx.get_gfs(g.name());
+        let gfs_borrow = gfs.borrow();
+        let file_content = gfs_borrow.get("handler.rs").unwrap();
+        assert!(file_content.contains("|item: Result<_, AbcUsecaseError>| match item {"), "{}", file_content);
+        assert!(file_content.contains("Err(err) => axum::response::sse::Event::default().event(\"error\").json_data(err.status_and_body().1),"), "{}", file_content);
+        Ok(())
+    }
+
     #[test]
     fn test_axum_no_path_var() -> Result<()> {
         let api_file: &'static str = r#"
@@ -1,21 +1,25 @@
 
 
+use std::{cell::RefCell, collections::{BTreeMap, HashSet}};
+
 use anyhow::{Ok, Result};
 use convert_case::{Casing, Case};
 use cronus_spec::{RawUsecase, RawSchema};
 use tracing::{span, Level};
 
-use crate::{Generator, Ctxt, utils::{get_request_name, get_usecase_name, get_response_name}};
+use crate::{Generator, Ctxt, utils::{get_request_name, get_usecase_name, is_named_ty, get_response_name, get_schema_parents, resolve_schema_spreads, get_default_value, default_value_to_json, get_alias_imports, get_spec_tys, is_stream_method, spec_ty_to_ts_builtin_ty, get_ts_ty_path, split_namespace, get_schema_description, description_to_comment, get_schema_by_name, get_schema_properties, collect_schema_tys, is_readonly, is_writeonly, get_ts_property_name, get_schema_deprecated, get_usecase_deprecated, get_method_deprecated, ts_deprecated_tag, ts_doc_tags, get_map_keys}};
 
 
 
 pub struct TypescriptGenerator {
+    /// generated interfaces of each namespace, written as its namespace at last
+    namespace_tys: RefCell<BTreeMap<String, String>>
 }
 
 impl TypescriptGenerator {
     pub fn new() -> Self {
         Self {
-           
+            namespace_tys: Default::default()
         }
     }
 }
@@ -25,8 +29,22 @@ impl Generator for TypescriptGenerator {
         return "typescript"
     }
 
+    fn before_all(&self, ctx: &Ctxt) -> Result<()> {
+        // imports of the declared types
+        let alias_imports: Vec<String> = get_alias_imports(ctx, &get_spec_tys(ctx), |alias| alias.typescript.as_ref())
+            .iter()
+            .map(|import| format!("{}\n", import))
+            .collect();
+        ctx.append_file(self.name(), &self.dst(ctx), &alias_imports.concat());
+        Ok(())
+    }
+
     fn generate_schema(&self, ctx: &Ctxt, schema_name:&str, schema: &RawSchema)-> Result<()> {
-        self.generate_schema(ctx, Some(schema_name.to_owned()),  schema);
+        self.generate_schema(ctx, Some(schema_name.to_owned()),  schema, false)?;
+        // the fields sent to the server are in an interface of their own if some are readonly or writeonly
+        if has_input_view(ctx, schema_name, &mut HashSet::new()) {
+            self.generate_schema(ctx, Some(format!("{}Input", schema_name)), schema, true)?;
+        }
         Ok(())
     }
 
@@ -37,15 +55,21 @@ impl Generator for TypescriptGenerator {
         
         let usecase_name = get_usecase_name(ctx, name);
     
-        let mut result = format!("export interface {} {{\n", usecase_name);
+        let doc = get_usecase_deprecated(usecase).map_or(String::new(), |deprecated| ts_doc_tags(&[ts_deprecated_tag(deprecated)], ""));
+        let mut result = format!("{}export interface {} {{\n", doc, usecase_name);
     
         for (method_name, method) in &usecase.methods {
             let method_name_camel = method_name.to_case(Case::Camel);
+            if let Some(deprecated) = get_method_deprecated(method) {
+                result += &ts_doc_tags(&[ts_deprecated_tag(deprecated)], "  ");
+            }
 
             let request_type = match &method.req {
+                // a named type is used as is
+                Some(req) if is_named_ty(req) => schema_to_ts_type(ctx, req, true)?,
                 Some(req) => {
                     let request_type = get_request_name(ctx, &method_name_camel);
-                    self.generate_schema(ctx, Some(request_type.clone()), req, );
+                    self.generate_schema(ctx, Some(request_type.clone()), req, true)?;
                     request_type
                 },
                 None => String::new(),
@@ -53,9 +77,19 @@ impl Generator for TypescriptGenerator {
     
             let response_type = match &method.res {
                 Some(res) => {
-                    let response_type = get_response_name(ctx,  &method_name_camel);
-                    self.generate_schema(ctx, Some(response_type.clone()), res, );
-                    response_type
+                    let response_type = if is_named_ty(res) {
+                        schema_to_ts_type(ctx, res, false)?
+                    } else {
+                        let response_type = get_response_name(ctx,  &method_name_camel);
+                        self.generate_schema(ctx, Some(response_type.clone()), res, false)?;
+                        response_type
+                    };
+                    // a stream method yields its responses one by one
+                    if is_stream_method(method) {
+                        format!("AsyncIterable<{}>", response_type)
+                    } else {
+                        response_type
+                    }
                 },
                 None => "Promise<void>".to_string(),
             };
@@ -75,7 +109,22 @@ impl Generator for TypescriptGenerator {
         Ok(())
     }
 
-    
+    /// Generate each namespace with its interfaces, e.g. billing.Invoice is in
+    ///
+    /// export namespace billing {
+    ///   export interface Invoice { ... }
+    /// }
+    fn after_all(&self, ctx: &Ctxt) -> Result<()> {
+        for (namespace, tys) in self.namespace_tys.borrow().iter() {
+            let mut result = format!("export namespace {} {{\n", namespace);
+            for line in tys.lines() {
+                result += &format!("  {}\n", line);
+            }
+            result += "}\n";
+            ctx.append_file(self.name(), &self.dst(ctx), &result);
+        }
+        Ok(())
+    }
 }
 
 
@@ -95,14 +144,17 @@ impl TypescriptGenerator {
         return "types.ts".to_string();
     }
 
+    /// Generate the interface of a struct, the input one leaves out the readonly properties and the other one the writeonly properties
     pub fn generate_schema(&self,
         ctx: &Ctxt,
         override_name: Option<String>,
         schema: &RawSchema,
-    ) {
+        input: bool,
+    ) -> Result<()> {
         let interface_name: String;
         if let Some(ty) = override_name {
-            interface_name = ty.to_case(Case::UpperCamel);
+            // declared by its name in its namespace
+            interface_name = split_namespace(&ty).1.to_case(Case::UpperCamel);
         } else {
             interface_name = schema.ty.as_ref().unwrap().clone();
         }
@@ -111,29 +163,101 @@ impl TypescriptGenerator {
         // Enter the span, returning a guard object.
         let _enter = span.enter();
 
-        let ts_type = schema_to_ts_type(&schema);
+        let ts_type = schema_to_ts_type(ctx, &resolve_schema_spreads(ctx, schema)?, input)?;
 
-        let result = format!("export interface {} {}\n", interface_name, ts_type);
+        let parents: Vec<String> = get_schema_parents(schema).into_iter().map(|(_, parent)| get_ts_ty_ref(ctx, &parent, input)).collect();
+        let extends = if parents.is_empty() {
+            String::new()
+        } else {
+            format!(" extends {}", parents.join(", "))
+        };
 
-        ctx.append_file(self.name(), &self.dst(ctx), &result);
+        let deprecated_tag = get_schema_deprecated(schema).map(ts_deprecated_tag);
+        let doc = match (get_schema_description(schema), deprecated_tag) {
+            (Some(description), Some(tag)) => format!("/**\n{} *\n * {}\n */\n", description_to_comment(description, " * "), tag),
+            (Some(description), None) => format!("/**\n{} */\n", description_to_comment(description, " * ")),
+            (None, Some(tag)) => ts_doc_tags(&[tag], ""),
+            (None, None) => String::new(),
+        };
+        let result = format!("{}export interface {}{} {}\n", doc, interface_name, extends, ts_type);
+
+        match &schema.namespace {
+            Some(namespace) => *self.namespace_tys.borrow_mut().entry(namespace.clone()).or_default() += &result,
+            None => ctx.append_file(self.name(), &self.dst(ctx), &result),
+        }
 
+        Ok(())
+    }
+}
+
+
+/// The interface of a declared type, the input one (TodoInput) if it has one and the type is sent to the server
+fn get_ts_ty_ref(ctx: &Ctxt, ty: &str, input: bool) -> String {
+    if input && has_input_view(ctx, ty, &mut HashSet::new()) {
+        format!("{}Input", get_ts_ty_path(ty))
+    } else {
+        get_ts_ty_path(ty)
     }
 }
 
+/// Whether a struct has an input interface, as a property of it, of its parents or of the structs it refers to is readonly or writeonly
+fn has_input_view(ctx: &Ctxt, ty: &str, visiting: &mut HashSet<String>) -> bool {
+    let schema = match get_schema_by_name(ctx, ty) {
+        Some(schema) if schema.enum_items.is_none() && schema.alias.is_none() => schema,
+        _ => return false,
+    };
+    if !visiting.insert(ty.to_string()) {
+        return false
+    }
+    let properties = get_schema_properties(ctx, schema).unwrap_or_default();
+    if properties.values().any(|prop| is_readonly(prop) || is_writeonly(prop)) {
+        return true
+    }
+    let mut tys = HashSet::new();
+    for prop in properties.values() {
+        collect_schema_tys(prop, &mut tys);
+    }
+    tys.extend(get_schema_parents(schema).into_iter().map(|(_, parent)| parent));
+    tys.iter().any(|ty| has_input_view(ctx, ty, visiting))
+}
 
 // Helper function to generate TypeScript type from RawSchema
-fn schema_to_ts_type(schema: &RawSchema) -> String {
+fn schema_to_ts_type(ctx: &Ctxt, schema: &RawSchema, input: bool) -> Result<String> {
     if let Some(ref ty) = schema.ty {
-        ty.clone()
+        Ok(spec_ty_to_ts_builtin_ty(ctx, ty).unwrap_or_else(|| get_ts_ty_ref(ctx, ty, input)))
     } else if let Some(ref items) = schema.items {
-        format!("Array<{}>", schema_to_ts_type(items))
+        Ok(format!("Array<{}>", schema_to_ts_type(ctx, items, input)?))
+    } else if let Some(ref values) = schema.values {
+        let keys = get_map_keys(schema);
+        let key_ty = spec_ty_to_ts_builtin_ty(ctx, keys).unwrap_or_else(|| get_ts_ty_ref(ctx, keys, input));
+        Ok(format!("Record<{}, {}>", key_ty, schema_to_ts_type(ctx, values, input)?))
     } else if let Some(ref properties) = schema.properties {
         let mut props = String::new();
         for (key, value) in properties {
-            props += &format!("  {}: {};\n", key, schema_to_ts_type(value));
+            // a readonly property is not sent to the server, a writeonly one is not sent back
+            if (input && is_readonly(value)) || (!input && is_writeonly(value)) {
+                continue;
+            }
+            let readonly = if is_readonly(value) { "readonly " } else { "" };
+            let key = get_ts_property_name(ctx, key, value);
+            // an optional property can be left out, a nullable one can be null
+            let optional = if value.required == Some(false) { "?" } else { "" };
+            let nullable = if value.nullable.unwrap_or(false) { " | null" } else { "" };
+            let default = get_default_value(ctx, value)?;
+            let tags: Vec<String> = default.iter().map(|default| format!("@default {}", default_value_to_json(default)))
+                .chain(get_schema_deprecated(value).map(ts_deprecated_tag))
+                .collect();
+            props += &ts_doc_tags(&tags, "  ");
+            match default {
+                // the property can be left out, the receiver fills in the default value
+                Some(_) => {
+                    props += &format!("  {}{}?: {}{};\n", readonly, key, schema_to_ts_type(ctx, value, input)?, nullable);
+                },
+                None => props += &format!("  {}{}{}: {}{};\n", readonly, key, optional, schema_to_ts_type(ctx, value, input)?, nullable),
+            }
         }
-        format!("{{\n{}}}", props)
+        Ok(format!("{{\n{}}}", props))
     } else {
-        "any".to_string() // Fallback to 'any' type if no other information is available
+        Ok("any".to_string()) // Fallback to 'any' type if no other information is available
     }
 }
\ No newline at end of file
@@ -1,19 +1,23 @@
 
 
+use std::{cell::RefCell, collections::HashSet};
+
 use convert_case::{Casing, Case};
-use cronus_spec::{RawUsecase, RawUsecaseMethod, RawUsecaseMethodRestOption, RawSchema};
+use cronus_spec::{RawUsecase, RawUsecaseMethod, RawUsecaseMethodRestOption, RawSchema, RawSchemaPropertyFormat};
 
-use crate::{Generator, Ctxt};
+use crate::{Generator, Ctxt, utils::{constraint_number_to_literal, get_alias_imports, get_default_value, get_spec_tys, get_prefixed_ty_name, get_request_name, get_schema_properties, has_constraints, is_named_ty, is_readonly, is_struct_ty, get_ts_property_name, resolve_method_spreads, check_readonly_params, spec_ty_to_ts_builtin_ty, get_schema_deprecated, get_usecase_deprecated, get_method_deprecated, ts_deprecated_tag, ts_doc_tags, string_literal, get_map_keys}};
 use anyhow::{Ok, Result};
 
 
 pub struct TypescriptNestjsGenerator {
+    /// the DTOs of the named request structs, shared by the methods taking the same struct
+    generated_dtos: RefCell<HashSet<String>>,
 }
 
 impl TypescriptNestjsGenerator {
     pub fn new() -> Self {
         Self {
-           
+            generated_dtos: Default::default(),
         }
     }
 }
@@ -23,10 +27,25 @@ impl Generator for TypescriptNestjsGenerator {
         return "typescript_nestjs"
     }
 
+    fn before_all(&self, ctx: &Ctxt) -> Result<()> {
+        let imports = [
+            "import { Body, Controller, Delete, Get, Patch, Post, Put, Query } from '@nestjs/common';",
+            "import * as validator from 'class-validator';",
+        ];
+        // imports of the declared types
+        let alias_imports = get_alias_imports(ctx, &get_spec_tys(ctx), |alias| alias.typescript.as_ref());
+        let imports: Vec<&str> = imports.into_iter().chain(alias_imports.iter().map(String::as_str)).collect();
+        ctx.append_file(self.name(), &self.dst(ctx), &format!("{}\n\n", imports.join("\n")));
+        Ok(())
+    }
+
     fn generate_usecase(&self, ctx: &Ctxt,  name: &str, usecase: &RawUsecase) -> Result<()> {
         let mut nestjs_code = String::new();
     
         // Start of the controller class
+        if let Some(deprecated) = get_usecase_deprecated(usecase) {
+            nestjs_code.push_str(&ts_doc_tags(&[ts_deprecated_tag(deprecated)], ""));
+        }
         nestjs_code.push_str(&format!("@Controller('/{}')\n", name.to_lowercase()));
         nestjs_code.push_str(&format!("export class {}Controller {{\n", name.to_case(Case::UpperCamel)));
     
@@ -34,7 +53,9 @@ impl Generator for TypescriptNestjsGenerator {
             // Generate methods within the controller
             if let Some(options) = &usecase_method.option {
                 if let Some(rest_option) = &options.rest {
-                    nestjs_code.push_str(&self.generate_method(usecase_name, usecase_method, rest_option));
+                    let usecase_method = &resolve_method_spreads(ctx, usecase_method)?;
+                    check_readonly_params(usecase_method)?;
+                    nestjs_code.push_str(&self.generate_method(ctx, usecase_name, usecase_method, rest_option)?);
                 }
             }
             
@@ -54,15 +75,41 @@ impl TypescriptNestjsGenerator {
 
     
 
-    fn generate_method(&self, usecase_name: &str, method: &RawUsecaseMethod, rest_option: &RawUsecaseMethodRestOption) -> String {
+    fn generate_method(&self, ctx: &Ctxt, usecase_name: &str, method: &RawUsecaseMethod, rest_option: &RawUsecaseMethodRestOption) -> Result<String> {
         let mut method_code = String::new();
+
+        // the request is bound to a DTO, so that the nestjs ValidationPipe can check its constraints
+        let request_arg = match &method.req {
+            Some(req) => {
+                // a named struct (createTodo NewTodo) is bound to a DTO of its own name, another named type as is
+                let dto_name = if !is_named_ty(req) {
+                    let dto_name = get_request_name(ctx, usecase_name);
+                    ctx.append_file(self.name(), &self.dst(ctx), &self.generate_dto(ctx, req, &dto_name)?);
+                    dto_name
+                } else if is_struct_ty(ctx, req) {
+                    let dto_name = get_prefixed_ty_name(req.ty.as_ref().unwrap());
+                    if self.generated_dtos.borrow_mut().insert(dto_name.clone()) {
+                        ctx.append_file(self.name(), &self.dst(ctx), &self.generate_dto(ctx, req, &dto_name)?);
+                    }
+                    dto_name
+                } else {
+                    map_ts_type(ctx, req)
+                };
+                let source = if rest_option.method.eq_ignore_ascii_case("get") { "Query" } else { "Body" };
+                format!("@{}() request: {}", source, dto_name)
+            },
+            None => String::new(),
+        };
     
         // Generate NestJS method code
+        if let Some(deprecated) = get_method_deprecated(method) {
+            method_code.push_str(&ts_doc_tags(&[ts_deprecated_tag(deprecated)], "    "));
+        }
         method_code.push_str(&format!("    @{}('{}')\n", &rest_option.method.to_case(Case::UpperCamel), rest_option.path.clone().unwrap_or("".to_string())));
-        method_code.push_str(&format!("    async {}() {{\n", usecase_name.to_case(Case::Camel)));
+        method_code.push_str(&format!("    async {}({}) {{\n", usecase_name.to_case(Case::Camel), request_arg));
         method_code.push_str("        // Handler logic here\n");
         method_code.push_str("    }\n");
-        method_code
+        Ok(method_code)
     }
 
     fn dst(&self, ctx: &Ctxt) -> String {
@@ -79,49 +126,82 @@ impl TypescriptNestjsGenerator {
     }
 
 
-    pub fn generate_dto(&self, schema: &RawSchema, dto_name: &str) -> String {
+    /// Generate the request DTO class of a method, with class-validator decorators for the property constraints
+    fn generate_dto(&self, ctx: &Ctxt, schema: &RawSchema, dto_name: &str) -> Result<String> {
         let mut dto_code = format!("export class {} {{\n", dto_name);
-    
-        if let Some(ty) = &schema.ty {
-            dto_code.push_str(&map_field("type", schema, false));
-        }
-    
-        if let Some(items) = &schema.items {
-            let nested_dto_name = format!("{}Item", dto_name);
-            dto_code.push_str(&self.generate_dto(items, &nested_dto_name));
-            dto_code.push_str(&format!("    items: {}[];\n", nested_dto_name));
-        }
-    
-        if let Some(properties) = &schema.properties {
-            for (key, prop_schema) in properties {
-                let is_optional = prop_schema.required.unwrap_or(false);
-                dto_code.push_str(&map_field(key, prop_schema, is_optional));
+
+        for (key, prop_schema) in get_schema_properties(ctx, schema)? {
+            // a readonly property is not read from the request
+            if is_readonly(&prop_schema) {
+                continue;
             }
+            let is_optional = !prop_schema.required.unwrap_or(false) || get_default_value(ctx, &prop_schema)?.is_some();
+            if let Some(deprecated) = get_schema_deprecated(&prop_schema) {
+                dto_code.push_str(&ts_doc_tags(&[ts_deprecated_tag(deprecated)], "    "));
+            }
+            for decorator in get_field_decorators(&prop_schema, is_optional) {
+                dto_code.push_str(&format!("    @validator.{}\n", decorator));
+            }
+            dto_code.push_str(&map_field(ctx, &key, &prop_schema, is_optional));
         }
-    
-        if let Some(enum_items) = &schema.enum_items {
-            // Handle enum items
-        }
-    
-        // Handle other fields like extends, flat_extends, etc.
-    
+
         dto_code.push_str("}\n\n");
-        dto_code
+        Ok(dto_code)
     }
-    
+}
 
-    
+fn map_ts_type(ctx: &Ctxt, schema: &RawSchema) -> String {
+    if let Some(items) = &schema.items {
+        return format!("Array<{}>", map_ts_type(ctx, items));
+    }
+    if let Some(values) = &schema.values {
+        let keys = get_map_keys(schema);
+        return format!("Record<{}, {}>", spec_ty_to_ts_builtin_ty(ctx, keys).unwrap_or_else(|| keys.to_string()), map_ts_type(ctx, values));
+    }
+    match &schema.ty {
+        Some(ty) => spec_ty_to_ts_builtin_ty(ctx, ty).unwrap_or_else(|| ty.clone()),
+        None => "any".to_string(),
+    }
 }
 
-fn map_field(field_name: &str, schema: &RawSchema, is_optional: bool) -> String {
-    let ts_type = match schema.ty.as_deref() {
-        Some("string") => "string",
-        Some("number") => "number",
-        Some("boolean") => "boolean",
-        Some("object") => "any", // Or map to a specific object type if possible
-        Some("array") => "Array<any>", // Or map to a specific array type if possible
-        _ => "any"
-    };
+fn map_field(ctx: &Ctxt, field_name: &str, schema: &RawSchema, is_optional: bool) -> String {
+    let nullable = if schema.nullable.unwrap_or(false) { " | null" } else { "" };
+    format!("    {}{}: {}{};\n", get_ts_property_name(ctx, field_name, schema), if is_optional { "?" } else { "" }, map_ts_type(ctx, schema), nullable)
+}
 
-    format!("    {}: {}{};\n", field_name, ts_type, if is_optional { "?" } else {""})
-}
\ No newline at end of file
+/// Get the class-validator decorators for the constraints of a property
+fn get_field_decorators(schema: &RawSchema, is_optional: bool) -> Vec<String> {
+    let mut decorators = Vec::new();
+    let option = match &schema.option {
+        Some(option) if has_constraints(schema) => option,
+        _ => return decorators,
+    };
+    if is_optional {
+        decorators.push("IsOptional()".to_string());
+    } else if schema.nullable.unwrap_or(false) {
+        // null is allowed, but the property has to be given
+        decorators.push("ValidateIf((_, value) => value !== null)".to_string());
+    }
+    if let Some(min) = option.min {
+        decorators.push(format!("Min({})", constraint_number_to_literal(min)));
+    }
+    if let Some(max) = option.max {
+        decorators.push(format!("Max({})", constraint_number_to_literal(max)));
+    }
+    let is_array = schema.items.is_some();
+    if let Some(min_len) = option.min_len {
+        decorators.push(format!("{}({})", if is_array { "ArrayMinSize" } else { "MinLength" }, min_len));
+    }
+    if let Some(max_len) = option.max_len {
+        decorators.push(format!("{}({})", if is_array { "ArrayMaxSize" } else { "MaxLength" }, max_len));
+    }
+    if let Some(pattern) = &option.pattern {
+        decorators.push(format!("Matches(new RegExp({}))", string_literal(pattern)));
+    }
+    match option.format {
+        Some(RawSchemaPropertyFormat::Email) => decorators.push("IsEmail()".to_string()),
+        Some(RawSchemaPropertyFormat::Url) => decorators.push("IsUrl()".to_string()),
+        None => {}
+    }
+    decorators
+}
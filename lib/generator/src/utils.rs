@@ -1,103 +1,223 @@
-use std::{collections::HashSet, path::{Path, PathBuf}};
+use std::{collections::{HashMap, HashSet}, path::{Path, PathBuf}, sync::Arc};
 
+use anyhow::{bail, Result};
 use convert_case::{Casing, Case};
-use cronus_spec::{RawSchema, RawUsecase, RawUsecaseMethod};
+use cronus_spec::{DefLoc, RawDeprecated, RawError, RawSchema, RawSchemaEnumItem, RawSchemaAlias, RawSchemaAliasTarget, RawSchemaDefaultValue, RawUsecase, RawUsecaseMethod};
 
-use crate::Ctxt;
+use crate::{openapi_utils::SchemaObject, Ctxt};
 
 
-pub fn spec_ty_to_rust_builtin_ty(spec_ty: &str) -> Option<String> {
-    if spec_ty == "string" {
-        return Some("String".to_string());
-    }
-    else if spec_ty == "integer" || spec_ty == "int" || spec_ty == "i32" {
-        return Some("i32".to_string());
-    }
-    else if spec_ty == "u32" {
-        return Some("u32".to_string());
+/// The built-in scalar types of the spec, each generator maps every one of them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scalar {
+    String,
+    Bool,
+    I32,
+    U32,
+    I64,
+    U64,
+    F32,
+    F64,
+    Decimal,
+    /// RFC 3339 date and time
+    DateTime,
+    /// ISO 8601 date, e.g. 2024-01-31
+    Date,
+    /// ISO 8601 duration, e.g. PT1H30M
+    Duration,
+    Uuid,
+    /// Base64 encoded on the wire
+    Bytes,
+    /// Any JSON value
+    Any,
+}
+
+impl Scalar {
+    pub fn parse(spec_ty: &str) -> Option<Scalar> {
+        let scalar = match spec_ty {
+            "string" => Scalar::String,
+            "bool" | "boolean" => Scalar::Bool,
+            "i32" | "int" | "integer" => Scalar::I32,
+            "u32" => Scalar::U32,
+            "i64" | "int64" => Scalar::I64,
+            "u64" | "uint64" => Scalar::U64,
+            "f32" => Scalar::F32,
+            "f64" | "float" | "double" | "number" => Scalar::F64,
+            "decimal" => Scalar::Decimal,
+            "datetime" => Scalar::DateTime,
+            "date" => Scalar::Date,
+            "duration" => Scalar::Duration,
+            "uuid" => Scalar::Uuid,
+            "bytes" => Scalar::Bytes,
+            "any" => Scalar::Any,
+            _ => return None,
+        };
+        Some(scalar)
     }
-    else if spec_ty == "bool" || spec_ty == "boolean" {
-        return Some("bool".to_string());
+
+    /// Whether a default value can be given to a property of this type
+    pub fn supports_default(&self) -> bool {
+        matches!(self, Scalar::String | Scalar::Bool | Scalar::I32 | Scalar::U32 | Scalar::I64 | Scalar::U64 | Scalar::F32 | Scalar::F64)
     }
-    else if spec_ty.starts_with("map<") {
-        let (left_ty, right_ty) = parse_map_type(spec_ty);
-        let left = spec_ty_to_rust_builtin_ty(left_ty).unwrap_or(left_ty.to_case(Case::UpperCamel));
-        let right = spec_ty_to_rust_builtin_ty(right_ty).unwrap_or(right_ty.to_case(Case::UpperCamel));
-        return Some(format!("HashMap<{left},{right}>").to_string())
+
+    /// The package a golang type needs to import
+    pub fn golang_import(&self) -> Option<&'static str> {
+        match self {
+            Scalar::Decimal => Some("github.com/shopspring/decimal"),
+            Scalar::DateTime => Some("time"),
+            Scalar::Uuid => Some("github.com/google/uuid"),
+            _ => None,
+        }
     }
-    return None;
 }
 
-pub fn spec_ty_to_golang_builtin_ty(spec_ty: &str) -> Option<String> {
-    if spec_ty == "string" {
-        return Some("string".to_string());
+pub fn spec_ty_to_rust_builtin_ty(ctx: &Ctxt, spec_ty: &str) -> Option<String> {
+    if let Some(alias) = get_type_alias(ctx, spec_ty) {
+        return Some(alias.rust.as_ref().map_or(split_namespace(spec_ty).1, |target| target.ty()).to_string());
     }
-    else if spec_ty == "integer" || spec_ty == "int" || spec_ty == "i32" {
-        return Some("int32".to_string());
+    if let Some(scalar) = Scalar::parse(spec_ty) {
+        let ty = match scalar {
+            Scalar::String => "String",
+            Scalar::Bool => "bool",
+            Scalar::I32 => "i32",
+            Scalar::U32 => "u32",
+            Scalar::I64 => "i64",
+            Scalar::U64 => "u64",
+            Scalar::F32 => "f32",
+            Scalar::F64 => "f64",
+            Scalar::Decimal => "rust_decimal::Decimal",
+            Scalar::DateTime => "chrono::DateTime<chrono::Utc>",
+            Scalar::Date => "chrono::NaiveDate",
+            // kept as the ISO 8601 string, std::time::Duration has no such serde format
+            Scalar::Duration => "String",
+            Scalar::Uuid => "uuid::Uuid",
+            Scalar::Bytes => "Vec<u8>",
+            Scalar::Any => "serde_json::Value",
+        };
+        return Some(ty.to_string());
     }
-    else if spec_ty == "u32" {
-        return Some("uint32".to_string());
-    }
-    else if spec_ty == "bool" || spec_ty == "boolean" {
-        return Some("bool".to_string());
+    None
+}
+
+pub fn spec_ty_to_golang_builtin_ty(ctx: &Ctxt, spec_ty: &str) -> Option<String> {
+    if let Some(alias) = get_type_alias(ctx, spec_ty) {
+        return Some(alias.golang.as_ref().map_or(split_namespace(spec_ty).1, |target| target.ty()).to_string());
     }
-    else if spec_ty.starts_with("map<") {
-        let (left_ty, right_ty) = parse_map_type(spec_ty);
-        let left = spec_ty_to_golang_builtin_ty(left_ty).unwrap_or(left_ty.to_case(Case::UpperCamel));
-        let right = spec_ty_to_golang_builtin_ty(right_ty).unwrap_or(right_ty.to_case(Case::UpperCamel));
-        return Some(format!("map[{left}]{right}").to_string())
+    if let Some(scalar) = Scalar::parse(spec_ty) {
+        let ty = match scalar {
+            Scalar::String => "string",
+            Scalar::Bool => "bool",
+            Scalar::I32 => "int32",
+            Scalar::U32 => "uint32",
+            Scalar::I64 => "int64",
+            Scalar::U64 => "uint64",
+            Scalar::F32 => "float32",
+            Scalar::F64 => "float64",
+            Scalar::Decimal => "decimal.Decimal",
+            Scalar::DateTime => "time.Time",
+            // kept as the ISO 8601 strings, time.Time and time.Duration use other formats
+            Scalar::Date | Scalar::Duration => "string",
+            Scalar::Uuid => "uuid.UUID",
+            // encoding/json encodes []byte as base64
+            Scalar::Bytes => "[]byte",
+            Scalar::Any => "any",
+        };
+        return Some(ty.to_string());
     }
-    return None;
+    None
 }
 
-pub fn spec_ty_to_py_builtin_ty(spec_ty: &str) -> Option<String> {
-    if spec_ty == "string" {
-        return Some("str".to_string());
-    }
-    else if spec_ty == "integer" || spec_ty == "int" || spec_ty == "i32"|| spec_ty == "number" {
-        return Some("int".to_string());
+pub fn spec_ty_to_py_builtin_ty(ctx: &Ctxt, spec_ty: &str) -> Option<String> {
+    if let Some(alias) = get_type_alias(ctx, spec_ty) {
+        return Some(alias.python.as_ref().map_or(split_namespace(spec_ty).1, |target| target.ty()).to_string());
     }
-    else if spec_ty == "u32" {
-        return Some("int".to_string());
+    if let Some(scalar) = Scalar::parse(spec_ty) {
+        let ty = match scalar {
+            Scalar::String => "str",
+            Scalar::Bool => "bool",
+            Scalar::I32 | Scalar::U32 | Scalar::I64 | Scalar::U64 => "int",
+            Scalar::F32 | Scalar::F64 => "float",
+            Scalar::Decimal => "Decimal",
+            Scalar::DateTime => "datetime",
+            Scalar::Date => "date",
+            Scalar::Duration => "timedelta",
+            Scalar::Uuid => "UUID",
+            Scalar::Bytes => "bytes",
+            Scalar::Any => "Any",
+        };
+        return Some(ty.to_string());
     }
-    else if spec_ty == "bool" || spec_ty == "boolean" {
-        return Some("bool".to_string());
+    None
+}
+
+/// The imports of the python types of the scalars
+pub const PY_SCALAR_IMPORTS: [&str; 4] = [
+    "from datetime import date, datetime, timedelta",
+    "from decimal import Decimal",
+    "from typing import Any",
+    "from uuid import UUID",
+];
+
+pub fn spec_ty_to_ts_builtin_ty(ctx: &Ctxt, spec_ty: &str) -> Option<String> {
+    if let Some(alias) = get_type_alias(ctx, spec_ty) {
+        return Some(alias.typescript.as_ref().map_or(split_namespace(spec_ty).1, |target| target.ty()).to_string());
     }
-    else if spec_ty == "float"  {
-        return Some("float".to_string());
+    if let Some(scalar) = Scalar::parse(spec_ty) {
+        let ty = match scalar {
+            Scalar::Bool => "boolean",
+            Scalar::I32 | Scalar::U32 | Scalar::I64 | Scalar::U64 | Scalar::F32 | Scalar::F64 => "number",
+            // the wire formats of the other scalars are strings
+            Scalar::String | Scalar::Decimal | Scalar::DateTime | Scalar::Date | Scalar::Duration | Scalar::Uuid | Scalar::Bytes => "string",
+            Scalar::Any => "unknown",
+        };
+        return Some(ty.to_string());
     }
-    else if spec_ty.starts_with("map<") {
-        let (left_ty, right_ty) = parse_map_type(spec_ty);
-        let left = spec_ty_to_rust_builtin_ty(left_ty).unwrap_or(left_ty.to_case(Case::UpperCamel));
-        let right = spec_ty_to_rust_builtin_ty(right_ty).unwrap_or(right_ty.to_case(Case::UpperCamel));
-        return Some(format!("dict[{left},{right}]").to_string())
+    None
+}
+
+/// Get the golang packages to import for the given types, the ones of the scalars and the declared types
+pub fn get_golang_imports(ctx: &Ctxt, used_tys: &HashSet<String>) -> Vec<String> {
+    let mut imports = get_alias_imports(ctx, used_tys, |alias| alias.golang.as_ref());
+    for ty in used_tys {
+        if let Some(import) = Scalar::parse(ty).and_then(|scalar| scalar.golang_import()) {
+            imports.push(import.to_string());
+        }
     }
-    return None;
+    imports.sort();
+    imports.dedup();
+    imports
 }
 
-pub fn parse_map_type(map_ty: &str) -> (&str,&str) {
-    let child_types:Vec<&str> = map_ty[4..map_ty.len()-1].split(",").collect();
-    let left_ty = child_types.get(0).unwrap();
-    let right_ty = child_types.get(1).unwrap();
-    return (left_ty, right_ty)
+/// Get the type of the keys of a map (map<K,V>), string by default
+pub fn get_map_keys(schema: &RawSchema) -> &str {
+    schema.keys.as_deref().unwrap_or("string")
 }
 
 
 /// valid openapi builtin types: "array", "boolean", "integer", "number", "object", "string"
-pub fn spec_ty_to_openapi_builtin_ty(spec_ty: &str) -> Option<String> {
-    let result = if spec_ty == "string" {
-         Some("string".to_string())
-    }
-    else if spec_ty == "integer" || spec_ty == "int" || spec_ty == "i32" || spec_ty == "u32" {
-         Some("integer".to_string())
-    }
-    else if spec_ty == "bool" || spec_ty == "boolean" {
-         Some("boolean".to_string())
-    } else {
-        None
+///
+/// The schema of `any` has no type, as every value is valid.
+pub fn spec_ty_to_openapi_builtin_ty(spec_ty: &str) -> Option<SchemaObject> {
+    let (ty, format) = match Scalar::parse(spec_ty)? {
+        Scalar::String => ("string", None),
+        Scalar::Bool => ("boolean", None),
+        Scalar::I32 => ("integer", Some("int32")),
+        Scalar::U32 | Scalar::I64 | Scalar::U64 => ("integer", Some("int64")),
+        Scalar::F32 => ("number", Some("float")),
+        Scalar::F64 => ("number", Some("double")),
+        Scalar::Decimal => ("string", Some("decimal")),
+        Scalar::DateTime => ("string", Some("date-time")),
+        Scalar::Date => ("string", Some("date")),
+        Scalar::Duration => ("string", Some("duration")),
+        Scalar::Uuid => ("string", Some("uuid")),
+        Scalar::Bytes => ("string", Some("byte")),
+        Scalar::Any => return Some(SchemaObject::default()),
     };
 
-    result
+    Some(SchemaObject {
+        format: format.map(str::to_string),
+        ..SchemaObject::new_with_type(ty.to_string())
+    })
 }
 
 /// Extract variables from url path like /abcde/:var1/jdf/:var2 => [var1, var2]
@@ -131,10 +251,611 @@ pub fn get_path_from_optional_parent(par: Option<&Path>, file:Option<&String>, d
     rel_root.join(default_file).to_str().unwrap().to_string()
 }
 
+/// Split a type name into its namespace and name, e.g. billing.Invoice
+pub fn split_namespace(ty_name: &str) -> (Option<&str>, &str) {
+    match ty_name.rsplit_once('.') {
+        Some((namespace, name)) => (Some(namespace), name),
+        None => (None, ty_name),
+    }
+}
+
+/// The name of a type in the languages declaring all the types in one scope (python, golang, openapi),
+/// prefixed by its namespace, e.g. billing.Invoice is BillingInvoice
+pub fn get_prefixed_ty_name(ty_name: &str) -> String {
+    ty_name.split('.').map(|part| part.to_case(Case::UpperCamel)).collect()
+}
+
+/// The path of a type in rust, where a namespace is a module, e.g. billing.Invoice is billing::Invoice
+pub fn get_rust_ty_path(ty_name: &str) -> String {
+    match split_namespace(ty_name) {
+        (Some(namespace), name) => format!("{}::{}", namespace.to_case(Case::Snake), name.to_case(Case::UpperCamel)),
+        (None, name) => name.to_case(Case::UpperCamel),
+    }
+}
+
+/// The path of a type in typescript, where a namespace is a namespace, e.g. billing.Invoice
+pub fn get_ts_ty_path(ty_name: &str) -> String {
+    match split_namespace(ty_name) {
+        (Some(namespace), name) => format!("{}.{}", namespace, name.to_case(Case::UpperCamel)),
+        (None, name) => name.to_case(Case::UpperCamel),
+    }
+}
+
+/// The TSDoc tag of a deprecated item, with the note of what to use instead
+pub fn ts_deprecated_tag(deprecated: &RawDeprecated) -> String {
+    match deprecated.note() {
+        Some(note) => format!("@deprecated {}", note),
+        None => "@deprecated".to_string(),
+    }
+}
+
+/// A TSDoc comment of the given tags, on a single line if there is only one
+pub fn ts_doc_tags(tags: &[String], indent: &str) -> String {
+    match tags {
+        [] => String::new(),
+        [tag] => format!("{}/** {} */\n", indent, tag),
+        tags => format!("{}/**\n{}{} */\n", indent, tags.iter().map(|tag| format!("{} * {}\n", indent, tag)).collect::<String>(), indent),
+    }
+}
+
+/// The name of a property in typescript, which is its name on the wire, quoted if it is not an identifier, e.g. 'created-at'
+pub fn get_ts_property_name(ctx: &Ctxt, prop_name: &str, schema: &RawSchema) -> String {
+    let name = get_wire_name(ctx, prop_name, schema).unwrap_or_else(|| prop_name.to_string());
+    let is_identifier = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier { name } else { format!("'{}'", name) }
+}
+
+/// The keywords of rust, including the reserved ones
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "self", "Self", "static",
+    "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while", "abstract", "become", "box", "do",
+    "final", "gen", "macro", "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// The keywords of python, the soft ones (match, type etc.) can still be names
+const PY_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif",
+    "else", "except", "finally", "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal", "not", "or",
+    "pass", "raise", "return", "try", "while", "with", "yield",
+];
+
+/// The name of a property in rust, a keyword is a raw identifier (r#type), or gets a trailing _ (self_) if it can't be one
+pub fn rust_ident(name: &str) -> String {
+    match name {
+        "self" | "Self" | "super" | "crate" => format!("{}_", name),
+        _ if RUST_KEYWORDS.contains(&name) => format!("r#{}", name),
+        _ => name.to_string(),
+    }
+}
+
+/// The docstring of a python class or function, indented into its body, with its quotes escaped
+pub fn py_docstring(description: &str, indent: &str) -> String {
+    let description = description.replace('\\', "\\\\").replace('"', "\\\"");
+    if description.lines().count() > 1 {
+        format!("{indent}\"\"\"\n{}{indent}\"\"\"\n", description_to_comment(&description, indent))
+    } else {
+        format!("{indent}\"\"\"{}\"\"\"\n", description)
+    }
+}
+
+/// The name of a property in python, which is snake cased, a keyword gets a trailing _ (from_)
+pub fn py_ident(name: &str) -> String {
+    let name = name.to_case(Case::Snake);
+    if PY_KEYWORDS.contains(&name.as_str()) {
+        format!("{}_", name)
+    } else {
+        name
+    }
+}
+
+/// The name of a property in go, which is exported, so never a keyword, and prefixed with X if it can't start a name (X1St)
+pub fn go_ident(name: &str) -> String {
+    let name = name.to_case(Case::UpperCamel);
+    if name.chars().next().is_some_and(|c| c.is_alphabetic()) {
+        name
+    } else {
+        format!("X{}", name)
+    }
+}
+
+/// Fail if two properties of a struct have the same name in the target language after case conversion,
+/// e.g. fooBar and foo_bar are both foo_bar in python
+pub fn check_ident_collisions<'a>(ty_name: &str, prop_names: impl IntoIterator<Item = &'a String>, to_ident: impl Fn(&str) -> String) -> Result<()> {
+    let mut prop_names: Vec<&String> = prop_names.into_iter().collect();
+    prop_names.sort();
+    let mut idents: HashMap<String, &String> = HashMap::new();
+    for prop_name in prop_names {
+        let ident = to_ident(prop_name);
+        if let Some(other) = idents.insert(ident.clone(), prop_name) {
+            bail!("properties '{}' and '{}' of '{}' are both named '{}'", other, prop_name, ty_name, ident)
+        }
+    }
+    Ok(())
+}
+
 pub fn get_schema_by_name<'ctx>(ctx: &'ctx Ctxt, ty_name: &str) -> Option<&'ctx RawSchema> {
     ctx.spec.ty.as_ref().and_then(|tys| tys.get(ty_name))
 }
 
+/// Get the language mappings of a declared type (type Timestamp)
+///
+/// A language without a mapping uses the name of the declared type as is, without its namespace.
+pub fn get_type_alias<'ctx>(ctx: &'ctx Ctxt, ty_name: &str) -> Option<&'ctx RawSchemaAlias> {
+    get_schema_by_name(ctx, ty_name).and_then(|schema| schema.alias.as_ref())
+}
+
+/// Collect the type names a schema refers to, including the ones of its items, map keys and values and properties
+pub fn collect_schema_tys(schema: &RawSchema, tys: &mut HashSet<String>) {
+    if let Some(ty) = &schema.ty {
+        tys.insert(ty.clone());
+    }
+    if schema.values.is_some() {
+        tys.insert(get_map_keys(schema).to_string());
+    }
+    for nested in schema.items.iter().chain(schema.values.iter()) {
+        collect_schema_tys(nested, tys);
+    }
+    for prop in schema.properties.iter().flat_map(|props| props.values()) {
+        collect_schema_tys(prop, tys);
+    }
+}
+
+/// Collect the type names referred to by the types and the usecases of the spec
+pub fn get_spec_tys(ctx: &Ctxt) -> HashSet<String> {
+    let mut tys = HashSet::new();
+    for schema in ctx.spec.ty.iter().flat_map(|tys| tys.values()) {
+        collect_schema_tys(schema, &mut tys);
+    }
+    for usecase in ctx.spec.usecases.iter().flat_map(|usecases| usecases.values()) {
+        for method in usecase.methods.values() {
+            for schema in method.req.iter().chain(method.res.iter()) {
+                collect_schema_tys(schema, &mut tys);
+            }
+        }
+    }
+    tys
+}
+
+/// Get the imports needed by the given declared types for a language, sorted and deduplicated
+///
+/// Only the used types are considered, as an unused import does not compile in some languages (golang).
+pub fn get_alias_imports(ctx: &Ctxt, used_tys: &HashSet<String>, target: impl Fn(&RawSchemaAlias) -> Option<&RawSchemaAliasTarget>) -> Vec<String> {
+    let mut imports: Vec<String> = used_tys.iter()
+        .filter_map(|ty| get_type_alias(ctx, ty))
+        .filter_map(|alias| target(alias).and_then(|target| target.import()))
+        .map(|import| import.to_string())
+        .collect();
+    imports.sort();
+    imports.dedup();
+    imports
+}
+
+/// Get the properties of a struct, including the ones copied from the types spread into it (...Base)
+/// 
+/// The properties declared by the struct itself take precedence over the copied ones.
+pub fn get_schema_properties(ctx: &Ctxt, schema: &RawSchema) -> Result<HashMap<String, RawSchema>> {
+    let mut visiting = HashSet::new();
+    collect_schema_properties(ctx, schema, false, &mut visiting)
+}
+
+fn collect_schema_properties(ctx: &Ctxt, schema: &RawSchema, with_parents: bool, visiting: &mut HashSet<String>) -> Result<HashMap<String, RawSchema>> {
+    let mut properties = HashMap::new();
+
+    // a spread type brings the fields of its parents as well
+    if with_parents {
+        for (_, parent) in get_schema_parents(schema) {
+            properties.extend(collect_named_schema_properties(ctx, &parent, visiting)?);
+        }
+    }
+
+    for spread in schema.flat_extends.iter().flatten() {
+        properties.extend(collect_named_schema_properties(ctx, spread, visiting)?);
+    }
+
+    if let Some(own_properties) = &schema.properties {
+        properties.extend(own_properties.iter().map(|(name, prop)| (name.clone(), prop.clone())));
+    }
+
+    Ok(properties)
+}
+
+fn collect_named_schema_properties(ctx: &Ctxt, ty_name: &str, visiting: &mut HashSet<String>) -> Result<HashMap<String, RawSchema>> {
+    if !visiting.insert(ty_name.to_owned()) {
+        bail!("type '{}' is spread or inherited by itself", ty_name)
+    }
+    let schema = match get_schema_by_name(ctx, ty_name) {
+        Some(schema) => schema,
+        None => bail!("unknown type '{}' to spread or inherit", ty_name),
+    };
+    let properties = collect_schema_properties(ctx, schema, true, visiting)?;
+    visiting.remove(ty_name);
+    Ok(properties)
+}
+
+/// Clone the struct with the fields of the types spread into it (...Base) copied in
+pub fn resolve_schema_spreads(ctx: &Ctxt, schema: &RawSchema) -> Result<RawSchema> {
+    let mut resolved = schema.clone();
+    if schema.flat_extends.is_some() {
+        resolved.properties = Some(get_schema_properties(ctx, schema)?);
+        resolved.flat_extends = None;
+    }
+    Ok(resolved)
+}
+
+/// Clone the method with the spreads of its request resolved, so that every request field is visible
+///
+/// A named request struct (createTodo NewTodo) keeps its type name, along with the fields of the struct and its parents.
+pub fn resolve_method_spreads(ctx: &Ctxt, method: &RawUsecaseMethod) -> Result<RawUsecaseMethod> {
+    let mut resolved = method.clone();
+    resolved.req = match &method.req {
+        Some(req) if is_named_ty(req) => {
+            let mut req = req.clone();
+            if is_struct_ty(ctx, &req) {
+                req.properties = Some(collect_named_schema_properties(ctx, req.ty.as_ref().unwrap(), &mut HashSet::new())?);
+            }
+            Some(req)
+        },
+        Some(req) => Some(resolve_schema_spreads(ctx, req)?),
+        None => None,
+    };
+    Ok(resolved)
+}
+
+/// Check that no readonly field of a resolved method is a path or query parameter, as they are never read from requests
+pub fn check_readonly_params(method: &RawUsecaseMethod) -> Result<()> {
+    let (path_params, query_params, _) = get_pqb(method);
+    let properties = method.req.as_ref().and_then(|req| req.properties.as_ref());
+    for param in path_params.iter().flatten().chain(query_params.iter().flatten()) {
+        if properties.and_then(|properties| properties.get(param)).is_some_and(is_readonly) {
+            bail!("readonly property '{}' can't be a path or query parameter", param)
+        }
+    }
+    Ok(())
+}
+
+/// Whether a property is assigned by the server, sent in responses but never read from requests, [readonly]
+pub fn is_readonly(schema: &RawSchema) -> bool {
+    schema.option.as_ref().and_then(|option| option.readonly).unwrap_or(false)
+}
+
+/// Whether a property is only read from requests and never sent back, [writeonly]
+pub fn is_writeonly(schema: &RawSchema) -> bool {
+    schema.option.as_ref().and_then(|option| option.writeonly).unwrap_or(false)
+}
+
+/// Get the global case of the property names on the wire, #[wire_case = "camel"]
+pub fn get_wire_case(ctx: &Ctxt) -> Option<Case> {
+    ctx.spec.option.as_ref()
+        .and_then(|go| go.wire_case.as_ref())
+        .map(|case| match case {
+            cronus_spec::Case::Camel => Case::Camel,
+            cronus_spec::Case::Snake => Case::Snake,
+            cronus_spec::Case::Pascal => Case::Pascal,
+            cronus_spec::Case::Kebab => Case::Kebab,
+        })
+}
+
+/// Get the serde rename_all of the global wire case, e.g. "camelCase"
+pub fn get_serde_wire_case(ctx: &Ctxt) -> Option<&'static str> {
+    ctx.spec.option.as_ref()
+        .and_then(|go| go.wire_case.as_ref())
+        .map(|case| match case {
+            cronus_spec::Case::Camel => "camelCase",
+            cronus_spec::Case::Snake => "snake_case",
+            cronus_spec::Case::Pascal => "PascalCase",
+            cronus_spec::Case::Kebab => "kebab-case",
+        })
+}
+
+/// Get the name of a property given by its json option, [json = "createdAt"]
+pub fn get_json_name(schema: &RawSchema) -> Option<&str> {
+    schema.option.as_ref().and_then(|option| option.json.as_deref())
+}
+
+/// Get the name of a property on the wire, which is its json option or its name in the global wire case,
+/// None if neither is set and the generator keeps its own naming
+pub fn get_wire_name(ctx: &Ctxt, prop_name: &str, schema: &RawSchema) -> Option<String> {
+    match get_json_name(schema) {
+        Some(json) => Some(json.to_string()),
+        None => get_wire_case(ctx).map(|case| prop_name.to_case(case)),
+    }
+}
+
+/// Whether the input or output of a method is a named type (createTodo NewTodo -> Todo[]) rather than a struct body
+pub fn is_named_ty(schema: &RawSchema) -> bool {
+    schema.ty.is_some() || schema.items.is_some() || schema.values.is_some()
+}
+
+/// Whether a schema refers to a declared struct, rather than to an enum, a declared type, a scalar or an array
+pub fn is_struct_ty(ctx: &Ctxt, schema: &RawSchema) -> bool {
+    schema.items.is_none() && schema.ty.as_ref()
+        .and_then(|ty| get_schema_by_name(ctx, ty))
+        .is_some_and(|schema| schema.enum_items.is_none() && schema.alias.is_none())
+}
+
+/// Get the properties which hold a struct by value and close a cycle of structs (struct Node { next?: Node }),
+/// as (struct, property). Putting them behind a pointer (Box in rust) breaks every such cycle,
+/// is_indirect tells the properties which are behind one already, e.g. the arrays
+pub fn get_recursive_props(ctx: &Ctxt, is_indirect: impl Fn(&RawSchema) -> bool) -> Result<HashSet<(String, String)>> {
+    let mut names: Vec<&String> = ctx.spec.ty.iter()
+        .flatten()
+        .filter(|(_, schema)| schema.properties.is_some())
+        .map(|(name, _)| name)
+        .collect();
+    names.sort();
+
+    let mut recursive_props = HashSet::new();
+    let mut visited = HashMap::new();
+    for name in names {
+        visit_value_props(ctx, name, &is_indirect, &mut visited, &mut recursive_props)?;
+    }
+    Ok(recursive_props)
+}
+
+/// Depth first search over the structs held by value, a property pointing back into the path closes a cycle
+fn visit_value_props(
+    ctx: &Ctxt,
+    name: &str,
+    is_indirect: &impl Fn(&RawSchema) -> bool,
+    visited: &mut HashMap<String, bool>,
+    recursive_props: &mut HashSet<(String, String)>
+) -> Result<()> {
+    if visited.contains_key(name) {
+        return Ok(())
+    }
+    // false while the struct is in the path, true once its properties are visited
+    visited.insert(name.to_string(), false);
+    let mut props = Vec::new();
+    collect_value_props(ctx, name, &mut HashSet::new(), &mut props)?;
+    for (owner, prop_name, prop) in props {
+        if is_indirect(&prop) || !is_struct_ty(ctx, &prop) {
+            continue
+        }
+        let ty = prop.ty.as_ref().unwrap();
+        match visited.get(ty) {
+            Some(false) => { recursive_props.insert((owner, prop_name)); },
+            Some(true) => {},
+            None => visit_value_props(ctx, ty, is_indirect, visited, recursive_props)?,
+        }
+    }
+    visited.insert(name.to_string(), true);
+    Ok(())
+}
+
+/// Get the properties of a struct along with the ones of its parents, which it holds by value as well,
+/// as (declaring struct, property name, property), ordered by struct and property name
+fn collect_value_props(ctx: &Ctxt, name: &str, visiting: &mut HashSet<String>, props: &mut Vec<(String, String, RawSchema)>) -> Result<()> {
+    let schema = match get_schema_by_name(ctx, name) {
+        Some(schema) if visiting.insert(name.to_string()) => schema,
+        _ => return Ok(()),
+    };
+    for (_, parent) in get_schema_parents(schema) {
+        collect_value_props(ctx, &parent, visiting, props)?;
+    }
+    let mut own_props: Vec<(String, RawSchema)> = get_schema_properties(ctx, schema)?.into_iter().collect();
+    own_props.sort_by(|(a, _), (b, _)| a.cmp(b));
+    props.extend(own_props.into_iter().map(|(prop_name, prop)| (name.to_string(), prop_name, prop)));
+    Ok(())
+}
+
+/// Get the parents of a struct (struct Admin : User) as (field name, type name), ordered by field name
+pub fn get_schema_parents(schema: &RawSchema) -> Vec<(String, String)> {
+    let mut parents: Vec<(String, String)> = schema.extends
+        .iter()
+        .flat_map(|extends| extends.iter())
+        .map(|(field, parent)| (field.clone(), parent.clone()))
+        .collect();
+    parents.sort();
+    parents
+}
+
+/// The default value of a property, resolved against the type of the property
+pub enum DefaultValue<'a> {
+    Literal(&'a RawSchemaDefaultValue),
+    /// (enum type, item name)
+    EnumItem(String, String),
+}
+
+/// Get the default value of a property
+///
+/// Only scalar and enum properties can have one, the default of an enum property has to name one of its items.
+pub fn get_default_value<'a>(ctx: &Ctxt, schema: &'a RawSchema) -> Result<Option<DefaultValue<'a>>> {
+    let default = match &schema.default {
+        Some(default) => default,
+        None => return Ok(None),
+    };
+
+    let ty = match &schema.ty {
+        Some(ty) if schema.items.is_none() => ty,
+        _ => bail!("default value is only supported for scalar and enum properties"),
+    };
+
+    if let Some(scalar) = Scalar::parse(ty) {
+        if !scalar.supports_default() {
+            bail!("default value is not supported for property of type '{}'", ty);
+        }
+    }
+
+    if let Some(ref_schema) = get_schema_by_name(ctx, ty) {
+        if ref_schema.alias.is_some() {
+            bail!("default value is not supported for property of declared type '{}'", ty);
+        }
+        let enum_items = match &ref_schema.enum_items {
+            Some(enum_items) => enum_items,
+            None => bail!("default value is not supported for property of struct type '{}'", ty),
+        };
+        let item = match default {
+            RawSchemaDefaultValue::String(item) => item,
+            _ => bail!("default value of enum '{}' has to be one of its items", ty),
+        };
+        if !enum_items.iter().any(|enum_item| &enum_item.name == item) {
+            bail!("'{}' is not an item of enum '{}'", item, ty);
+        }
+        return Ok(Some(DefaultValue::EnumItem(ty.clone(), item.clone())));
+    }
+
+    Ok(Some(DefaultValue::Literal(default)))
+}
+
+/// The description of a type, [description = "..."] before its declaration
+pub fn get_schema_description(schema: &RawSchema) -> Option<&str> {
+    schema.option.as_ref().and_then(|option| option.description.as_deref())
+}
+
+/// The deprecation of a type or a property, [deprecated] or [deprecated = "..."] before its declaration
+pub fn get_schema_deprecated(schema: &RawSchema) -> Option<&RawDeprecated> {
+    schema.option.as_ref().and_then(|option| option.deprecated.as_ref()).filter(|deprecated| deprecated.is_deprecated())
+}
+
+pub fn get_enum_item_deprecated(item: &RawSchemaEnumItem) -> Option<&RawDeprecated> {
+    item.deprecated.as_ref().filter(|deprecated| deprecated.is_deprecated())
+}
+
+pub fn get_usecase_deprecated(usecase: &RawUsecase) -> Option<&RawDeprecated> {
+    usecase.option.as_ref().and_then(|option| option.deprecated.as_ref()).filter(|deprecated| deprecated.is_deprecated())
+}
+
+pub fn get_method_deprecated(method: &RawUsecaseMethod) -> Option<&RawDeprecated> {
+    method.option.as_ref().and_then(|option| option.deprecated.as_ref()).filter(|deprecated| deprecated.is_deprecated())
+}
+
+/// Whether a schema refers to a deprecated type, e.g. Todo[] or map<string, Todo> with a deprecated Todo
+pub fn refers_deprecated_ty(ctx: &Ctxt, schema: &RawSchema) -> bool {
+    let is_deprecated_ty = |ty: &str| get_schema_by_name(ctx, ty).is_some_and(|schema| get_schema_deprecated(schema).is_some());
+    if let Some(items) = &schema.items {
+        return refers_deprecated_ty(ctx, items)
+    }
+    if let Some(values) = &schema.values {
+        return is_deprecated_ty(get_map_keys(schema)) || refers_deprecated_ty(ctx, values)
+    }
+    schema.properties.is_none() && schema.ty.as_deref().is_some_and(is_deprecated_ty)
+}
+
+/// The sentence of a deprecated item in a doc comment, with the note of what to use instead
+pub fn deprecation_text(deprecated: &RawDeprecated) -> String {
+    format!("Deprecated: {}", deprecated.note().unwrap_or("no longer supported."))
+}
+
+/// Prefix every line of a description, to make it a comment of the generated type
+pub fn description_to_comment(description: &str, prefix: &str) -> String {
+    description.lines().map(|line| format!("{}\n", format!("{}{}", prefix, line).trim_end())).collect()
+}
+
+/// Whether a property has any validation constraint, e.g. [min = 1]
+pub fn has_constraints(schema: &RawSchema) -> bool {
+    schema.option.as_ref().is_some_and(|option| {
+        option.min.is_some()
+            || option.max.is_some()
+            || option.min_len.is_some()
+            || option.max_len.is_some()
+            || option.pattern.is_some()
+            || option.format.is_some()
+    })
+}
+
+/// Whether a struct has to be validated, because of the constraints of its properties or the ones of the types it contains
+pub fn schema_has_constraints(ctx: &Ctxt, schema: &RawSchema) -> Result<bool> {
+    let mut visiting = HashSet::new();
+    collect_schema_has_constraints(ctx, schema, &mut visiting)
+}
+
+fn collect_schema_has_constraints(ctx: &Ctxt, schema: &RawSchema, visiting: &mut HashSet<String>) -> Result<bool> {
+    if let Some(items) = &schema.items {
+        return collect_schema_has_constraints(ctx, items, visiting);
+    }
+    if has_constraints(schema) {
+        return Ok(true);
+    }
+    if let Some(ty) = &schema.ty {
+        if schema.properties.is_none() {
+            // a reference to another type, recursive types are checked once
+            return match get_schema_by_name(ctx, ty) {
+                Some(ref_schema) if visiting.insert(ty.clone()) => collect_schema_has_constraints(ctx, ref_schema, visiting),
+                _ => Ok(false),
+            };
+        }
+    }
+    for (_, parent) in get_schema_parents(schema) {
+        if collect_schema_has_constraints(ctx, &RawSchema::new(schema.def_loc.clone(), parent), visiting)? {
+            return Ok(true);
+        }
+    }
+    for prop in get_schema_properties(ctx, schema)?.values() {
+        if collect_schema_has_constraints(ctx, prop, visiting)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Format the bound of a range constraint, an integral bound has no fractional part so that it fits integer types as well
+pub fn constraint_number_to_literal(value: f64) -> String {
+    format!("{}", value)
+}
+
+pub fn default_value_to_rust_literal(value: &DefaultValue) -> String {
+    match value {
+        DefaultValue::Literal(RawSchemaDefaultValue::Bool(b)) => b.to_string(),
+        DefaultValue::Literal(RawSchemaDefaultValue::Integer(i)) => i.to_string(),
+        DefaultValue::Literal(RawSchemaDefaultValue::Float(f)) => format!("{:?}", f),
+        DefaultValue::Literal(RawSchemaDefaultValue::String(s)) => format!("{:?}.to_string()", s),
+        DefaultValue::EnumItem(ty, item) => format!("{}::{}", get_rust_ty_path(ty), item.to_case(Case::UpperCamel)),
+    }
+}
+
+/// A double quoted string literal which python, go and javascript all read back as the given string
+///
+/// Unlike the Debug format of rust, a control character is escaped as \xNN, which the three of them understand.
+pub fn string_literal(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => literal += "\\\"",
+            '\\' => literal += "\\\\",
+            '\n' => literal += "\\n",
+            '\r' => literal += "\\r",
+            '\t' => literal += "\\t",
+            c if (c as u32) < 0x20 || c == '\u{7f}' => literal += &format!("\\x{:02x}", c as u32),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+pub fn default_value_to_py_literal(value: &DefaultValue) -> String {
+    match value {
+        DefaultValue::Literal(RawSchemaDefaultValue::Bool(b)) => if *b { "True".to_string() } else { "False".to_string() },
+        DefaultValue::Literal(RawSchemaDefaultValue::Integer(i)) => i.to_string(),
+        DefaultValue::Literal(RawSchemaDefaultValue::Float(f)) => format!("{:?}", f),
+        DefaultValue::Literal(RawSchemaDefaultValue::String(s)) => string_literal(s),
+        DefaultValue::EnumItem(ty, item) => format!("{}.{}", get_prefixed_ty_name(ty), item.to_case(Case::UpperSnake)),
+    }
+}
+
+pub fn default_value_to_golang_literal(value: &DefaultValue) -> String {
+    match value {
+        DefaultValue::Literal(RawSchemaDefaultValue::Bool(b)) => b.to_string(),
+        DefaultValue::Literal(RawSchemaDefaultValue::Integer(i)) => i.to_string(),
+        DefaultValue::Literal(RawSchemaDefaultValue::Float(f)) => format!("{:?}", f),
+        DefaultValue::Literal(RawSchemaDefaultValue::String(s)) => string_literal(s),
+        DefaultValue::EnumItem(_, item) => item.to_case(Case::UpperSnake),
+    }
+}
+
+/// The default value as it is on the wire, where an enum item is its upper snake cased name
+pub fn default_value_to_json(value: &DefaultValue) -> serde_json::Value {
+    match value {
+        DefaultValue::Literal(RawSchemaDefaultValue::Bool(b)) => serde_json::Value::from(*b),
+        DefaultValue::Literal(RawSchemaDefaultValue::Integer(i)) => serde_json::Value::from(*i),
+        DefaultValue::Literal(RawSchemaDefaultValue::Float(f)) => serde_json::Value::from(*f),
+        DefaultValue::Literal(RawSchemaDefaultValue::String(s)) => serde_json::Value::from(s.clone()),
+        DefaultValue::EnumItem(_, item) => serde_json::Value::from(item.to_case(Case::UpperSnake)),
+    }
+}
+
 pub fn get_usecase_suffix(ctx: &Ctxt) -> String {
     let mut suffix = "Usecase".to_owned();
     if let Some(global_option) = &ctx.spec.option {
@@ -180,6 +901,44 @@ pub fn get_response_name(ctx: &Ctxt,  method_name:&str) ->String {
     return ( method_name.to_owned() + &get_response_suffix(ctx)).to_case(Case::UpperCamel)
 }
 
+/// The name of the error enum of a usecase, the union of the errors declared by its methods
+pub fn get_usecase_error_name(ctx: &Ctxt, usecase_name:&str) -> String {
+    get_usecase_name(ctx, usecase_name) + "Error"
+}
+
+/// Whether the method responds with a stream of its response
+pub fn is_stream_method(method: &RawUsecaseMethod) -> bool {
+    method.stream.unwrap_or(false) && method.res.is_some()
+}
+
+/// The name of the golang sentinel error of a declared error, e.g. ErrNotFound
+pub fn get_golang_error_name(error_name: &str) -> String {
+    format!("Err{}", error_name.to_case(Case::UpperCamel))
+}
+
+/// Get the declared errors of a method, in the order of its errors clause
+pub fn get_method_errors<'a>(ctx: &'a Ctxt, method: &RawUsecaseMethod) -> Result<Vec<(&'a str, &'a RawError)>> {
+    let mut errors = Vec::new();
+    for name in method.errors.iter().flatten() {
+        match ctx.spec.errors.as_ref().and_then(|errors| errors.get_key_value(name)) {
+            Some((name, error)) => errors.push((name.as_str(), error)),
+            None => bail!("error '{}' is not declared", name),
+        }
+    }
+    Ok(errors)
+}
+
+/// Get the declared errors of all methods of a usecase, sorted by name
+pub fn get_usecase_errors<'a>(ctx: &'a Ctxt, usecase: &RawUsecase) -> Result<Vec<(&'a str, &'a RawError)>> {
+    let mut errors = Vec::new();
+    for method in usecase.methods.values() {
+        errors.extend(get_method_errors(ctx, method)?);
+    }
+    errors.sort_by_key(|(name, _)| *name);
+    errors.dedup_by_key(|(name, _)| *name);
+    Ok(errors)
+}
+
 pub fn get_request_name(ctx: &Ctxt,  method_name:&str) ->String {
     return  (method_name.to_owned() + &get_request_suffix(ctx)).to_case(Case::UpperCamel)
 }
@@ -316,4 +1075,153 @@ mod tests {
         let expected = vec!["variable".to_string()];
         assert_eq!(extract_url_variables(path), expected);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_spread_properties() -> Result<()> {
+        let api_file: &'static str = r#"
+        struct Base {
+            id: string
+        }
+        struct Timestamps : Base {
+            created_at: string
+        }
+        struct Post {
+            ...Timestamps
+            title: string
+        }
+        "#;
+        let spec = cronus_parser::api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let post = get_schema_by_name(&ctx, "Post").unwrap();
+        let properties = get_schema_properties(&ctx, post)?;
+        let mut names: Vec<&String> = properties.keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["created_at", "id", "title"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_literal() {
+        assert_eq!(super::string_literal("plain"), r#""plain""#);
+        assert_eq!(super::string_literal("a\"b\\c"), r#""a\"b\\c""#);
+        assert_eq!(super::string_literal("\n\t\u{1}é"), r#""\n\t\x01é""#);
+    }
+
+    #[test]
+    fn test_default_value_not_enum_item() -> Result<()> {
+        let api_file: &'static str = r#"
+        enum Status {
+            Active
+        }
+        struct Hello {
+            status: Status = Deleted
+        }
+        "#;
+        let spec = cronus_parser::api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let hello = get_schema_by_name(&ctx, "Hello").unwrap();
+        let status = hello.properties.as_ref().unwrap().get("status").unwrap();
+        assert!(get_default_value(&ctx, status).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_spread_cycle() -> Result<()> {
+        let api_file: &'static str = r#"
+        struct A {
+            ...B
+        }
+        struct B {
+            ...A
+        }
+        "#;
+        let spec = cronus_parser::api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let a = get_schema_by_name(&ctx, "A").unwrap();
+        assert!(get_schema_properties(&ctx, a).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_readonly_param() -> Result<()> {
+        let api_file: &'static str = r#"
+        struct Todo {
+            [readonly] id: string
+            title: string
+        }
+        usecase todo {
+            [rest.method = "put"]
+            [rest.path = ":id"]
+            updateTodo Todo
+
+            [rest.method = "put"]
+            replaceTodo Todo
+        }
+        "#;
+        let spec = cronus_parser::api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let methods = &ctx.spec.usecases.as_ref().unwrap().get("todo").unwrap().methods;
+        let err = check_readonly_params(&resolve_method_spreads(&ctx, methods.get("updateTodo").unwrap())?).unwrap_err();
+        assert!(err.to_string().contains("readonly property 'id' can't be a path or query parameter"), "{}", err);
+        assert!(check_readonly_params(&resolve_method_spreads(&ctx, methods.get("replaceTodo").unwrap())?).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_idents() -> Result<()> {
+        assert_eq!(rust_ident("type"), "r#type");
+        assert_eq!(rust_ident("self"), "self_");
+        assert_eq!(rust_ident("name"), "name");
+        assert_eq!(py_ident("from"), "from_");
+        assert_eq!(py_ident("createdAt"), "created_at");
+        assert_eq!(py_ident("match"), "match");
+        assert_eq!(go_ident("func"), "Func");
+        assert_eq!(go_ident("1st"), "X1St");
+
+        let props = ["fooBar".to_string(), "foo_bar".to_string()];
+        let err = check_ident_collisions("Item", &props, py_ident).unwrap_err();
+        assert_eq!(err.to_string(), "properties 'fooBar' and 'foo_bar' of 'Item' are both named 'foo_bar'");
+        assert!(check_ident_collisions("Item", &props, |name| name.to_string()).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_recursive_props() -> Result<()> {
+        let api_file: &'static str = r#"
+        struct Node {
+            next?: Node
+            children: Node[]
+        }
+        struct A {
+            b: B
+        }
+        struct B {
+            c: C
+        }
+        struct C {
+            a: A
+            b?: B
+        }
+        struct Base {
+            child: Child
+        }
+        struct Child : Base {
+            name: string
+        }
+        "#;
+        let spec = cronus_parser::api_parse::parse(PathBuf::from(""), api_file)?;
+        let ctx = Ctxt::new(spec);
+        let recursive_props = get_recursive_props(&ctx, |prop| prop.items.is_some())?;
+        let mut recursive_props: Vec<(String, String)> = recursive_props.into_iter().collect();
+        recursive_props.sort();
+        let expected: Vec<(String, String)> = [("Base", "child"), ("C", "a"), ("C", "b"), ("Node", "next")].iter()
+            .map(|(ty, prop)| (ty.to_string(), prop.to_string()))
+            .collect();
+        assert_eq!(recursive_props, expected);
+
+        // an optional property is a pointer in go
+        let recursive_props = get_recursive_props(&ctx, |prop| prop.items.is_some() || prop.required == Some(false))?;
+        assert!(!recursive_props.contains(&("Node".to_string(), "next".to_string())));
+        Ok(())
+    }
+}
@@ -0,0 +1,5 @@
+#![deny(deprecated)]
+#![allow(dead_code, unused_imports)]
+mod generated;
+
+fn main() {}
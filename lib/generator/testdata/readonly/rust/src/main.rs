@@ -0,0 +1,8 @@
+#![allow(dead_code, unused_imports)]
+mod generated;
+
+fn main() {
+    // the readonly fields are left to their defaults
+    let todo: generated::Todo = serde_json::from_str(r#"{"id": "1", "owner": {"name": "a"}, "secret": "s", "title": "t"}"#).unwrap();
+    assert!(todo.owner.is_none());
+}
@@ -0,0 +1,150 @@
+use anyhow::{bail, Result};
+use pest::{iterators::Pair, Parser};
+use crate::api_parser::{APIParser, Rule};
+
+/// Rewrite the deprecated syntax of an api file to the current one, the rest of the file is kept as is
+///
+/// - `global [...]` becomes `#[...]`
+/// - `createTodo { in { ... } out { ... } }` becomes `createTodo { ... } -> { ... }`
+pub fn migrate(content: &str) -> Result<String> {
+    let pairs = APIParser::parse(Rule::file, content)?;
+
+    // the replaced ranges of the content, in the order of the content
+    let mut replacements: Vec<(usize, usize, String)> = Vec::new();
+    for pair in pairs.flatten() {
+        match pair.as_rule() {
+            Rule::global_option => {
+                let mut inner = pair.into_inner();
+                let keyword = inner.next().unwrap();
+                if keyword.as_rule() == Rule::legacy_global {
+                    let option = inner.next().unwrap();
+                    replacements.push((keyword.as_span().start(), option.as_span().start(), "#".to_string()));
+                }
+            },
+            Rule::legacy_method_body => replacements.push(migrate_method_body(content, pair)?),
+            _ => {}
+        }
+    }
+
+    let mut result = String::new();
+    let mut last = 0;
+    for (start, end, text) in replacements {
+        result += &content[last..start];
+        result += &text;
+        last = end;
+    }
+    result += &content[last..];
+    Ok(result)
+}
+
+/// Rewrite { in { ... } out { ... } } to { ... } -> { ... }, the blocks are moved out of the braces by one level
+fn migrate_method_body(content: &str, pair: Pair<Rule>) -> Result<(usize, usize, String)> {
+    let (line, col) = pair.line_col();
+    let span = pair.as_span();
+    let line_start = content[..span.start()].rfind('\n').map_or(0, |i| i + 1);
+    let method_indent = content[line_start..].len() - content[line_start..].trim_start_matches([' ', '\t']).len();
+
+    let mut blocks = Vec::new();
+    // the text of the method body other than its blocks, which has to be whitespace only
+    let mut rest = String::new();
+    let mut last = span.start() + 1;
+    for legacy_pair in pair.into_inner() {
+        let legacy_span = legacy_pair.as_span();
+        rest += &content[last..legacy_span.start()];
+        last = legacy_span.end();
+
+        // the indent the block is moved out of, from the method to the in or out keyword
+        let (_, keyword_col) = legacy_pair.line_col();
+        let shift = (keyword_col - 1).saturating_sub(method_indent);
+        let block = legacy_pair.into_inner().next().unwrap();
+        let text = dedent(block.as_str(), shift);
+        match block.as_rule() {
+            Rule::in_block => blocks.push(text),
+            _ => blocks.push(format!("-> {}", text)),
+        }
+    }
+    rest += &content[last..span.end() - 1];
+    if !rest.trim().is_empty() {
+        bail!("the method body at {}:{} has comments between its blocks, which have to be moved out of it first", line, col)
+    }
+
+    Ok((span.start(), span.end(), blocks.join(" ")))
+}
+
+/// Remove up to the given number of leading spaces from every line but the first one
+fn dedent(text: &str, shift: usize) -> String {
+    text.split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 {
+                return line
+            }
+            let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+            &line[indent.min(shift)..]
+        })
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use anyhow::Result;
+
+    #[test]
+    fn can_migrate_legacy_syntax() -> Result<()> {
+        let legacy = r#"
+global [generator.rust.file = "src/generated.rs"]
+global [generator.rust.async]
+
+usecase Hello {
+  [rest.method = "post"]
+  createHello {
+      in {
+          hi: string
+      }
+      out {
+          answer: string
+      }
+  }
+
+  deleteHello { in { id: string } }
+
+  listHellos {
+      out {
+          hellos: string[]
+      }
+  }
+}
+"#;
+        let migrated = super::migrate(legacy)?;
+        assert_eq!(migrated, r#"
+#[generator.rust.file = "src/generated.rs"]
+#[generator.rust.async]
+
+usecase Hello {
+  [rest.method = "post"]
+  createHello {
+      hi: string
+  } -> {
+      answer: string
+  }
+
+  deleteHello { id: string }
+
+  listHellos -> {
+      hellos: string[]
+  }
+}
+"#);
+
+        let legacy_spec = crate::api_parse::parse(PathBuf::new(), legacy)?;
+        let migrated_spec = crate::api_parse::parse(PathBuf::new(), &migrated)?;
+        assert_eq!(serde_json::to_value(&legacy_spec)?, serde_json::to_value(&migrated_spec)?);
+
+        // nothing to migrate anymore
+        assert_eq!(super::migrate(&migrated)?, migrated);
+
+        Ok(())
+    }
+}
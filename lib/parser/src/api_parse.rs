@@ -4,6 +4,14 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use anyhow::bail;
 use anyhow::Result;
+use convert_case::Case;
+use convert_case::Casing;
+use cronus_spec::RawError;
+use cronus_spec::RawImport;
+use cronus_spec::RawImportName;
+use cronus_spec::RawSchemaDefaultValue;
+use cronus_spec::RawSchemaDerive;
+use cronus_spec::RawSchemaDeriveOp;
 use cronus_spec::RawSchemaEnumItem;
 use serde::de::DeserializeOwned;
 use cronus_spec::DefLoc;
@@ -22,56 +30,34 @@ fn parse_file(def_loc:Arc<DefLoc>, pairs: Pair<Rule>) -> Result<RawSpec> {
     let mut spec = RawSpec::new();
 
     let mut options: Vec<(Vec<String>, serde_yaml::Value)> = Default::default();
+    let mut package: Option<String> = None;
     
     for pair in pairs.into_inner() {
         let (line, col) = pair.line_col();
         match pair.as_rule() {
-            Rule::struct_def => {
-                let (name, schema) = parse_struct_def(def_loc.clone(), pair)?;
-                if let Some(n) = name {
-                    match spec.ty {
-                        Some(ref mut tys) => {
-                            tys.insert(n, schema);
-                        },
-                        None => {
-                            let mut tys = HashMap::new();
-                            tys.insert(n, schema);
-                            spec.ty = Some(tys);
-                        },
-                    }
-                } else {
-                    bail!("expect to have name of the schema in the top level at {}:{}", line, col)
-                }
+            Rule::package => {
+                package = pair.into_inner().next().map(|name| name.as_str().to_string());
             }
-            Rule::enum_def => {
-                let (name, schema) = parse_enum_def(def_loc.clone(), pair)?;
-                if let Some(n) = name {
-                    match spec.ty {
-                        Some(ref mut tys) => {
-                            tys.insert(n, schema);
-                        },
-                        None => {
-                            let mut tys = HashMap::new();
-                            tys.insert(n, schema);
-                            spec.ty = Some(tys);
-                        },
-                    }
-                } else {
-                    bail!("expect to have name of the enum in the top level at {}:{}", line, col)
+            Rule::struct_def | Rule::enum_def | Rule::type_def | Rule::usecase => {
+                parse_declaration(def_loc.clone(), &mut spec, pair, package.as_deref())?;
+            }
+            Rule::namespace_def => {
+                if let Some(ref package) = package {
+                    bail!("a namespace can't be declared in the file of package '{}' at {}:{}", package, line, col)
+                }
+                let mut inner = pair.into_inner();
+                let namespace = inner.next().unwrap().as_str().to_string();
+                for declaration in inner {
+                    parse_declaration(def_loc.clone(), &mut spec, declaration, Some(&namespace))?;
                 }
             }
-            Rule::usecase => {
-                let (name, usecase) = parse_usecase(def_loc.clone(), pair)?;
-                match spec.usecases {
-                    Some(ref mut usecases) => {
-                        usecases.insert(name, usecase);
-                    },
-                    None => {
-                        let mut usecases = HashMap::new();
-                        usecases.insert(name, usecase);
-                        spec.usecases = Some(usecases);
-                    },
+            Rule::error_def => {
+                let (name, error) = parse_error_def(def_loc.clone(), pair)?;
+                let errors = spec.errors.get_or_insert_with(HashMap::new);
+                if errors.contains_key(&name) {
+                    bail!("error '{}' is declared more than once at {}:{}", name, line, col)
                 }
+                errors.insert(name, error);
             }
             Rule::global_option => {
                 for inner_pair in pair.into_inner() {
@@ -80,19 +66,17 @@ fn parse_file(def_loc:Arc<DefLoc>, pairs: Pair<Rule>) -> Result<RawSpec> {
                             let option = parse_option(def_loc.clone(), inner_pair)?;
                             options.push(option);
                         }
+                        Rule::legacy_global => warn_deprecated(&def_loc, &inner_pair, "global [...]", "#[...]"),
                         _ => {}
                     }
                 }
             }
             Rule::import => {
-                let import = parse_import( pair);
-                match spec.imports {
-                    Some(ref mut imp) => {
-                        imp.push(import);
-                    },
-                    None => {
-                        spec.imports = Some(vec![import])
-                    },
+                let import = parse_import(def_loc.clone(), pair);
+                if import.names.is_none() && import.alias.is_none() {
+                    spec.imports.get_or_insert_with(Vec::new).push(import.path);
+                } else {
+                    spec.scoped_imports.get_or_insert_with(Vec::new).push(import);
                 }
             },
             _ => {}
@@ -111,6 +95,46 @@ fn parse_file(def_loc:Arc<DefLoc>, pairs: Pair<Rule>) -> Result<RawSpec> {
     Ok(spec)
 }
 
+/// Parse a declaration of a type or a usecase, a type in a namespace is keyed by its qualified name, e.g. billing.Invoice
+fn parse_declaration(def_loc:Arc<DefLoc>, spec: &mut RawSpec, pair: Pair<Rule>, namespace: Option<&str>) -> Result<()> {
+    let (line, col) = pair.line_col();
+    let (name, mut schema) = match pair.as_rule() {
+        Rule::struct_def => match parse_struct_def(def_loc, pair)? {
+            (Some(name), schema) => (name, schema),
+            (None, _) => bail!("expect to have name of the schema in the top level at {}:{}", line, col),
+        },
+        Rule::enum_def => match parse_enum_def(def_loc, pair)? {
+            (Some(name), schema) => (name, schema),
+            (None, _) => bail!("expect to have name of the enum in the top level at {}:{}", line, col),
+        },
+        Rule::type_def => parse_type_def(def_loc, pair)?,
+        Rule::usecase => {
+            let (name, mut usecase) = parse_usecase(def_loc, pair)?;
+            // the usecases are generated by their names, which the namespace does not qualify
+            if spec.usecases.as_ref().is_some_and(|usecases| usecases.contains_key(&name)) {
+                bail!("usecase '{}' at {}:{} is declared twice, the usecases of different namespaces need different names", name, line, col)
+            }
+            usecase.namespace = namespace.map(str::to_string);
+            spec.usecases.get_or_insert_with(HashMap::new).insert(name, usecase);
+            return Ok(())
+        },
+        _ => bail!("unexpected rule found in declaration: {:?}", pair.as_rule())
+    };
+
+    let name = match namespace {
+        Some(namespace) => {
+            schema.namespace = Some(namespace.to_string());
+            format!("{}.{}", namespace, name)
+        },
+        None => name,
+    };
+    if spec.ty.as_ref().is_some_and(|tys| tys.contains_key(&name)) {
+        bail!("type '{}' at {}:{} is declared twice", name, line, col)
+    }
+    spec.ty.get_or_insert_with(HashMap::new).insert(name, schema);
+    Ok(())
+}
+
 fn set_def_loc_for_global_option(def_loc:Arc<DefLoc>,  global_option: Option<&mut GlobalOption>) {
     match global_option {
         Some(go) => {
@@ -167,46 +191,91 @@ fn set_def_loc_for_global_option(def_loc:Arc<DefLoc>,  global_option: Option<&mu
     }
 }
 
-fn parse_import(pair: pest::iterators::Pair<Rule>) -> String {
-    let mut path = String::new();
+/// Parse an import, it imports the whole file if it has neither names nor an alias
+fn parse_import(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> RawImport {
+    let mut import = RawImport {
+        def_loc,
+        line_col: Some(pair.line_col()),
+        path: String::new(),
+        names: None,
+        alias: None,
+    };
 
     for inner_pair in pair.into_inner() {
         match inner_pair.as_rule() {
             Rule::path => {
-                path = inner_pair.as_str().to_string();
-                break; // Path is the only expected content after 'import'
+                import.path = inner_pair.as_str().to_string();
+            },
+            Rule::string => {
+                import.path = inner_pair.as_str().trim_matches('"').to_string();
+            },
+            Rule::import_names => {
+                import.names = Some(inner_pair.into_inner().map(|name_pair| {
+                    let mut names = name_pair.into_inner().map(|name| name.as_str().to_string());
+                    RawImportName {
+                        name: names.next().unwrap(),
+                        alias: names.next(),
+                    }
+                }).collect());
+            },
+            Rule::import_alias => {
+                import.alias = inner_pair.into_inner().next().map(|alias| alias.as_str().to_string());
             },
             _ => {}
         }
     }
 
-    path
+    import
+}
+
+/// The options a struct or an enum can have
+pub(crate) const TYPE_OPTIONS: [&str; 4] = ["description", "deprecated", "rust.attrs", "openapi.exclude"];
+
+/// Whether a struct or an enum can have the option, the user-defined annotations (x.*) are allowed as well
+pub(crate) fn is_type_option(keys: &[String]) -> bool {
+    keys.first().is_some_and(|key| key == "x") || TYPE_OPTIONS.contains(&keys.join(".").as_str())
 }
 
 fn parse_struct_def(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Result<(Option<String>, RawSchema)> {
     let mut name = None;
-    let mut options = None; 
+    let mut options = serde_yaml::Mapping::new();
     let mut schema = RawSchema {
         def_loc: def_loc.clone(),
         ty: None,
         properties: None,
         items: None,
+        keys: None,
+        values: None,
         required:None,
+        nullable: None,
         namespace: None,
         enum_items:None,
         option: None,
         extends:None,
         flat_extends: None,
+        default: None,
+        alias: None,
+        derive: None,
     };
     for inner_pair in pair.into_inner() {
         match inner_pair.as_rule() {
             Rule::identifier => name = Some(inner_pair.as_str().to_string()),
             Rule::option => {
-                options = Some(parse_option(def_loc.clone(), inner_pair));
+                let (keys, value) = parse_type_option(def_loc.clone(), inner_pair)?;
+                insert_value_by_keys(&mut options, keys, value)?;
+            },
+            Rule::struct_parents => {
+                schema.extends = Some(parse_struct_parents(inner_pair)?);
             },
             Rule::struct_body => {
-                let properties = parse_struct_body(def_loc.clone(), inner_pair)?;
-                schema.properties = Some(properties)
+                let (properties, spreads) = parse_struct_body(def_loc.clone(), inner_pair)?;
+                schema.properties = Some(properties);
+                if !spreads.is_empty() {
+                    schema.flat_extends = Some(spreads);
+                }
+            },
+            Rule::struct_derive => {
+                schema.derive = Some(parse_struct_derive(inner_pair)?);
             },
             _ => {
                 panic!("missing rule handling")
@@ -214,15 +283,42 @@ fn parse_struct_def(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> R
         }
     }
 
+    schema.option = yaml_mapping_to_option(options)?;
+
     Ok((name, schema))
 }
 
+/// Parse the struct a struct is derived from, e.g. pick Todo (id, title)
+fn parse_struct_derive(pair: pest::iterators::Pair<Rule>) -> Result<RawSchemaDerive> {
+    let (line, col) = pair.line_col();
+    let mut inner = pair.into_inner();
+    let op = match inner.next().unwrap().as_str() {
+        "partial" => RawSchemaDeriveOp::Partial,
+        "pick" => RawSchemaDeriveOp::Pick,
+        _ => RawSchemaDeriveOp::Omit,
+    };
+    let from = inner.next().unwrap().as_str().to_string();
+    let fields: Option<Vec<String>> = inner.next()
+        .map(|fields| fields.into_inner().map(|field| field.as_str().to_string()).collect());
+    match (op, &fields) {
+        (RawSchemaDeriveOp::Partial, Some(_)) => bail!("partial takes every field of '{}', no field can be given at {}:{}", from, line, col),
+        (RawSchemaDeriveOp::Pick, None) | (RawSchemaDeriveOp::Omit, None) => bail!("expect the fields to pick or omit of '{}' at {}:{}", from, line, col),
+        _ => {}
+    }
+    Ok(RawSchemaDerive { op, from, fields })
+}
+
 fn parse_enum_def(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Result<(Option<String>, RawSchema)> {
     let mut name = None;
     let mut enum_items = Vec::new();
+    let mut options = serde_yaml::Mapping::new();
     for inner_pair in pair.into_inner() {
         match inner_pair.as_rule() {
             Rule::identifier => name = Some(inner_pair.as_str().to_string()),
+            Rule::option => {
+                let (keys, value) = parse_type_option(def_loc.clone(), inner_pair)?;
+                insert_value_by_keys(&mut options, keys, value)?;
+            },
             Rule::enum_body => {
                 enum_items = parse_enum_body(def_loc.clone(), inner_pair)?;
             },
@@ -237,12 +333,18 @@ fn parse_enum_def(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Res
         ty: None,
         properties: None,
         items: None,
+        keys: None,
+        values: None,
         required: None,
+        nullable: None,
         namespace: None,
         enum_items: Some(enum_items),
-        option: None,
+        option: yaml_mapping_to_option(options)?,
         extends: None,
         flat_extends: None,
+        default: None,
+        alias: None,
+        derive: None,
     };
 
     Ok((name, schema))
@@ -271,6 +373,7 @@ fn parse_enum_body(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Re
 fn parse_enum_property(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Result<RawSchemaEnumItem> {
     let mut name = String::new();
     let mut enum_value: Option<i32> = None;
+    let mut deprecated = None;
     for inner_pair in pair.into_inner() {
         match inner_pair.as_rule() {
             Rule::identifier => {
@@ -287,6 +390,8 @@ fn parse_enum_property(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -
                             bail!("value must be an integer for enum item: {:?}", inner_pair.as_str());
                         }
                     }
+                } else if keys.len() == 1 && keys[0] == "deprecated" {
+                    deprecated = Some(serde_yaml::from_value(value)?);
                 }
             },
             _ => {
@@ -296,20 +401,101 @@ fn parse_enum_property(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -
     }
 
 
-    Ok(RawSchemaEnumItem { name: name, value: enum_value })
+    Ok(RawSchemaEnumItem { name, value: enum_value, deprecated })
 }
 
-fn parse_struct_body(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Result<HashMap<String, RawSchema>> {
+/// Parse a declared type (type Timestamp), its generator options (@rust, @golang etc.) are the type of each language
+fn parse_type_def(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Result<(String, RawSchema)> {
+    let mut name = String::new();
+    let mut options = serde_yaml::Mapping::new();
+    for inner_pair in pair.into_inner() {
+        match inner_pair.as_rule() {
+            Rule::identifier => name = inner_pair.as_str().to_string(),
+            Rule::option => {
+                let (line, col) = inner_pair.line_col();
+                let (keys, value) = parse_option(def_loc.clone(), inner_pair)?;
+                if keys.len() < 2 || keys[0] != "generator" {
+                    bail!("unsupported option '{}' for a declared type at {}:{}, expect a generator option like @rust", keys.join("."), line, col)
+                }
+                insert_value_by_keys(&mut options, keys[1..].to_vec(), value)?;
+            },
+            _ => {
+                bail!("unexpected rule found in type declaration: {:?}", inner_pair.as_rule())
+            }
+        }
+    }
+
+    let schema = RawSchema {
+        def_loc,
+        ty: None,
+        properties: None,
+        items: None,
+        keys: None,
+        values: None,
+        required: None,
+        nullable: None,
+        namespace: None,
+        enum_items: None,
+        option: None,
+        extends: None,
+        flat_extends: None,
+        default: None,
+        alias: Some(yaml_mapping_to_option(options)?.unwrap_or_default()),
+        derive: None,
+    };
+
+    Ok((name, schema))
+}
+
+/// Parse the parents of a struct (struct Admin : User), the result is field name => parent type
+/// 
+/// The field name is used by the generators which embed the parent instead of inheriting from it,
+/// so two parents of the same name in different namespaces are an error
+fn parse_struct_parents(pair: pest::iterators::Pair<Rule>) -> Result<HashMap<String, String>> {
+    let mut parents: HashMap<String, String> = HashMap::new();
+    for inner_pair in pair.into_inner().filter(|inner_pair| inner_pair.as_rule() == Rule::qualified_identifier) {
+        let (line, col) = inner_pair.line_col();
+        let parent = inner_pair.as_str().to_string();
+        // the field of billing.User is user
+        let field = parent.rsplit('.').next().unwrap().to_case(Case::Snake);
+        if let Some(other) = parents.insert(field.clone(), parent.clone()) {
+            bail!("parents '{}' and '{}' at {}:{} are both the field '{}' of the struct", other, parent, line, col, field)
+        }
+    }
+    Ok(parents)
+}
+
+/// Parse the struct body, the result is the properties and the spread (...Base) types
+fn parse_struct_body(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Result<(HashMap<String, RawSchema>, Vec<String>)> {
     let mut properties = HashMap::new();
+    let mut spreads = Vec::new();
 
     for inner_pair in pair.into_inner() {
-        if inner_pair.as_rule() == Rule::property {
-            let (prop_name, prop_schema) = parse_property(def_loc.clone(), inner_pair)?;
-            properties.insert(prop_name, prop_schema);
+        match inner_pair.as_rule() {
+            Rule::property => {
+                let (prop_name, prop_schema) = parse_property(def_loc.clone(), inner_pair)?;
+                properties.insert(prop_name, prop_schema);
+            },
+            Rule::struct_spread => {
+                for spread_pair in inner_pair.into_inner() {
+                    spreads.push(spread_pair.as_str().to_string());
+                }
+            },
+            _ => {}
         }
     }
 
-    Ok(properties)
+    Ok((properties, spreads))
+}
+
+/// Parse an option of a struct or an enum, only the ones about the type as a whole are allowed
+fn parse_type_option(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Result<(Vec<String>, serde_yaml::Value)> {
+    let (line, col) = pair.line_col();
+    let (keys, value) = parse_option(def_loc, pair)?;
+    if !is_type_option(&keys) {
+        bail!("unsupported option '{}' for a type at {}:{}, expect one of {} or x.*", keys.join("."), line, col, TYPE_OPTIONS.join(", "))
+    }
+    Ok((keys, value))
 }
 
 fn parse_option(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Result<(Vec<String>, serde_yaml::Value)> {
@@ -352,10 +538,12 @@ fn parse_option(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Resul
 
 fn parse_option_value(pair: pest::iterators::Pair<Rule>) -> serde_yaml::Value {
     match pair.as_rule() {
-        Rule::integer => parse_integer(pair),
+        Rule::number => parse_number(pair),
         Rule::string => parse_string(pair),
+        Rule::multiline_string => parse_multiline_string(pair),
         Rule::bool => parse_bool(pair),
         Rule::array => parse_array(pair),
+        Rule::option_map => parse_option_map(pair),
         Rule::option_value => parse_option_value(pair.into_inner().next().unwrap()),
         _ => {
             unreachable!()
@@ -363,15 +551,71 @@ fn parse_option_value(pair: pest::iterators::Pair<Rule>) -> serde_yaml::Value {
     }
 }
 
-fn parse_integer(pair: pest::iterators::Pair<Rule>) -> serde_yaml::Value {
-    // Parse the integer string into i64
-    let int_value = pair.as_str().parse::<i64>().unwrap(); // Handle errors appropriately
-    serde_yaml::Value::from(int_value)
+fn parse_number(pair: pest::iterators::Pair<Rule>) -> serde_yaml::Value {
+    // the grammar only matches valid numbers, an integer out of the i64 and u64 ranges is a float
+    let value = pair.as_str();
+    if !value.contains(['.', 'e', 'E']) {
+        if let Ok(value) = value.parse::<i64>() {
+            return serde_yaml::Value::from(value)
+        }
+        if let Ok(value) = value.parse::<u64>() {
+            return serde_yaml::Value::from(value)
+        }
+    }
+    serde_yaml::Value::from(value.parse::<f64>().unwrap())
 }
 
 
 fn parse_string(pair: pest::iterators::Pair<Rule>) -> serde_yaml::Value {
-    serde_yaml::Value::String(pair.as_str().trim_matches('"').to_string())
+    serde_yaml::Value::String(unescape_string(pair.as_str()))
+}
+
+/// Get the content of a quoted string, an unknown escape is kept as is, e.g. "^\d+$" is ^\d+$
+fn unescape_string(quoted: &str) -> String {
+    let content = &quoted[1..quoted.len() - 1];
+    let mut result = String::with_capacity(content.len());
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => result.push('"'),
+            Some('\\') => result.push('\\'),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            },
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Get the content of a triple-quoted string, the line breaks after the opening quotes and before
+/// the closing ones are dropped and the lines are dedented by their common indent
+fn parse_multiline_string(pair: pest::iterators::Pair<Rule>) -> serde_yaml::Value {
+    let quoted = pair.as_str();
+    let content = &quoted[3..quoted.len() - 3];
+    let content = content.strip_prefix("\r\n").or_else(|| content.strip_prefix('\n')).unwrap_or(content);
+    let mut lines: Vec<&str> = content.lines().collect();
+    if lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+
+    let indent = lines.iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let lines: Vec<&str> = lines.iter()
+        .map(|line| line.get(indent..).unwrap_or("").trim_end_matches('\r'))
+        .collect();
+    serde_yaml::Value::String(lines.join("\n"))
 }
 
 fn parse_bool(pair: pest::iterators::Pair<Rule>) -> serde_yaml::Value {
@@ -383,6 +627,20 @@ fn parse_array(pair: pest::iterators::Pair<Rule>) -> serde_yaml::Value {
     serde_yaml::Value::Sequence(values)
 }
 
+fn parse_option_map(pair: pest::iterators::Pair<Rule>) -> serde_yaml::Value {
+    let mut mapping = serde_yaml::Mapping::new();
+    for entry in pair.into_inner() {
+        let mut inner = entry.into_inner();
+        let key_pair = inner.next().unwrap();
+        let key = match key_pair.as_rule() {
+            Rule::string => unescape_string(key_pair.as_str()),
+            _ => key_pair.as_str().to_string(),
+        };
+        mapping.insert(serde_yaml::Value::String(key), parse_option_value(inner.next().unwrap()));
+    }
+    serde_yaml::Value::Mapping(mapping)
+}
+
 fn insert_value_by_keys(mapping: &mut serde_yaml::Mapping, keys: Vec<String>, value: serde_yaml::Value) -> Result<()> {
     if keys.is_empty() {
         bail!("keys is empty");
@@ -417,16 +675,19 @@ fn insert_value_by_keys(mapping: &mut serde_yaml::Mapping, keys: Vec<String>, va
 
 fn parse_property(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Result<(String, RawSchema)> {
     let mut name = String::new();
-    let mut type_name = String::new();
+    let mut ty_schema = None;
     let mut options = serde_yaml::Mapping::new();
     let mut required = true;
+    let mut nullable = false;
+    let mut default = None;
+    let (line, col) = pair.line_col();
     for inner_pair in pair.into_inner() {
         match inner_pair.as_rule() {
             Rule::identifier => {
                 name = inner_pair.as_str().to_string();
             },
-            Rule::type_identifier => {
-                type_name = inner_pair.as_str().to_string();
+            Rule::type_expr => {
+                ty_schema = Some(parse_type_expr(def_loc.clone(), inner_pair)?);
             },
             Rule::option => {
                 let (keys, value) = parse_option(def_loc.clone(), inner_pair)?;
@@ -435,39 +696,100 @@ fn parse_property(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Res
             Rule::optional_property => {
                 required = false;
             }
+            Rule::nullable_property => {
+                nullable = true;
+            }
+            Rule::default_value => {
+                default = Some(parse_default_value(inner_pair)?);
+            }
             _ => {}
         }
     }  
 
-    let mut items = None;
-    let is_array = type_name.ends_with("[]");
-    // check if type is array
-    if is_array {
-        let mut items_ty = type_name.clone();
-        items_ty.truncate(type_name.len() - 2);
-        items = Some(Box::new(RawSchema::new(def_loc.clone(), items_ty)));
-    }
-
-    
     let op:Option<RawSchemaPropertyOption> = yaml_mapping_to_option(options)?;
+    if op.as_ref().is_some_and(|op| op.readonly == Some(true) && op.writeonly == Some(true)) {
+        bail!("property '{}' can't be both readonly and writeonly at {}:{}", name, line, col)
+    }
 
+    // the grammar requires the type of a property
+    let ty_schema = ty_schema.unwrap();
     let schema = RawSchema {
         def_loc,
-        ty: if is_array { None } else { Some(type_name) },
-        items,
+        ty: ty_schema.ty,
+        items: ty_schema.items,
+        keys: ty_schema.keys,
+        values: ty_schema.values,
         properties: None,
         required: Some(required),
+        nullable: if nullable { Some(true) } else { None },
         namespace: None,
         enum_items: None,
         option: op,
         extends: None,
         flat_extends: None,
-        
+        default,
+        alias: None,
+        derive: None,
     };
 
     Ok((name, schema))
 }
 
+/// Parse a type expression, every [] suffix wraps the type into one more array, e.g. string[][]
+fn parse_type_expr(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Result<RawSchema> {
+    let mut inner = pair.into_inner();
+    let mut schema = parse_type_term(def_loc.clone(), inner.next().unwrap())?;
+    for _ in inner {
+        let mut array = RawSchema::new_array_ty(def_loc.clone(), String::new());
+        array.items = Some(Box::new(schema));
+        schema = array;
+    }
+    Ok(schema)
+}
+
+fn parse_type_term(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Result<RawSchema> {
+    match pair.as_rule() {
+        Rule::type_name => Ok(RawSchema::new(def_loc, pair.as_str().to_string())),
+        Rule::map_type => {
+            let (line, col) = pair.line_col();
+            let mut inner = pair.into_inner();
+            let key_pair = inner.next().unwrap();
+            let key_ty = key_pair.as_str().trim().to_string();
+            let key = parse_type_expr(def_loc.clone(), key_pair)?;
+            let values = parse_type_expr(def_loc.clone(), inner.next().unwrap())?;
+            match key.ty {
+                Some(key_ty) if key.items.is_none() && key.values.is_none() => Ok(RawSchema::new_map_ty(def_loc, key_ty, values)),
+                _ => bail!("the key of a map can't be the type '{}' at {}:{}, expect a scalar or a named type", key_ty, line, col),
+            }
+        },
+        _ => bail!("unexpected rule found in type expression: {:?}", pair.as_rule())
+    }
+}
+
+fn parse_default_value(pair: pest::iterators::Pair<Rule>) -> Result<RawSchemaDefaultValue> {
+    let value_pair = pair.into_inner().next().unwrap();
+    let value = value_pair.as_str();
+    let default = match value_pair.as_rule() {
+        Rule::number => {
+            if value.contains(['.', 'e', 'E']) {
+                RawSchemaDefaultValue::Float(value.parse()?)
+            } else {
+                RawSchemaDefaultValue::Integer(value.parse()?)
+            }
+        },
+        Rule::string => RawSchemaDefaultValue::String(unescape_string(value)),
+        // true and false are identifiers as well
+        Rule::identifier => match value {
+            "true" => RawSchemaDefaultValue::Bool(true),
+            "false" => RawSchemaDefaultValue::Bool(false),
+            // enum item
+            _ => RawSchemaDefaultValue::String(value.to_string()),
+        },
+        _ => unreachable!(),
+    };
+    Ok(default)
+}
+
 
 pub fn parse(file_path: PathBuf, file_content: &str) -> Result<RawSpec> {
     let mut pairs = crate::api_parser::APIParser::parse(Rule::file, file_content)?;
@@ -475,13 +797,50 @@ pub fn parse(file_path: PathBuf, file_content: &str) -> Result<RawSpec> {
     match pairs.next() {
         Some(pair) => {
             // One api file content only has one Rule::file
-            let file = parse_file(def_loc.clone(), pair)?;
+            let mut file = parse_file(def_loc.clone(), pair)?;
+            crate::resolve_namespaces(&mut file)?;
+            crate::defaults::check_default_values(&file)?;
             Ok(file)
         }
         None => bail!("empty file found"),
     }
 }
 
+/// Parse the type expression of a yaml, json or toml spec, e.g. type: map<string,Todo[]>
+pub fn parse_type_expr_str(def_loc: Arc<DefLoc>, ty: &str) -> Result<RawSchema> {
+    let pair = crate::api_parser::APIParser::parse(Rule::type_expr, ty)
+        .map_err(|err| anyhow::anyhow!("invalid type '{}'\n{}", ty, err))?
+        .next()
+        .unwrap();
+    if pair.as_span().end() != ty.len() {
+        bail!("invalid type '{}', expect a type name, T[] or map<K,V>", ty)
+    }
+    parse_type_expr(def_loc, pair)
+}
+
+fn parse_error_def(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Result<(String, RawError)> {
+    let mut name = String::new();
+    let mut options = serde_yaml::Mapping::new();
+
+    for inner_pair in pair.into_inner() {
+        match inner_pair.as_rule() {
+            Rule::identifier => {
+                name = inner_pair.as_str().to_string();
+            },
+            Rule::option => {
+                let (keys, value) = parse_option(def_loc.clone(), inner_pair)?;
+                insert_value_by_keys(&mut options, keys, value)?;
+            },
+            _ => bail!("unexpected rule found in error declaration: {:?}", inner_pair.as_rule())
+        }
+    }
+
+    Ok((name, RawError {
+        def_loc,
+        option: yaml_mapping_to_option(options)?,
+    }))
+}
+
 fn parse_usecase(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Result<(String, RawUsecase)> {
     let mut methods = HashMap::new();
     let mut usecase_name = String::new();
@@ -507,6 +866,7 @@ fn parse_usecase(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Resu
     Ok((usecase_name, RawUsecase {
         def_loc,
         methods,
+        namespace: None,
         option: if options.is_empty() { None } else { serde_yaml::from_value(serde_yaml::Value::Mapping(options)).unwrap() },
     }))
 }
@@ -516,21 +876,31 @@ fn parse_block(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Result
     for inner_pair in pair.into_inner() {
         match inner_pair.as_rule() {
             Rule::struct_body => {
-                let properties = parse_struct_body(def_loc.clone(), inner_pair)?;
+                let (properties, spreads) = parse_struct_body(def_loc.clone(), inner_pair)?;
 
                 return Ok(RawSchema {
                     def_loc,
                     properties: Some(properties),
                     ty: None,
                     items: None,
+                    keys: None,
+                    values: None,
                     required: None,
+                    nullable: None,
                     namespace: None,
                     enum_items: None,
                     option: None,
                     extends: None,
-                    flat_extends: None,
+                    default: None,
+                    flat_extends: if spreads.is_empty() { None } else { Some(spreads) },
+                    alias: None,
+                    derive: None,
                 })
             },
+            // a named type is used as is, e.g. createTodo NewTodo -> Todo[]
+            Rule::type_expr => {
+                return parse_type_expr(def_loc, inner_pair)
+            },
             _ => {
                 unreachable!()
             }
@@ -541,6 +911,17 @@ fn parse_block(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> Result
     
 }
 
+/// Warn about a deprecated syntax, which `cronus_cli migrate` rewrites to the current one
+fn warn_deprecated(def_loc: &DefLoc, pair: &Pair<Rule>, deprecated: &str, current: &str) {
+    let (line, col) = pair.line_col();
+    let location = if def_loc.file.as_os_str().is_empty() {
+        format!("{}:{}", line, col)
+    } else {
+        format!("{}:{}:{}", def_loc.file.display(), line, col)
+    };
+    tracing::warn!("'{}' is deprecated at {}, use '{}' instead or rewrite the file with 'cronus_cli migrate'", deprecated, location, current);
+}
+
 fn yaml_mapping_to_option< T: DeserializeOwned>(m: serde_yaml::Mapping) -> Result<Option<T>> {
     if m.is_empty() { Ok(None) } else { Ok(serde_yaml::from_value(serde_yaml::Value::Mapping(m))?) }
 }
@@ -549,6 +930,8 @@ fn parse_method_def(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> R
     let mut method_name = String::new();
     let mut req = None;
     let mut res = None;
+    let mut stream = None;
+    let mut errors = None;
     let mut options = serde_yaml::Mapping::new();
 
     for inner_pair in pair.into_inner() {
@@ -570,6 +953,22 @@ fn parse_method_def(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> R
             Rule::out_block => {
                 res = Some(parse_block(def_loc.clone(), inner_pair)?);
             },
+            Rule::legacy_method_body => {
+                warn_deprecated(&def_loc, &inner_pair, "{ in { ... } out { ... } }", "{ ... } -> { ... }");
+                for legacy_pair in inner_pair.into_inner() {
+                    let block = legacy_pair.into_inner().next().unwrap();
+                    match block.as_rule() {
+                        Rule::in_block => req = Some(parse_block(def_loc.clone(), block)?),
+                        _ => res = Some(parse_block(def_loc.clone(), block)?),
+                    }
+                }
+            },
+            Rule::out_stream => {
+                stream = Some(true);
+            },
+            Rule::method_errors => {
+                errors = Some(inner_pair.into_inner().map(|error| error.as_str().to_string()).collect());
+            },
             _ => {}
         }
     }
@@ -577,6 +976,8 @@ fn parse_method_def(def_loc:Arc<DefLoc>, pair: pest::iterators::Pair<Rule>) -> R
     let method = RawUsecaseMethod {
         req,
         res,
+        stream,
+        errors,
         option: yaml_mapping_to_option(options)?
     };
 
@@ -589,6 +990,7 @@ mod tests {
 
 
     use crate::*;
+    use cronus_spec::{RawDeprecated, RawSchemaDefaultValue};
 
     #[test]
     fn can_parse_import() -> Result<()> {
@@ -607,6 +1009,32 @@ import def.api
         Ok(())
     }
 
+    #[test]
+    fn can_parse_scoped_import() -> Result<()> {
+        let api_file: &'static str = r#"
+import { Todo, Status as TodoStatus } from "todo.api"
+import "common.api" as common
+import "plain.api"
+        "#;
+
+        let res = api_parse::parse(PathBuf::from(""),api_file)?;
+
+        assert_eq!(res.imports, Some(vec!["plain.api".to_owned()]));
+        let scoped = res.scoped_imports.as_ref().unwrap();
+        assert_eq!(scoped.len(), 2);
+        assert_eq!(scoped[0].path, "todo.api");
+        assert_eq!(scoped[0].line_col, Some((2, 1)));
+        let names = scoped[0].names.as_ref().unwrap();
+        assert_eq!(names[0].name, "Todo");
+        assert!(names[0].alias.is_none());
+        assert_eq!(names[1].name, "Status");
+        assert_eq!(names[1].alias.as_deref(), Some("TodoStatus"));
+        assert_eq!(scoped[1].path, "common.api");
+        assert_eq!(scoped[1].alias.as_deref(), Some("common"));
+
+        Ok(())
+    }
+
     #[test]
     fn can_parse_usecase() -> Result<()> {
         let api_file: &'static str = r#"
@@ -673,6 +1101,534 @@ struct abc {
 
     }
 
+    #[test]
+    fn can_parse_type_options() -> Result<()> {
+        let api_file: &'static str = r#"
+[description = "a todo"]
+[rust.attrs = ("serde(deny_unknown_fields)")]
+struct Todo {
+    title: string
+}
+
+[openapi.exclude]
+enum Status {
+    Open
+}
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+
+        let tys = spec.ty.unwrap();
+        let todo_option = tys.get("Todo").unwrap().option.as_ref().unwrap();
+        assert_eq!(todo_option.description.as_deref(), Some("a todo"));
+        assert_eq!(todo_option.rust.as_ref().unwrap().attrs, Some(vec!["serde(deny_unknown_fields)".to_string()]));
+        let status_option = tys.get("Status").unwrap().option.as_ref().unwrap();
+        assert_eq!(status_option.openapi.as_ref().unwrap().exclude, Some(true));
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_parse_deprecated() -> Result<()> {
+        let api_file: &'static str = r#"
+[deprecated = "use TodoV2"]
+struct Todo {
+    [deprecated]
+    tag?: string
+    title: string
+}
+
+enum Status {
+    Open
+    [deprecated = "use Open"]
+    Active
+}
+
+[deprecated = false]
+usecase Todo {
+    [deprecated = "use listTodosV2"]
+    listTodos -> Todo[]
+}
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+
+        let tys = spec.ty.as_ref().unwrap();
+        let todo = tys.get("Todo").unwrap();
+        assert_eq!(todo.option.as_ref().unwrap().deprecated, Some(RawDeprecated::Note("use TodoV2".to_string())));
+        let tag = todo.properties.as_ref().unwrap().get("tag").unwrap();
+        assert_eq!(tag.option.as_ref().unwrap().deprecated, Some(RawDeprecated::Flag(true)));
+        let status_items = tys.get("Status").unwrap().enum_items.as_ref().unwrap();
+        assert!(status_items[0].deprecated.is_none());
+        assert_eq!(status_items[1].deprecated.as_ref().and_then(|deprecated| deprecated.note()), Some("use Open"));
+
+        let usecase = spec.usecases.as_ref().unwrap().get("Todo").unwrap();
+        assert!(!usecase.option.as_ref().unwrap().deprecated.as_ref().unwrap().is_deprecated());
+        let method = usecase.methods.get("listTodos").unwrap();
+        assert_eq!(method.option.as_ref().unwrap().deprecated.as_ref().and_then(|deprecated| deprecated.note()), Some("use listTodosV2"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn cannot_parse_parents_of_same_name() {
+        let api_file: &'static str = r#"
+namespace a {
+    struct Base { id: string }
+}
+namespace b {
+    struct Base { name: string }
+}
+struct Todo : a.Base, b.Base {
+    title: string
+}
+        "#;
+
+        let err = api_parse::parse(PathBuf::from(""), api_file).unwrap_err();
+        assert!(err.to_string().contains("parents 'a.Base' and 'b.Base' at 8:23 are both the field 'base' of the struct"), "{}", err);
+    }
+
+    #[test]
+    fn cannot_parse_duplicate_declarations() {
+        let api_file: &'static str = r#"
+namespace a {
+    usecase Pay {
+        pay {}
+    }
+}
+namespace b {
+    usecase Pay {
+        refund {}
+    }
+}
+        "#;
+        let err = api_parse::parse(PathBuf::from(""), api_file).unwrap_err();
+        assert!(err.to_string().contains("usecase 'Pay' at 8:5 is declared twice"), "{}", err);
+
+        let err = api_parse::parse(PathBuf::from(""), "struct Todo {}\nstruct Todo {}").unwrap_err();
+        assert!(err.to_string().contains("type 'Todo' at 2:1 is declared twice"), "{}", err);
+        // the same name in two namespaces are two types
+        assert!(api_parse::parse(PathBuf::from(""), "namespace a { struct Todo {} }\nnamespace b { struct Todo {} }").is_ok());
+    }
+
+    #[test]
+    fn cannot_parse_property_option_on_type() {
+        let api_file: &'static str = r#"
+[max_len = 10]
+struct Todo {
+    title: string
+}
+        "#;
+
+        let err = api_parse::parse(PathBuf::from(""), api_file).unwrap_err();
+        assert!(err.to_string().contains("unsupported option 'max_len' for a type at 2:1"), "{}", err);
+    }
+
+    #[test]
+    fn can_parse_option_literals() -> Result<()> {
+        let api_file: &'static str = r#"
+struct Reading {
+    [min = -1.5] [max = 1e3] [pattern = "^\d+ \"// \\ \t$"] value: float
+    [description = """
+        first line
+          second line
+    """]
+    [min = -10] [max = 9223372036854775808]
+    count: int
+    [max = 100000000000000000000000]
+    total: int
+}
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let properties = spec.ty.as_ref().unwrap().get("Reading").unwrap().properties.as_ref().unwrap();
+        let value_option = properties.get("value").unwrap().option.as_ref().unwrap();
+        assert_eq!(value_option.min, Some(-1.5));
+        assert_eq!(value_option.pattern.as_deref(), Some("^\\d+ \"// \\ \t$"));
+        let count_option = properties.get("count").unwrap().option.as_ref().unwrap();
+        assert_eq!(count_option.min, Some(-10.0));
+        assert_eq!(count_option.description.as_deref(), Some("first line\n  second line"));
+        assert_eq!(count_option.max, Some(9223372036854775808.0));
+        let total_option = properties.get("total").unwrap().option.as_ref().unwrap();
+        assert_eq!(total_option.max, Some(1e23));
+        Ok(())
+    }
+
+    #[test]
+    fn can_parse_nullable_property() -> Result<()> {
+        let api_file: &'static str = r#"
+struct UpdateUser {
+    name?: string | null
+    email: string | null
+    age?: int
+}
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let properties = spec.ty.as_ref().unwrap().get("UpdateUser").unwrap().properties.as_ref().unwrap();
+        let name = properties.get("name").unwrap();
+        assert_eq!((name.required, name.nullable), (Some(false), Some(true)));
+        let email = properties.get("email").unwrap();
+        assert_eq!((email.required, email.nullable), (Some(true), Some(true)));
+        let age = properties.get("age").unwrap();
+        assert_eq!((age.required, age.nullable), (Some(false), None));
+        Ok(())
+    }
+
+    #[test]
+    fn can_parse_readonly_writeonly() -> Result<()> {
+        let api_file: &'static str = r#"
+struct User {
+    [readonly] id: string
+    [writeonly] password: string
+    name: string
+}
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let properties = spec.ty.as_ref().unwrap().get("User").unwrap().properties.as_ref().unwrap();
+        let id = properties.get("id").unwrap().option.as_ref().unwrap();
+        assert_eq!((id.readonly, id.writeonly), (Some(true), None));
+        let password = properties.get("password").unwrap().option.as_ref().unwrap();
+        assert_eq!((password.readonly, password.writeonly), (None, Some(true)));
+        assert!(properties.get("name").unwrap().option.is_none());
+
+        let err = api_parse::parse(PathBuf::from(""), "struct User {\n  [readonly] [writeonly] id: string\n}").unwrap_err();
+        assert!(err.to_string().contains("property 'id' can't be both readonly and writeonly at 2:3"), "{}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn can_parse_extensions() -> Result<()> {
+        let api_file: &'static str = r#"
+[x.audit.level = 2]
+struct User {
+    [x.audit = "pii"] [x.tags = ("a", "b")] email: string
+}
+
+[x.owner = "billing-team"]
+usecase Users {
+    [x.rate_limit = { per_second = 10 }]
+    getUser { id: string } -> { user: User }
+}
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let user = spec.ty.as_ref().unwrap().get("User").unwrap();
+        let user_x = user.option.as_ref().unwrap().x.as_ref().unwrap();
+        assert_eq!(serde_json::to_value(user_x)?, serde_json::json!({ "audit": { "level": 2 } }));
+        let email_x = user.properties.as_ref().unwrap().get("email").unwrap().option.as_ref().unwrap().x.as_ref().unwrap();
+        assert_eq!(serde_json::to_value(email_x)?, serde_json::json!({ "audit": "pii", "tags": ["a", "b"] }));
+
+        let usecase = spec.usecases.as_ref().unwrap().get("Users").unwrap();
+        let usecase_x = usecase.option.as_ref().unwrap().x.as_ref().unwrap();
+        assert_eq!(serde_json::to_value(usecase_x)?, serde_json::json!({ "owner": "billing-team" }));
+        let method_x = usecase.methods.get("getUser").unwrap().option.as_ref().unwrap().x.as_ref().unwrap();
+        assert_eq!(serde_json::to_value(method_x)?, serde_json::json!({ "rate_limit": { "per_second": 10 } }));
+
+        // kept by the yaml conversion
+        let yaml_spec = crate::from_yaml_str(&crate::to_yaml_str(&spec)?)?;
+        assert_eq!(serde_json::to_value(&spec)?, serde_json::to_value(&yaml_spec)?);
+        Ok(())
+    }
+
+    #[test]
+    fn can_parse_option_map() -> Result<()> {
+        use pest::Parser;
+        let mut pairs = crate::api_parser::APIParser::parse(crate::api_parser::Rule::option, r#"[headers = { accept = "json", "X-Retry": (1, 2,), nested = { on = true } }]"#)?;
+        let (keys, value) = super::parse_option(std::sync::Arc::new(cronus_spec::DefLoc::default()), pairs.next().unwrap())?;
+        assert_eq!(keys, vec!["headers".to_string()]);
+        assert_eq!(value, serde_yaml::from_str::<serde_yaml::Value>(r#"{accept: json, X-Retry: [1, 2], nested: {on: true}}"#)?);
+        Ok(())
+    }
+
+    #[test]
+    fn can_parse_type_expr() -> Result<()> {
+        let api_file: &'static str = r#"
+        struct Todo {
+            tags: string[][]
+            by_user: map<string, Todo[]>
+            nested: map<string,map<string,int>>
+            spaced: map< string , int >[]
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let tys = spec.ty.unwrap();
+        let props = tys.get("Todo").unwrap().properties.as_ref().unwrap();
+
+        let tags = props.get("tags").unwrap();
+        assert!(tags.ty.is_none());
+        let tags_items = tags.items.as_ref().unwrap();
+        assert!(tags_items.ty.is_none());
+        assert_eq!(tags_items.items.as_ref().unwrap().ty.as_deref(), Some("string"));
+
+        let by_user = props.get("by_user").unwrap();
+        assert!(by_user.ty.is_none());
+        assert_eq!(by_user.keys.as_deref(), Some("string"));
+        let by_user_values = by_user.values.as_ref().unwrap();
+        assert_eq!(by_user_values.items.as_ref().unwrap().ty.as_deref(), Some("Todo"));
+
+        let nested_values = props.get("nested").unwrap().values.as_ref().unwrap();
+        assert_eq!(nested_values.keys.as_deref(), Some("string"));
+        assert_eq!(nested_values.values.as_ref().unwrap().ty.as_deref(), Some("int"));
+
+        let spaced_items = props.get("spaced").unwrap().items.as_ref().unwrap();
+        assert_eq!(spaced_items.keys.as_deref(), Some("string"));
+        assert_eq!(spaced_items.values.as_ref().unwrap().ty.as_deref(), Some("int"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn cannot_parse_malformed_type_expr() {
+        for ty in ["map<string>", "map<string,int", "string[", "map", "map<string[],int>"] {
+            let api_file = format!("struct Todo {{\n    a: {}\n}}", ty);
+            let err = api_parse::parse(PathBuf::from(""), &api_file).unwrap_err();
+            // either the syntax error of pest or ours, both are located
+            let message = err.to_string();
+            assert!(message.contains(" --> ") || message.contains(" at 2:"), "{}: {}", ty, message);
+        }
+    }
+
+    #[test]
+    fn can_parse_named_method_io() -> Result<()> {
+        let api_file: &'static str = r#"
+        usecase todo {
+            createTodo NewTodo -> Todo errors(Conflict)
+            listTodos -> Todo[]
+            ping
+            getInvoice billing.InvoiceQuery -> string
+            watchTodos -> stream Todo
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let methods = &spec.usecases.as_ref().unwrap().get("todo").unwrap().methods;
+        assert_eq!(methods.len(), 5);
+
+        let create_todo = methods.get("createTodo").unwrap();
+        assert_eq!(create_todo.req.as_ref().unwrap().ty.as_deref(), Some("NewTodo"));
+        assert!(create_todo.req.as_ref().unwrap().properties.is_none());
+        assert_eq!(create_todo.res.as_ref().unwrap().ty.as_deref(), Some("Todo"));
+        assert_eq!(create_todo.errors, Some(vec!["Conflict".to_string()]));
+
+        let list_todos = methods.get("listTodos").unwrap();
+        assert!(list_todos.req.is_none());
+        assert_eq!(list_todos.res.as_ref().unwrap().items.as_ref().unwrap().ty.as_deref(), Some("Todo"));
+
+        // a lowercase name after a method is the next method rather than its input
+        assert!(methods.get("ping").unwrap().req.is_none());
+        assert_eq!(methods.get("getInvoice").unwrap().req.as_ref().unwrap().ty.as_deref(), Some("billing.InvoiceQuery"));
+        assert_eq!(methods.get("getInvoice").unwrap().res.as_ref().unwrap().ty.as_deref(), Some("string"));
+        assert_eq!(methods.get("watchTodos").unwrap().stream, Some(true));
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_parse_errors() -> Result<()> {
+        let api_file: &'static str = r#"
+        [rest.status = 404]
+        error NotFound
+
+        error Conflict
+
+        usecase todo {
+            get_todo {
+                id: string
+            } -> {
+                title: string
+            } errors(NotFound, Conflict)
+            list_todos
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let errors = spec.errors.as_ref().unwrap();
+        assert_eq!(errors.get("NotFound").unwrap().status(), 404);
+        // a client error if the status is not given
+        assert_eq!(errors.get("Conflict").unwrap().status(), 400);
+
+        let methods = &spec.usecases.as_ref().unwrap().get("todo").unwrap().methods;
+        assert_eq!(methods.get("get_todo").unwrap().errors, Some(vec!["NotFound".to_string(), "Conflict".to_string()]));
+        assert!(methods.get("list_todos").unwrap().errors.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_parse_stream_method() -> Result<()> {
+        let api_file: &'static str = r#"
+        usecase todo {
+            watch_todos {} -> stream {
+                title: string
+            }
+            get_todo {} -> {
+                title: string
+            }
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let methods = &spec.usecases.as_ref().unwrap().get("todo").unwrap().methods;
+        assert_eq!(methods.get("watch_todos").unwrap().stream, Some(true));
+        assert!(methods.get("watch_todos").unwrap().res.is_some());
+        assert!(methods.get("get_todo").unwrap().stream.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_parse_namespace() -> Result<()> {
+        let api_file: &'static str = r#"
+        struct Item {
+            id: string
+        }
+        namespace billing {
+            struct Item {
+                price: int
+            }
+            struct Invoice : Item {
+                items: map<string, Item[]>
+                shared: shop.Item
+            }
+            usecase payment {
+                pay { invoice: Invoice } -> { item: Item }
+            }
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let tys = spec.ty.as_ref().unwrap();
+        assert!(tys.contains_key("Item"));
+        assert!(tys.contains_key("billing.Item"));
+
+        let invoice = tys.get("billing.Invoice").unwrap();
+        assert_eq!(invoice.namespace.as_deref(), Some("billing"));
+        assert_eq!(invoice.extends.as_ref().unwrap().get("item").unwrap(), "billing.Item");
+        let props = invoice.properties.as_ref().unwrap();
+        let items_values = props.get("items").unwrap().values.as_ref().unwrap();
+        assert_eq!(items_values.items.as_ref().unwrap().ty.as_deref(), Some("billing.Item"));
+        assert_eq!(props.get("shared").unwrap().ty.as_deref(), Some("shop.Item"));
+
+        let method = spec.usecases.as_ref().unwrap().get("payment").unwrap().methods.get("pay").unwrap();
+        let res = method.res.as_ref().unwrap().properties.as_ref().unwrap();
+        assert_eq!(res.get("item").unwrap().ty.as_deref(), Some("billing.Item"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_parse_package() -> Result<()> {
+        let api_file: &'static str = r#"
+        package billing
+
+        struct Invoice {
+            id: string
+        }
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+        let invoice = spec.ty.as_ref().unwrap().get("billing.Invoice").unwrap();
+        assert_eq!(invoice.namespace.as_deref(), Some("billing"));
+
+        let api_file: &'static str = r#"
+        package billing
+
+        namespace shop {
+        }
+        "#;
+        assert!(api_parse::parse(PathBuf::from(""), api_file).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_parse_default_value() -> Result<()> {
+        let api_file: &'static str = r#"
+struct abc {
+    a: int = 10
+    b: float = -1.5
+    c?: string = "hi"
+    d: bool = true
+    e: Status = Active
+    f: int
+}
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+
+        let tys = spec.ty.unwrap();
+        let properties = tys.get("abc").unwrap().properties.as_ref().unwrap();
+        assert_eq!(properties.get("a").unwrap().default, Some(RawSchemaDefaultValue::Integer(10)));
+        assert_eq!(properties.get("b").unwrap().default, Some(RawSchemaDefaultValue::Float(-1.5)));
+        assert_eq!(properties.get("c").unwrap().default, Some(RawSchemaDefaultValue::String("hi".to_string())));
+        assert_eq!(properties.get("d").unwrap().default, Some(RawSchemaDefaultValue::Bool(true)));
+        assert_eq!(properties.get("e").unwrap().default, Some(RawSchemaDefaultValue::String("Active".to_string())));
+        assert_eq!(properties.get("f").unwrap().default, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_parse_type_def() -> Result<()> {
+        let api_file: &'static str = r#"
+[@rust = "chrono::DateTime<chrono::Utc>"]
+[@golang.type = "time.Time"]
+[@golang.import = "time"]
+[@openapi.format = "date-time"]
+type Timestamp
+
+type Opaque
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+
+        let tys = spec.ty.unwrap();
+        let alias = tys.get("Timestamp").unwrap().alias.as_ref().unwrap();
+        assert_eq!(alias.rust.as_ref().unwrap().ty(), "chrono::DateTime<chrono::Utc>");
+        assert_eq!(alias.golang.as_ref().unwrap().ty(), "time.Time");
+        assert_eq!(alias.golang.as_ref().unwrap().import(), Some("time"));
+        assert_eq!(alias.openapi.as_ref().unwrap().format, Some("date-time".to_string()));
+        assert!(alias.python.is_none());
+        assert!(tys.get("Opaque").unwrap().alias.is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn cannot_parse_type_def_with_non_generator_option() {
+        let api_file: &'static str = r#"
+[description = "abc"]
+type Timestamp
+        "#;
+
+        assert!(api_parse::parse(PathBuf::from(""), api_file).is_err());
+    }
+
+    #[test]
+    fn can_parse_struct_inheritance_and_spread() -> Result<()> {
+        let api_file: &'static str = r#"
+struct Admin : User, AuditInfo {
+    ...Timestamps
+    level: int
+}
+        "#;
+
+        let spec = api_parse::parse(PathBuf::from(""), api_file)?;
+
+        let tys = spec.ty.unwrap();
+        let admin = tys.get("Admin").unwrap();
+        let extends = admin.extends.as_ref().unwrap();
+        assert_eq!(extends.get("user"), Some(&"User".to_string()));
+        assert_eq!(extends.get("audit_info"), Some(&"AuditInfo".to_string()));
+        assert_eq!(admin.flat_extends, Some(vec!["Timestamps".to_string()]));
+        assert_eq!(admin.properties.as_ref().unwrap().len(), 1);
+
+        Ok(())
+    }
+
     #[test]
     #[should_panic(expected = "unknown field")]
     fn cannot_parse_undefined_option()  {
@@ -0,0 +1,507 @@
+use std::collections::BTreeMap;
+use anyhow::{bail, Result};
+use cronus_spec::{RawError, RawImport, RawSchema, RawSchemaDefaultValue, RawSchemaDeriveOp, RawSpec, RawUsecase, RawUsecaseMethod};
+use serde::Serialize;
+
+const INDENT: &str = "    ";
+
+/// Print a spec in the api syntax, the reverse of api_parse::parse
+///
+/// The definitions are sorted by their names, and the ones of a namespace are put into its namespace block.
+/// A spec from a yaml file may have schemas the api syntax can't express (e.g. inline objects), which is an error.
+pub fn print(spec: &RawSpec) -> Result<String> {
+    let mut sections: Vec<String> = Vec::new();
+
+    if let Some(option) = &spec.option {
+        let lines = flatten_option(option)?
+            .iter()
+            .map(|(keys, value)| Ok(format!("#{}", format_option(keys, value)?)))
+            .collect::<Result<Vec<String>>>()?;
+        if !lines.is_empty() {
+            sections.push(lines.join("\n"));
+        }
+    }
+
+    let mut imports: Vec<String> = spec.imports.iter().flatten()
+        .map(|import| format!("import \"{}\"", import))
+        .collect();
+    for import in spec.scoped_imports.iter().flatten() {
+        imports.push(print_scoped_import(import));
+    }
+    if !imports.is_empty() {
+        sections.push(imports.join("\n"));
+    }
+
+    let errors: BTreeMap<&String, &RawError> = spec.errors.iter().flatten().collect();
+    for (name, error) in errors {
+        sections.push(print_error(name, error)?);
+    }
+
+    // the declarations of each namespace, the top level ones are in None
+    let mut namespaces: BTreeMap<Option<&str>, Vec<String>> = BTreeMap::new();
+
+    let tys: BTreeMap<&String, &RawSchema> = spec.ty.iter().flatten().collect();
+    for (name, schema) in tys {
+        let (namespace, local_name) = match name.rsplit_once('.') {
+            Some((namespace, local_name)) => (Some(namespace), local_name),
+            None => (schema.namespace.as_deref(), name.as_str()),
+        };
+        namespaces.entry(namespace).or_default().push(print_ty(local_name, schema)?);
+    }
+
+    let usecases: BTreeMap<&String, &RawUsecase> = spec.usecases.iter().flatten().collect();
+    for (name, usecase) in usecases {
+        namespaces.entry(usecase.namespace.as_deref()).or_default().push(print_usecase(name, usecase)?);
+    }
+
+    for (namespace, declarations) in namespaces {
+        match namespace {
+            Some(namespace) => {
+                let body = declarations.iter()
+                    .map(|declaration| indent(declaration))
+                    .collect::<Vec<String>>()
+                    .join("\n\n");
+                sections.push(format!("namespace {} {{\n{}\n}}", namespace, body));
+            },
+            None => sections.extend(declarations),
+        }
+    }
+
+    let mut result = sections.join("\n\n");
+    result.push('\n');
+    Ok(result)
+}
+
+fn print_scoped_import(import: &RawImport) -> String {
+    match (&import.names, &import.alias) {
+        (Some(names), _) => {
+            let names = names.iter()
+                .map(|name| match &name.alias {
+                    Some(alias) => format!("{} as {}", name.name, alias),
+                    None => name.name.clone(),
+                })
+                .collect::<Vec<String>>()
+                .join(", ");
+            format!("import {{ {} }} from \"{}\"", names, import.path)
+        },
+        (None, Some(alias)) => format!("import \"{}\" as {}", import.path, alias),
+        (None, None) => format!("import \"{}\"", import.path),
+    }
+}
+
+fn print_error(name: &str, error: &RawError) -> Result<String> {
+    let mut result = print_options(&error.option)?;
+    result += &format!("error {}", name);
+    Ok(result)
+}
+
+fn print_ty(name: &str, schema: &RawSchema) -> Result<String> {
+    if let Some(alias) = &schema.alias {
+        let mut result = String::new();
+        for (mut keys, value) in flatten_option(alias)? {
+            // the per-language types are the generator options of the declared type, e.g. [@rust = "..."]
+            keys[0].insert(0, '@');
+            result += &format!("{}\n", format_option(&keys, &value)?);
+        }
+        result += &format!("type {}", name);
+        return Ok(result)
+    }
+
+    let mut result = String::new();
+    for (keys, value) in flatten_option(&schema.option)? {
+        if !crate::api_parse::is_type_option(&keys) {
+            bail!("the option '{}' of type '{}' can't be written in the api syntax", keys.join("."), name)
+        }
+        result += &format!("{}\n", format_option(&keys, &value)?);
+    }
+
+    if let Some(derive) = &schema.derive {
+        let op = match derive.op {
+            RawSchemaDeriveOp::Partial => "partial",
+            RawSchemaDeriveOp::Pick => "pick",
+            RawSchemaDeriveOp::Omit => "omit",
+        };
+        result += &format!("struct {} = {} {}", name, op, derive.from);
+        if let Some(fields) = &derive.fields {
+            result += &format!(" ({})", fields.join(", "));
+        }
+        return Ok(result)
+    }
+
+    if let Some(enum_items) = &schema.enum_items {
+        result += &format!("enum {} {{\n", name);
+        for item in enum_items {
+            result += INDENT;
+            if let Some(value) = item.value {
+                result += &format!("[value = {}] ", value);
+            }
+            if let Some(deprecated) = &item.deprecated {
+                result += &format!("{} ", format_option(&["deprecated".to_string()], &serde_yaml::to_value(deprecated)?)?);
+            }
+            result += &format!("{}\n", item.name);
+        }
+        result.push('}');
+        return Ok(result)
+    }
+
+    if schema.ty.is_some() || schema.items.is_some() || schema.values.is_some() {
+        bail!("type '{}' is not a struct, an enum or a declared type, it can't be written in the api syntax", name)
+    }
+    result += &format!("struct {}", name);
+    if let Some(extends) = &schema.extends {
+        let mut parents: Vec<&String> = extends.values().collect();
+        parents.sort();
+        result += &format!(" : {}", parents.iter().map(|parent| parent.as_str()).collect::<Vec<&str>>().join(", "));
+    }
+    result += &format!(" {}", print_struct_body(schema)?);
+    Ok(result)
+}
+
+fn print_struct_body(schema: &RawSchema) -> Result<String> {
+    let mut result = String::from("{\n");
+    for spread in schema.flat_extends.iter().flatten() {
+        result += &format!("{}...{}\n", INDENT, spread);
+    }
+    let properties: BTreeMap<&String, &RawSchema> = schema.properties.iter().flatten().collect();
+    for (name, property) in properties {
+        result += &format!("{}{}\n", INDENT, print_property(name, property)?);
+    }
+    result.push('}');
+    Ok(result)
+}
+
+fn print_property(name: &str, schema: &RawSchema) -> Result<String> {
+    let mut result = String::new();
+    for (keys, value) in flatten_option(&schema.option)? {
+        result += &format!("{} ", format_option(&keys, &value)?);
+    }
+    result += name;
+    if schema.required != Some(true) {
+        result.push('?');
+    }
+    let ty = print_type_expr(schema).map_err(|err| anyhow::anyhow!("{} of property '{}'", err, name))?;
+    result += &format!(": {}", ty);
+    if schema.nullable == Some(true) {
+        result += " | null";
+    }
+    if let Some(default) = &schema.default {
+        result += &format!(" = {}", print_default_value(default, &ty)?);
+    }
+    Ok(result)
+}
+
+/// Print the type of a property, e.g. string, Todo[][] or map<string,Todo[]>
+fn print_type_expr(schema: &RawSchema) -> Result<String> {
+    if let Some(items) = &schema.items {
+        return Ok(format!("{}[]", print_type_expr(items)?))
+    }
+    if let Some(values) = &schema.values {
+        return Ok(format!("map<{},{}>", schema.keys.as_deref().unwrap_or("string"), print_type_expr(values)?))
+    }
+    match &schema.ty {
+        Some(ty) if !ty.is_empty() => Ok(ty.clone()),
+        _ => bail!("the inline type can't be written in the api syntax"),
+    }
+}
+
+fn print_default_value(default: &RawSchemaDefaultValue, ty: &str) -> Result<String> {
+    let value = match default {
+        RawSchemaDefaultValue::Bool(value) => value.to_string(),
+        RawSchemaDefaultValue::Integer(value) => value.to_string(),
+        // keep the fraction, 1.0 is a float rather than an integer
+        RawSchemaDefaultValue::Float(value) => format!("{:?}", value),
+        // an enum item is given by its name
+        RawSchemaDefaultValue::String(value) if ty != "string" && is_identifier(value) => value.clone(),
+        RawSchemaDefaultValue::String(value) => quote(value),
+    };
+    Ok(value)
+}
+
+fn print_usecase(name: &str, usecase: &RawUsecase) -> Result<String> {
+    let mut result = print_options(&usecase.option)?;
+    result += &format!("usecase {} {{\n", name);
+    let methods: BTreeMap<&String, &RawUsecaseMethod> = usecase.methods.iter().collect();
+    for (method_name, method) in methods {
+        let method = print_method(method_name, method)
+            .map_err(|err| anyhow::anyhow!("{} in usecase '{}'", err, name))?;
+        result += &format!("{}\n", indent(&method));
+    }
+    result.push('}');
+    Ok(result)
+}
+
+fn print_method(name: &str, method: &RawUsecaseMethod) -> Result<String> {
+    let mut result = String::new();
+    for (keys, value) in flatten_option(&method.option)? {
+        result += &format!("{}\n", format_option(&keys, &value)?);
+    }
+    result += name;
+    if let Some(req) = &method.req {
+        result += &format!(" {}", print_block(name, req, true)?);
+    }
+    if let Some(res) = &method.res {
+        result += " -> ";
+        if method.stream == Some(true) {
+            result += "stream ";
+        }
+        result += &print_block(name, res, false)?;
+    }
+    if let Some(errors) = &method.errors {
+        result += &format!(" errors({})", errors.join(", "));
+    }
+    Ok(result)
+}
+
+/// Print the input or output of a method, which is a struct body or a named type
+fn print_block(method_name: &str, schema: &RawSchema, is_input: bool) -> Result<String> {
+    if schema.ty.is_some() || schema.items.is_some() || schema.values.is_some() {
+        let ty = print_type_expr(schema)?;
+        // the named input is told apart from the name of the next method by its first letter or its namespace
+        if is_input && !(ty.starts_with(|c: char| c.is_ascii_uppercase()) || ty.contains('.') && is_identifier(ty.split('.').next().unwrap())) {
+            bail!("the input '{}' of method '{}' does not start with an uppercase letter, it can't be written in the api syntax", ty, method_name)
+        }
+        return Ok(ty)
+    }
+    if schema.extends.is_some() {
+        bail!("the input or output of method '{}' is not a struct body, it can't be written in the api syntax", method_name)
+    }
+    print_struct_body(schema)
+}
+
+fn print_options<T: Serialize>(option: &T) -> Result<String> {
+    let mut result = String::new();
+    for (keys, value) in flatten_option(option)? {
+        result += &format!("{}\n", format_option(&keys, &value)?);
+    }
+    Ok(result)
+}
+
+/// Flatten an option into the keys and the value of each one, e.g. rest.method = "get"
+fn flatten_option<T: Serialize>(option: &T) -> Result<Vec<(Vec<String>, serde_yaml::Value)>> {
+    let mut result = Vec::new();
+    flatten_value(Vec::new(), serde_yaml::to_value(option)?, &mut result)?;
+    Ok(result)
+}
+
+fn flatten_value(keys: Vec<String>, value: serde_yaml::Value, result: &mut Vec<(Vec<String>, serde_yaml::Value)>) -> Result<()> {
+    match value {
+        serde_yaml::Value::Null => {},
+        // [redis] is the only option parsed as an empty mapping
+        serde_yaml::Value::Mapping(mapping) if mapping.is_empty() => {
+            if keys.len() == 1 && keys[0] == "redis" {
+                result.push((keys, serde_yaml::Value::Bool(true)));
+            }
+        },
+        // keys which are not a part of an option name are written as a map, e.g. [headers = { "X-Api" = "v1" }]
+        serde_yaml::Value::Mapping(mapping) if !keys.is_empty() && !mapping.iter().all(|(key, _)| is_option_key(key)) => {
+            result.push((keys, serde_yaml::Value::Mapping(mapping)));
+        },
+        serde_yaml::Value::Mapping(mapping) => {
+            for (key, value) in mapping {
+                let key = match key {
+                    serde_yaml::Value::String(key) => key,
+                    _ => bail!("unexpected option key {:?}", key),
+                };
+                let mut keys = keys.clone();
+                keys.push(key);
+                flatten_value(keys, value, result)?;
+            }
+        },
+        value => result.push((keys, value)),
+    }
+    Ok(())
+}
+
+fn format_option(keys: &[String], value: &serde_yaml::Value) -> Result<String> {
+    let key = keys.join(".");
+    match value {
+        // means it is implicitly boolean
+        serde_yaml::Value::Bool(true) => Ok(format!("[{}]", key)),
+        value => Ok(format!("[{} = {}]", key, format_option_value(value)?)),
+    }
+}
+
+fn format_option_value(value: &serde_yaml::Value) -> Result<String> {
+    match value {
+        serde_yaml::Value::Bool(value) => Ok(value.to_string()),
+        serde_yaml::Value::Number(number) => match (number.as_i64(), number.as_u64(), number.as_f64()) {
+            (Some(value), _, _) => Ok(value.to_string()),
+            (None, Some(value), _) => Ok(value.to_string()),
+            // keep the fraction, 1.0 is a float rather than an integer
+            (None, None, Some(value)) if value.is_finite() => Ok(format!("{:?}", value)),
+            _ => bail!("the option value {} can't be written in the api syntax", number),
+        },
+        serde_yaml::Value::String(value) => Ok(quote(value)),
+        serde_yaml::Value::Sequence(values) => {
+            let values = values.iter().map(format_option_value).collect::<Result<Vec<String>>>()?;
+            Ok(format!("({})", values.join(", ")))
+        },
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut entries = Vec::new();
+            for (key, value) in mapping {
+                let key = match key {
+                    serde_yaml::Value::String(key) if is_identifier(key) => key.clone(),
+                    serde_yaml::Value::String(key) => quote(key),
+                    _ => bail!("the option key {:?} can't be written in the api syntax", key),
+                };
+                entries.push(format!("{} = {}", key, format_option_value(value)?));
+            }
+            Ok(format!("{{ {} }}", entries.join(", ")))
+        },
+        _ => bail!("the option value {:?} can't be written in the api syntax", value),
+    }
+}
+
+fn quote(value: &str) -> String {
+    let mut result = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => result += "\\\"",
+            '\\' => result += "\\\\",
+            '\n' => result += "\\n",
+            '\t' => result += "\\t",
+            '\r' => result += "\\r",
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// Whether a key of a mapping can be a part of an option name, e.g. rest of rest.method
+fn is_option_key(key: &serde_yaml::Value) -> bool {
+    key.as_str().is_some_and(|key| !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+}
+
+fn is_identifier(value: &str) -> bool {
+    let mut chars = value.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && value != "true" && value != "false"
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| if line.is_empty() { String::new() } else { format!("{}{}", INDENT, line) })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use anyhow::Result;
+
+    /// Parse the printed spec again, which has to be the same spec
+    fn assert_round_trip(api: &str) -> Result<String> {
+        let spec = crate::api_parse::parse(PathBuf::new(), api)?;
+        let printed = super::print(&spec)?;
+        let reparsed = crate::api_parse::parse(PathBuf::new(), &printed)?;
+        assert_eq!(serde_json::to_value(&spec)?, serde_json::to_value(&reparsed)?, "{}", printed);
+        Ok(printed)
+    }
+
+    #[test]
+    fn can_print_spec() -> Result<()> {
+        let printed = assert_round_trip(r#"
+        #[generator.rust.file = "src/generated.rs"]
+        #[generator.rust.uses = ("serde", "chrono")]
+
+        [rest.status = 404]
+        error NotFound
+
+        [@golang.type = "time.Time"]
+        [@golang.import = "time"]
+        [@rust = "String"]
+        type Timestamp
+
+        [openapi.exclude]
+        enum Status {
+            Open
+            [value = 2] Done
+            [value = -1] Unknown
+            [value = 3] [deprecated = "use Done"] Closed
+        }
+
+        [description = "the base"]
+        [x.audit = { level = 2, "by-team" = "core" }]
+        [rust.attrs = ("serde(deny_unknown_fields)")]
+        struct Base {
+            id: string
+        }
+
+        struct Todo : Base {
+            ...Base
+            [description = "the title"] [max_len = 20] title: string = "todo"
+            status: Status = Open
+            tags?: string[][]
+            counts: map<string,int[]>
+            ratio: float = 1.0
+            note?: string | null
+            [min = -1.5] [pattern = "^\d+ \"quoted\"$"] [description = """
+                first line
+                second line
+            """]
+            score: float = -0.5
+        }
+
+        struct NewTodo = omit Todo (status, tags)
+        struct UpdateTodo = partial Todo
+
+        namespace billing {
+            struct Invoice {
+                todo: Todo
+                total: billing.Money
+            }
+            struct Money {
+                amount: int
+            }
+        }
+
+        [rest.path = "todos"]
+        usecase Todo {
+            [rest.method = "get"]
+            [x.rate_limit = 10]
+            getTodo {
+                id: string
+            } -> {
+                todo: Todo
+            } errors(NotFound)
+
+            createTodo NewTodo -> Todo
+            [deprecated]
+            listTodos -> Todo[]
+            createInvoice billing.Invoice -> string
+
+            [redis]
+            watchTodos -> stream {
+                todo: Todo
+            }
+        }
+        "#)?;
+
+        assert!(printed.contains("namespace billing {\n    struct Invoice {"), "{}", printed);
+        assert!(printed.contains("    createTodo NewTodo -> Todo\n"), "{}", printed);
+        assert!(printed.contains("    [deprecated]\n    listTodos -> Todo[]\n"), "{}", printed);
+        assert!(printed.contains("    [value = 3] [deprecated = \"use Done\"] Closed\n"), "{}", printed);
+        assert!(printed.contains("[description = \"the title\"] [max_len = 20] title: string = \"todo\""), "{}", printed);
+        assert!(printed.contains("[description = \"first line\\nsecond line\"] [min = -1.5] [pattern = \"^\\\\d+ \\\"quoted\\\"$\"] score: float = -0.5"), "{}", printed);
+        Ok(())
+    }
+
+    #[test]
+    fn cannot_print_inline_object() -> Result<()> {
+        let spec = crate::from_yaml_str(r#"
+        types:
+          Todo:
+            properties:
+              owner:
+                properties:
+                  name:
+                    type: string
+        "#)?;
+        let err = super::print(&spec).unwrap_err();
+        assert!(err.to_string().contains("property 'owner'"), "{}", err);
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use anyhow::{bail, Result};
+use cronus_spec::{RawSchema, RawSchemaDefaultValue, RawSpec};
+
+/// Check that the default value of every scalar property fits the type of the property, e.g. count: u32 = -1 is an error
+///
+/// The defaults of enum properties are checked against the items of the enum by the generators.
+pub fn check_default_values(spec: &RawSpec) -> Result<()> {
+    let mut tys: Vec<(&String, &RawSchema)> = spec.ty.iter().flatten().collect();
+    tys.sort_by_key(|(name, _)| *name);
+    for (name, schema) in tys {
+        check_properties(name, schema)?;
+    }
+
+    let mut usecases: Vec<_> = spec.usecases.iter().flatten().collect();
+    usecases.sort_by_key(|(name, _)| *name);
+    for (usecase_name, usecase) in usecases {
+        for (method_name, method) in &usecase.methods {
+            let owner = format!("{}.{}", usecase_name, method_name);
+            for schema in method.req.iter().chain(method.res.iter()) {
+                check_properties(&owner, schema)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_properties(owner: &str, schema: &RawSchema) -> Result<()> {
+    for (prop_name, prop) in schema.properties.iter().flatten() {
+        if let (Some(default), Some(ty)) = (&prop.default, &prop.ty) {
+            if !fits(default, ty) {
+                bail!("default value {} of property '{}' in '{}' does not fit its type '{}'", print_default_value(default), prop_name, owner, ty)
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether the default value is a literal of the scalar type, any other type is not checked here
+fn fits(default: &RawSchemaDefaultValue, ty: &str) -> bool {
+    match (ty, default) {
+        ("string", value) => matches!(value, RawSchemaDefaultValue::String(_)),
+        ("bool" | "boolean", value) => matches!(value, RawSchemaDefaultValue::Bool(_)),
+        ("i32" | "int" | "integer", RawSchemaDefaultValue::Integer(value)) => i32::try_from(*value).is_ok(),
+        ("u32", RawSchemaDefaultValue::Integer(value)) => u32::try_from(*value).is_ok(),
+        ("i64" | "int64", RawSchemaDefaultValue::Integer(_)) => true,
+        ("u64" | "uint64", RawSchemaDefaultValue::Integer(value)) => *value >= 0,
+        ("i32" | "int" | "integer" | "u32" | "i64" | "int64" | "u64" | "uint64", _) => false,
+        ("f32" | "f64" | "float" | "double" | "number", value) => matches!(value, RawSchemaDefaultValue::Integer(_) | RawSchemaDefaultValue::Float(_)),
+        _ => true,
+    }
+}
+
+fn print_default_value(default: &RawSchemaDefaultValue) -> String {
+    match default {
+        RawSchemaDefaultValue::Bool(value) => value.to_string(),
+        RawSchemaDefaultValue::Integer(value) => value.to_string(),
+        RawSchemaDefaultValue::Float(value) => value.to_string(),
+        RawSchemaDefaultValue::String(value) => format!("{:?}", value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    #[test]
+    fn cannot_parse_default_value_of_other_type() -> Result<()> {
+        for (api, message) in [
+            ("struct X { count: u32 = -1 }", "default value -1 of property 'count' in 'X' does not fit its type 'u32'"),
+            ("struct X { name: string = 3 }", "default value 3 of property 'name' in 'X' does not fit its type 'string'"),
+            ("struct X { done: bool = \"yes\" }", "default value \"yes\" of property 'done' in 'X' does not fit its type 'bool'"),
+            ("struct X { count: i32 = 3000000000 }", "does not fit its type 'i32'"),
+            ("struct X { count: int = 1.5 }", "does not fit its type 'int'"),
+            ("usecase Todo { listTodos { page: int = true } }", "property 'page' in 'Todo.listTodos'"),
+        ] {
+            let err = crate::from_api_str(api).unwrap_err();
+            assert!(err.to_string().contains(message), "{}", err);
+        }
+
+        let spec = crate::from_api_str("struct X { ratio: float = 1\ncount: u64 = 10\nname: string = \"a\" }")?;
+        assert!(spec.ty.unwrap().contains_key("X"));
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::{HashMap, HashSet};
+use anyhow::{bail, Result};
+use cronus_spec::{RawSchema, RawSchemaDeriveOp, RawSpec};
+
+/// Replace every derived struct (struct UpdateTodo = partial Todo) by the properties it takes from its struct,
+/// which can be a derived struct as well
+pub fn resolve_derived_types(spec: &mut RawSpec) -> Result<()> {
+    let mut names: Vec<String> = spec.ty.iter()
+        .flatten()
+        .filter(|(_, schema)| schema.derive.is_some())
+        .map(|(name, _)| name.clone())
+        .collect();
+    names.sort();
+    for name in names {
+        resolve_derived_type(spec, &name, &mut Vec::new())?;
+    }
+    Ok(())
+}
+
+fn resolve_derived_type(spec: &mut RawSpec, name: &str, resolving: &mut Vec<String>) -> Result<()> {
+    let (derive, file) = match spec.ty.as_ref().and_then(|tys| tys.get(name)) {
+        Some(RawSchema { derive: Some(derive), def_loc, .. }) => (derive.clone(), def_loc.file.display().to_string()),
+        // not derived, or resolved already
+        _ => return Ok(()),
+    };
+    if resolving.iter().any(|resolving_name| resolving_name == name) {
+        bail!("struct '{}' is derived from itself through {} -> {}", name, resolving.join(" -> "), name)
+    }
+    // the struct, its parents and the structs it spreads have to be resolved first
+    resolving.push(name.to_string());
+    let mut pending = vec![derive.from.clone()];
+    let mut visited = HashSet::new();
+    while let Some(dependency) = pending.pop() {
+        if !visited.insert(dependency.clone()) {
+            continue
+        }
+        resolve_derived_type(spec, &dependency, resolving)?;
+        if let Some(schema) = spec.ty.as_ref().and_then(|tys| tys.get(&dependency)) {
+            pending.extend(schema.extends.iter().flat_map(|extends| extends.values()).cloned());
+            pending.extend(schema.flat_extends.iter().flatten().cloned());
+        }
+    }
+    resolving.pop();
+
+    let tys = spec.ty.as_ref().unwrap();
+    let mut fields = get_struct_fields(tys, &derive.from, &mut Vec::new())
+        .map_err(|err| anyhow::anyhow!("{}, which struct '{}' in '{}' is derived from", err, name, file))?;
+    let given = derive.fields.unwrap_or_default();
+    for field in &given {
+        if !fields.contains_key(field) {
+            bail!("struct '{}' in '{}' takes field '{}', which struct '{}' does not have", name, file, field, derive.from)
+        }
+    }
+    match derive.op {
+        RawSchemaDeriveOp::Partial => {
+            // an absent field is left as is rather than set to its default value
+            for field in fields.values_mut() {
+                field.required = Some(false);
+                field.default = None;
+            }
+        },
+        RawSchemaDeriveOp::Pick => fields.retain(|field, _| given.contains(field)),
+        RawSchemaDeriveOp::Omit => fields.retain(|field, _| !given.contains(field)),
+    }
+
+    let schema = spec.ty.as_mut().unwrap().get_mut(name).unwrap();
+    schema.properties = Some(fields);
+    schema.derive = None;
+    Ok(())
+}
+
+/// Get every field of a struct, along with the fields of its parents and of the structs it spreads
+fn get_struct_fields(tys: &HashMap<String, RawSchema>, name: &str, visiting: &mut Vec<String>) -> Result<HashMap<String, RawSchema>> {
+    let schema = match tys.get(name) {
+        Some(schema) => schema,
+        None => bail!("struct '{}' is not defined", name),
+    };
+    let properties = match &schema.properties {
+        Some(properties) => properties,
+        None => bail!("'{}' is not a struct", name),
+    };
+    if visiting.iter().any(|visiting_name| visiting_name == name) {
+        bail!("struct '{}' inherits from itself through {} -> {}", name, visiting.join(" -> "), name)
+    }
+
+    visiting.push(name.to_string());
+    let mut fields = HashMap::new();
+    let mut parents: Vec<&String> = schema.extends.iter().flat_map(|extends| extends.values()).collect();
+    parents.sort();
+    for parent in parents.into_iter().chain(schema.flat_extends.iter().flatten()) {
+        fields.extend(get_struct_fields(tys, parent, visiting)?);
+    }
+    visiting.pop();
+
+    fields.extend(properties.iter().map(|(field, schema)| (field.clone(), schema.clone())));
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    #[test]
+    fn can_resolve_derived_types() -> Result<()> {
+        let spec = crate::from_api_str(r#"
+        struct Base {
+            id: string
+        }
+        struct Todo : Base {
+            title: string
+            done: bool = false
+            note?: string
+        }
+        struct UpdateTodo = partial Todo
+        struct TodoSummary = pick Todo (id, title)
+        struct NewTodo = omit Todo (id)
+        struct TodoTitle = pick TodoSummary (title)
+
+        namespace billing {
+            struct Invoice {
+                total: int
+            }
+            struct InvoicePatch = partial Invoice
+        }
+        "#)?;
+        let tys = spec.ty.as_ref().unwrap();
+        let fields = |name: &str| {
+            let mut fields: Vec<String> = tys.get(name).unwrap().properties.as_ref().unwrap().keys().cloned().collect();
+            fields.sort();
+            fields
+        };
+
+        assert_eq!(fields("UpdateTodo"), vec!["done", "id", "note", "title"]);
+        for field in tys.get("UpdateTodo").unwrap().properties.as_ref().unwrap().values() {
+            assert_eq!(field.required, Some(false));
+            assert!(field.default.is_none());
+        }
+        assert!(tys.get("UpdateTodo").unwrap().derive.is_none());
+        assert_eq!(fields("TodoSummary"), vec!["id", "title"]);
+        assert_eq!(tys.get("TodoSummary").unwrap().properties.as_ref().unwrap().get("title").unwrap().required, Some(true));
+        assert_eq!(fields("NewTodo"), vec!["done", "note", "title"]);
+        assert_eq!(fields("TodoTitle"), vec!["title"]);
+        assert_eq!(fields("billing.InvoicePatch"), vec!["total"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn cannot_resolve_invalid_derived_types() -> Result<()> {
+        for (api, message) in [
+            ("struct Todo { id: string }\nstruct X = pick Todo (title)", "takes field 'title', which struct 'Todo' does not have"),
+            ("struct X = partial Todo", "struct 'Todo' is not defined, which struct 'X' in '' is derived from"),
+            ("enum Status { Open }\nstruct X = partial Status", "'Status' is not a struct"),
+            ("struct X = partial Y\nstruct Y = partial X", "is derived from itself through"),
+            ("struct X = omit Y", "expect the fields to pick or omit of 'Y' at 1:10"),
+            ("struct X = partial Y (id)", "no field can be given at 1:10"),
+        ] {
+            let err = crate::from_api_str(api).unwrap_err();
+            assert!(err.to_string().contains(message), "{}", err);
+        }
+        Ok(())
+    }
+}
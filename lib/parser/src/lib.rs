@@ -1,14 +1,29 @@
 
-use std::{path::{Path, PathBuf}, error::Error, collections::{VecDeque, HashSet}, fs, fmt::format};
+use std::{path::{Path, PathBuf}, error::Error, collections::{VecDeque, HashSet, HashMap}, fs, fmt::format};
 use anyhow::{bail, Result};
-use cronus_spec::RawSpec;
+use cronus_spec::{RawImport, RawImportName, RawSchema, RawSpec};
 
+pub mod api_migrate;
 pub mod api_parse;
+pub mod api_print;
 pub mod api_parser;
+pub mod defaults;
+pub mod derived;
+pub mod package;
 
 pub fn from_yaml(file: &Path) -> Result<RawSpec> {
     let contents = fs::read_to_string(file)?;
-    from_yaml_str(&contents)
+    finish_spec(serde_yaml::from_str(&contents)?, false)
+}
+
+pub fn from_json(file: &Path) -> Result<RawSpec> {
+    let contents = fs::read_to_string(file)?;
+    finish_spec(serde_json::from_str(&contents)?, false)
+}
+
+pub fn from_toml(file: &Path) -> Result<RawSpec> {
+    let contents = fs::read_to_string(file)?;
+    finish_spec(toml::from_str(&contents)?, false)
 }
 
 pub fn from_api(file: &Path) -> Result<RawSpec> {
@@ -18,78 +33,721 @@ pub fn from_api(file: &Path) -> Result<RawSpec> {
 
 #[tracing::instrument]
 pub fn from_file(file: &Path, resolve_import: bool, search_paths: Option<&Vec<PathBuf>>, explored: &mut HashSet<PathBuf>) -> Result<RawSpec> {
-    match file.extension() {
-        Some(ext) => {
-            match ext.to_str() {
-                Some(ext_str) => {
-                    let mut spec: RawSpec;
-                    if ext_str.ends_with("yaml") || ext_str.ends_with("yml") {
-                        spec = from_yaml(file)?;
-                    } 
-                    else if ext_str.ends_with("api")  {
-                        spec = from_api(file)?;
-                    } else {
-                        bail!("unsupported file extension '{:?}', expect .yaml, .yml or .api", ext)
-                    }
-                    
-                    if resolve_import {
-                        resolve_imports(&mut spec, explored, file.parent().unwrap(), search_paths)?;
-                    }
-                    
-                    Ok(spec)
-                },
-                None => bail!("unsupported file extension '{:?}', expect .yaml, .yml or .api", ext),
-            }
-            
-        },
-        None => bail!("no file extension, expect .yaml, .yml or .api"),
+    let mut spec = from_file_importing(file, resolve_import, search_paths, explored, &[])?;
+    // a struct may be derived from an imported one, the files of the spec are resolved together
+    if resolve_import {
+        derived::resolve_derived_types(&mut spec)?;
     }
-    
+    Ok(spec)
+}
+
+/// Parse the file imported by the given files, which are checked for import cycles
+fn from_file_importing(file: &Path, resolve_import: bool, search_paths: Option<&Vec<PathBuf>>, explored: &mut HashSet<PathBuf>, importing: &[PathBuf]) -> Result<RawSpec> {
+    let mut spec = match file.extension().and_then(|ext| ext.to_str()) {
+        Some("yaml") | Some("yml") => from_yaml(file)?,
+        Some("json") => from_json(file)?,
+        Some("toml") => from_toml(file)?,
+        Some("api") => from_api(file)?,
+        Some(ext) => bail!("unsupported file extension '{}', expect .yaml, .yml, .json, .toml or .api", ext),
+        None => bail!("no file extension, expect .yaml, .yml, .json, .toml or .api"),
+    };
+
+    if resolve_import {
+        let importing = [importing, &[file.to_path_buf()]].concat();
+        resolve_imports_importing(&mut spec, explored, file.parent().unwrap(), search_paths, &importing)?;
+        // the types of a namespace may be declared by the imported files
+        resolve_namespaces(&mut spec)?;
+    }
+
+    Ok(spec)
 }
 
 pub fn from_yaml_str(str: &str) -> Result<RawSpec> {
-    let spec: RawSpec = serde_yaml::from_str(&str)?;
+    finish_spec(serde_yaml::from_str(str)?, true)
+}
+
+pub fn from_json_str(str: &str) -> Result<RawSpec> {
+    finish_spec(serde_json::from_str(str)?, true)
+}
+
+pub fn from_toml_str(str: &str) -> Result<RawSpec> {
+    finish_spec(toml::from_str(str)?, true)
+}
+
+pub fn from_api_str(str: &str) -> Result<RawSpec> {
+    let mut spec = api_parse::parse(PathBuf::new(), str)?;
+    derived::resolve_derived_types(&mut spec)?;
+    Ok(spec)
+}
+
+/// Finish a spec loaded from yaml, json or toml: expand its type expressions, resolve its namespaces and check its default values
+///
+/// The derived types of a file are resolved once its imports are merged, so only a standalone spec resolves them here.
+fn finish_spec(mut spec: RawSpec, resolve_derived: bool) -> Result<RawSpec> {
+    expand_type_exprs(&mut spec)?;
+    resolve_namespaces(&mut spec)?;
+    defaults::check_default_values(&spec)?;
+    if resolve_derived {
+        derived::resolve_derived_types(&mut spec)?;
+    }
     Ok(spec)
 }
 
+/// Expand the type expressions of a yaml, json or toml spec into the nested schemas the api files are parsed into,
+/// e.g. type: map<string,Todo[]> becomes keys: string and values: { items: { type: Todo } }
+fn expand_type_exprs(spec: &mut RawSpec) -> Result<()> {
+    for schema in spec.ty.iter_mut().flat_map(|tys| tys.values_mut()) {
+        expand_schema_type_exprs(schema)?;
+    }
+    for usecase in spec.usecases.iter_mut().flat_map(|usecases| usecases.values_mut()) {
+        for method in usecase.methods.values_mut() {
+            for schema in method.req.iter_mut().chain(method.res.iter_mut()) {
+                expand_schema_type_exprs(schema)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn expand_schema_type_exprs(schema: &mut RawSchema) -> Result<()> {
+    if let Some(ty) = schema.ty.as_ref().filter(|ty| ty.contains(['<', '>', '[', ']', ','])) {
+        let expanded = api_parse::parse_type_expr_str(schema.def_loc.clone(), ty)?;
+        schema.ty = expanded.ty;
+        schema.items = expanded.items;
+        schema.keys = expanded.keys;
+        schema.values = expanded.values;
+    }
+    for nested in schema.items.iter_mut().chain(schema.values.iter_mut()) {
+        expand_schema_type_exprs(nested)?;
+    }
+    for prop in schema.properties.iter_mut().flat_map(|props| props.values_mut()) {
+        expand_schema_type_exprs(prop)?;
+    }
+    Ok(())
+}
+
 pub fn to_yaml_str(spec: &RawSpec) -> Result<String> {
     let yaml = serde_yaml::to_string(spec)?;
     Ok(yaml)
 }
 
+pub fn to_json_str(spec: &RawSpec) -> Result<String> {
+    let json = serde_json::to_string_pretty(spec)?;
+    Ok(json)
+}
+
+pub fn to_api_str(spec: &RawSpec) -> Result<String> {
+    api_print::print(spec)
+}
+
 pub fn resolve_imports(spec: &mut RawSpec, explored: &mut HashSet<PathBuf>, spec_parent:&Path, search_paths: Option<&Vec<PathBuf>>) -> Result<()> {
+    resolve_imports_importing(spec, explored, spec_parent, search_paths, &[])
+}
+
+fn resolve_imports_importing(spec: &mut RawSpec, explored: &mut HashSet<PathBuf>, spec_parent:&Path, search_paths: Option<&Vec<PathBuf>>, importing: &[PathBuf]) -> Result<()> {
 
     for import in spec.imports.clone().into_iter().flatten() {
-       
-        let import_path = get_import_path(&import, spec_parent,search_paths)?;
-        if explored.contains(&import_path) {
+        for import_path in get_import_paths(&import, spec_parent, search_paths)? {
+            if explored.contains(&import_path) {
+                continue
+            }
+            explored.insert(import_path.clone());
+            let imported_spec = from_file_importing(&import_path, true, search_paths, explored, importing)?;
+
+            spec.merge(imported_spec)?
+        }
+    }
+
+    for import in spec.scoped_imports.clone().into_iter().flatten() {
+        if import.names.is_some() && is_glob(&import.path) {
+            bail!("names can't be imported from the files of '{}'", import.path)
+        }
+        for import_path in get_import_paths(&import.path, spec_parent, search_paths)? {
+            if importing.contains(&import_path) {
+                bail!("'{}' imports itself through {}", import.path, importing.iter().map(|file| file.display().to_string()).collect::<Vec<String>>().join(" -> "))
+            }
+            // every scoped import has a whole copy of the file, as each one may import other definitions of it
+            let imported_spec = from_file_importing(&import_path, true, search_paths, &mut HashSet::new(), importing)?;
+            spec.merge(scope_import(imported_spec, &import)?)?
+        }
+    }
+
+    Ok(())
+}
+
+/// Keep the definitions of an imported file which the scoped import imports
+/// 
+/// An import of some definitions keeps them, renamed if they are imported as another name, and the types they refer to.
+/// An import of a file as a namespace keeps every definition, with the types of the top level put into the namespace.
+fn scope_import(mut imported: RawSpec, import: &RawImport) -> Result<RawSpec> {
+    let location = match import.line_col {
+        Some((line, col)) => format!("{}:{}:{}", import.def_loc.file.display(), line, col),
+        None => import.def_loc.file.display().to_string(),
+    };
+
+    let renames: HashMap<String, String> = match (&import.names, &import.alias) {
+        (Some(_), Some(_)) => bail!("import of '{}' can't have both names and a namespace at {}", import.path, location),
+        (Some(names), None) => {
+            for name in names {
+                let is_defined = imported.ty.as_ref().is_some_and(|tys| tys.contains_key(&name.name))
+                    || imported.usecases.as_ref().is_some_and(|usecases| usecases.contains_key(&name.name));
+                if !is_defined {
+                    bail!("'{}' is imported but not defined in '{}' at {}", name.name, import.path, location)
+                }
+            }
+            // the types pulled in by the imported names stay private, qualified by the file they come from
+            let namespace = get_file_namespace(&import.path);
+            let imported_names: HashSet<&String> = names.iter().map(|name| &name.name).collect();
+            let dependencies = collect_dependencies(&imported, names);
+            let private_names = dependencies.into_iter()
+                .filter(|name| !imported_names.contains(name))
+                .filter(|name| imported.ty.as_ref().and_then(|tys| tys.get(name)).is_some_and(|schema| schema.namespace.is_none()))
+                .map(|name| {
+                    let private_name = format!("{}.{}", namespace, name);
+                    (name, private_name)
+                });
+            names.iter()
+                .filter_map(|name| name.alias.as_ref().map(|alias| (name.name.clone(), alias.clone())))
+                .chain(private_names)
+                .collect()
+        },
+        (None, Some(namespace)) => imported.ty.iter()
+            .flatten()
+            .filter(|(_, schema)| schema.namespace.is_none())
+            .map(|(name, _)| (name.clone(), format!("{}.{}", namespace, name)))
+            .collect(),
+        (None, None) => HashMap::new(),
+    };
+    rename_definitions(&mut imported, &renames);
+
+    let names = match (&import.names, &import.alias) {
+        (Some(names), _) => names,
+        (None, Some(namespace)) => {
+            for usecase in imported.usecases.iter_mut().flat_map(|usecases| usecases.values_mut()) {
+                usecase.namespace.get_or_insert_with(|| namespace.clone());
+            }
+            resolve_namespaces(&mut imported)?;
+            return Ok(imported)
+        },
+        (None, None) => return Ok(imported),
+    };
+
+    let mut scoped = RawSpec::new();
+    let mut pending: Vec<String> = Vec::new();
+    let mut error_names: HashSet<String> = HashSet::new();
+    for name in names {
+        let name = name.alias.as_ref().unwrap_or(&name.name);
+        if let Some(usecase) = imported.usecases.as_mut().and_then(|usecases| usecases.remove(name)) {
+            for method in usecase.methods.values() {
+                for schema in method.req.iter().chain(method.res.iter()) {
+                    collect_schema_ty_names(schema, &mut pending);
+                }
+                error_names.extend(method.errors.iter().flatten().cloned());
+            }
+            scoped.usecases.get_or_insert_with(HashMap::new).insert(name.clone(), usecase);
+        } else {
+            pending.push(name.clone());
+        }
+    }
+
+    // the imported types along with the types they refer to
+    let mut tys = imported.ty.take().unwrap_or_default();
+    while let Some(name) = pending.pop() {
+        if let Some(schema) = tys.remove(&name) {
+            collect_schema_ty_names(&schema, &mut pending);
+            scoped.ty.get_or_insert_with(HashMap::new).insert(name, schema);
+        }
+    }
+
+    if let Some(errors) = imported.errors.take() {
+        let errors: HashMap<_, _> = errors.into_iter().filter(|(name, _)| error_names.contains(name)).collect();
+        if !errors.is_empty() {
+            scoped.errors = Some(errors);
+        }
+    }
+
+    Ok(scoped)
+}
+
+/// The namespace of the private types of an imported file, e.g. todo for "types/todo.api"
+fn get_file_namespace(path: &str) -> String {
+    let stem = Path::new(path).file_stem().and_then(|stem| stem.to_str()).unwrap_or(path);
+    let namespace: String = stem.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if namespace.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", namespace)
+    } else {
+        namespace
+    }
+}
+
+/// The names of the types the imported types and usecases refer to, directly or through other types
+fn collect_dependencies(spec: &RawSpec, names: &[RawImportName]) -> HashSet<String> {
+    let mut pending: Vec<String> = Vec::new();
+    for name in names {
+        match spec.usecases.as_ref().and_then(|usecases| usecases.get(&name.name)) {
+            Some(usecase) => {
+                for method in usecase.methods.values() {
+                    for schema in method.req.iter().chain(method.res.iter()) {
+                        collect_schema_ty_names(schema, &mut pending);
+                    }
+                }
+            },
+            None => pending.push(name.name.clone()),
+        }
+    }
+
+    let mut dependencies = HashSet::new();
+    while let Some(name) = pending.pop() {
+        if dependencies.contains(&name) {
             continue
         }
-        explored.insert(import_path.clone());
-        let imported_spec = from_file(&import_path, true, search_paths, explored)?;
+        if let Some(schema) = spec.ty.as_ref().and_then(|tys| tys.get(&name)) {
+            collect_schema_ty_names(schema, &mut pending);
+        }
+        dependencies.insert(name);
+    }
+    dependencies
+}
+
+/// Rename the types and usecases of a spec, along with every reference to the types
+fn rename_definitions(spec: &mut RawSpec, renames: &HashMap<String, String>) {
+    if renames.is_empty() {
+        return
+    }
+    let rename = |name: &str| renames.get(name).cloned().unwrap_or_else(|| name.to_string());
+
+    if let Some(tys) = spec.ty.take() {
+        spec.ty = Some(tys.into_iter().map(|(name, mut schema)| {
+            map_schema_ty_names(&mut schema, &mut |ty| rename(ty));
+            if let Some((namespace, _)) = renames.get(&name).and_then(|renamed| renamed.rsplit_once('.')) {
+                schema.namespace.get_or_insert_with(|| namespace.to_string());
+            }
+            (rename(&name), schema)
+        }).collect());
+    }
 
-        spec.merge(imported_spec)?
+    if let Some(usecases) = spec.usecases.take() {
+        spec.usecases = Some(usecases.into_iter().map(|(name, mut usecase)| {
+            for method in usecase.methods.values_mut() {
+                for schema in method.req.iter_mut().chain(method.res.iter_mut()) {
+                    map_schema_ty_names(schema, &mut |ty| rename(ty));
+                }
+            }
+            (rename(&name), usecase)
+        }).collect());
+    }
+}
+
+/// Collect the names of the types a schema refers to
+fn collect_schema_ty_names(schema: &RawSchema, names: &mut Vec<String>) {
+    names.extend(schema.ty.iter().cloned());
+    if let Some(items) = schema.items.as_ref() {
+        collect_schema_ty_names(items, names);
+    }
+    names.extend(schema.keys.iter().cloned());
+    if let Some(values) = schema.values.as_ref() {
+        collect_schema_ty_names(values, names);
+    }
+    for prop in schema.properties.iter().flat_map(|props| props.values()) {
+        collect_schema_ty_names(prop, names);
+    }
+    names.extend(schema.extends.iter().flat_map(|parents| parents.values()).cloned());
+    names.extend(schema.flat_extends.iter().flatten().cloned());
+    names.extend(schema.derive.iter().map(|derive| derive.from.clone()));
+}
+
+/// Qualify the types referred to in a namespace, e.g. Invoice in namespace billing is billing.Invoice
+/// if billing.Invoice is declared, otherwise it is the type of the top level
+/// 
+/// A type with a namespace but an unqualified key (from a yaml spec) is keyed by its qualified name
+pub fn resolve_namespaces(spec: &mut RawSpec) -> Result<()> {
+    if let Some(tys) = spec.ty.take() {
+        let mut qualified_tys = HashMap::new();
+        for (name, mut schema) in tys {
+            let name = match (name.rsplit_once('.'), schema.namespace.clone()) {
+                (Some((ns, _)), None) => {
+                    schema.namespace = Some(ns.to_string());
+                    name
+                },
+                (Some((ns, _)), Some(namespace)) if ns != namespace => {
+                    bail!("type '{}' can't be declared in namespace '{}'", name, namespace)
+                },
+                (None, Some(namespace)) => format!("{}.{}", namespace, name),
+                _ => name,
+            };
+            if qualified_tys.contains_key(&name) {
+                bail!("Conflict for key '{}' in 'ty' hashmap", name)
+            }
+            qualified_tys.insert(name, schema);
+        }
+        spec.ty = Some(qualified_tys);
+    }
+
+    let declared: HashSet<String> = spec.ty.iter().flatten().map(|(name, _)| name.clone()).collect();
+
+    for schema in spec.ty.iter_mut().flat_map(|tys| tys.values_mut()) {
+        if let Some(namespace) = schema.namespace.clone() {
+            qualify_schema(schema, &namespace, &declared);
+        }
+    }
+
+    for usecase in spec.usecases.iter_mut().flat_map(|usecases| usecases.values_mut()) {
+        if let Some(namespace) = usecase.namespace.clone() {
+            for method in usecase.methods.values_mut() {
+                for schema in method.req.iter_mut().chain(method.res.iter_mut()) {
+                    qualify_schema(schema, &namespace, &declared);
+                }
+            }
+        }
     }
 
     Ok(())
 }
 
+fn qualify_schema(schema: &mut RawSchema, namespace: &str, declared: &HashSet<String>) {
+    map_schema_ty_names(schema, &mut |name| {
+        let qualified = format!("{}.{}", namespace, name);
+        if !name.contains('.') && declared.contains(&qualified) { qualified } else { name.to_string() }
+    });
+}
+
+/// Map every type name a schema refers to, in its items, map keys and values, parents, spreads and the struct it is derived from
+fn map_schema_ty_names(schema: &mut RawSchema, f: &mut impl FnMut(&str) -> String) {
+    if let Some(ty) = schema.ty.as_mut() {
+        *ty = f(ty);
+    }
+    if let Some(items) = schema.items.as_mut() {
+        map_schema_ty_names(items, f);
+    }
+    if let Some(keys) = schema.keys.as_mut() {
+        *keys = f(keys);
+    }
+    if let Some(values) = schema.values.as_mut() {
+        map_schema_ty_names(values, f);
+    }
+    for prop in schema.properties.iter_mut().flat_map(|props| props.values_mut()) {
+        map_schema_ty_names(prop, f);
+    }
+    for parent in schema.extends.iter_mut().flat_map(|parents| parents.values_mut()) {
+        *parent = f(parent);
+    }
+    for spread in schema.flat_extends.iter_mut().flatten() {
+        *spread = f(spread);
+    }
+    if let Some(derive) = schema.derive.as_mut() {
+        derive.from = f(&derive.from);
+    }
+}
+
+/// Get the files of an import, every file matching its name for a glob import (types/*.api) in the order of their paths
+///
+/// A package import (@acme/common/money.api) is looked up in the package directories of the importing file
+/// before the search paths, and the version of the package is checked against its lockfile.
 #[tracing::instrument]
-fn get_import_path(import: &str, default_path:&Path, available_paths: Option<&Vec<PathBuf>>) -> Result<PathBuf> {
+fn get_import_paths(import: &str, default_path:&Path, available_paths: Option<&Vec<PathBuf>>) -> Result<Vec<PathBuf>> {
     let cleaned = import.replace("\r", "");
-    let defualt_relative = std::path::absolute(default_path.join(&cleaned))?;
-    if defualt_relative.exists() {
-        return Ok(defualt_relative)
+    let package_name = if package::is_package_import(&cleaned) {
+        match package::get_package_name(&cleaned) {
+            Some(name) => Some(name),
+            None => bail!("invalid package import '{}', expected @scope/package/file", cleaned),
+        }
+    } else {
+        None
+    };
+
+    let mut roots = match package_name {
+        Some(_) => package::get_package_roots(&std::path::absolute(default_path)?),
+        None => vec![default_path.to_path_buf()],
+    };
+    roots.extend(available_paths.into_iter().flatten().cloned());
+
+    for root in roots {
+        let candidates = match_import_path(&std::path::absolute(root.join(&cleaned))?)?;
+        if candidates.is_empty() {
+            continue
+        }
+        if let Some(name) = &package_name {
+            package::lock_package(&std::path::absolute(&root)?, name)?;
+        }
+        return Ok(candidates)
     }
 
-    if let Some(paths) = available_paths {
-        for p in paths {
-            let candidate = std::path::absolute(p.join(&cleaned))?;
-            if candidate.exists() {
-                return Ok(candidate)
+    bail!("no available file found for import '{}'", cleaned)
+}
+
+fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?'])
+}
+
+/// Get the files of a path whose file name may have the wildcards * and ?, sorted by their paths
+fn match_import_path(path: &Path) -> Result<Vec<PathBuf>> {
+    let pattern = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    if !is_glob(&pattern) {
+        return Ok(if path.exists() { vec![path.to_path_buf()] } else { vec![] })
+    }
+
+    let dir = match path.parent() {
+        Some(dir) if dir.is_dir() => dir,
+        _ => return Ok(vec![]),
+    };
+    let mut paths = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() && match_wildcard(&pattern, &entry.file_name().to_string_lossy()) {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Whether a name matches a pattern, where * matches any characters and ? matches one character
+fn match_wildcard(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // the position of the last * in the pattern and of the name it was matched from
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, fs, ops::Deref, path::{Path, PathBuf}};
+
+    use anyhow::Result;
+
+    /// A temporary directory, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl Deref for TempDir {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Write the files of a spec into a new temporary directory
+    fn write_spec_files(name: &str, files: &[(&str, &str)]) -> Result<TempDir> {
+        let dir = TempDir(std::path::absolute(std::env::temp_dir().join(format!("cronus_{}_{}", name, std::process::id())))?);
+        fs::create_dir_all(&*dir)?;
+        for (file, content) in files {
+            if let Some(parent) = dir.join(file).parent() {
+                fs::create_dir_all(parent)?;
             }
+            fs::write(dir.join(file), content)?;
         }
+        Ok(dir)
     }
 
-    bail!("no available file found for import '{}'", cleaned)
-}
\ No newline at end of file
+    #[test]
+    fn can_resolve_scoped_imports() -> Result<()> {
+        let dir = write_spec_files("scoped_imports", &[
+            ("todo.api", r#"
+            enum Status { Open Done }
+            struct Todo {
+                status: Status
+                owner: User
+            }
+            struct User {
+                name: string
+            }
+            struct Private {
+                id: string
+            }
+            "#),
+            ("common.api", r#"
+            struct Money {
+                amount: int
+            }
+            struct Price {
+                money: Money
+            }
+            "#),
+            ("main.api", r#"
+            import { Todo, Status as TodoStatus } from "todo.api"
+            import "common.api" as common
+            struct Status {
+                price: common.Price
+            }
+            "#),
+        ])?;
+
+        let spec = super::from_file(&dir.join("main.api"), true, None, &mut HashSet::new())?;
+        let tys = spec.ty.as_ref().unwrap();
+        let mut names: Vec<&String> = tys.keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["Status", "Todo", "TodoStatus", "common.Money", "common.Price", "todo.User"]);
+
+        let todo = tys.get("Todo").unwrap().properties.as_ref().unwrap();
+        assert_eq!(todo.get("status").unwrap().ty.as_deref(), Some("TodoStatus"));
+        assert_eq!(todo.get("owner").unwrap().ty.as_deref(), Some("todo.User"));
+        assert_eq!(tys.get("todo.User").unwrap().namespace.as_deref(), Some("todo"));
+        let price = tys.get("common.Price").unwrap();
+        assert_eq!(price.namespace.as_deref(), Some("common"));
+        assert_eq!(price.properties.as_ref().unwrap().get("money").unwrap().ty.as_deref(), Some("common.Money"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn cannot_import_undefined_name() -> Result<()> {
+        let dir = write_spec_files("undefined_import", &[
+            ("todo.api", "struct Todo {\n  title: string\n}\n"),
+            ("main.api", "\nimport { Todo, Todos } from \"todo.api\"\n"),
+        ])?;
+
+        let err = super::from_file(&dir.join("main.api"), true, None, &mut HashSet::new()).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("'Todos' is imported but not defined in 'todo.api'"), "{}", message);
+        assert!(message.contains("main.api:2:1"), "{}", message);
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_resolve_glob_imports() -> Result<()> {
+        let dir = write_spec_files("glob_imports", &[
+            ("types/b.api", "struct B {\n  a: A\n}\n"),
+            ("types/a.api", "struct A {\n  id: string\n}\n"),
+            ("types/notes.txt", "not a spec"),
+            ("main.api", "import \"types/*.api\"\n"),
+        ])?;
+
+        let mut explored = HashSet::new();
+        let spec = super::from_file(&dir.join("main.api"), true, None, &mut explored)?;
+        let tys = spec.ty.as_ref().unwrap();
+        assert!(tys.contains_key("A") && tys.contains_key("B"));
+        assert_eq!(explored.len(), 2);
+
+        let paths = super::get_import_paths("types/*.api", &dir, None)?;
+        assert_eq!(paths, vec![dir.join("types/a.api"), dir.join("types/b.api")]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_derive_imported_struct() -> Result<()> {
+        let dir = write_spec_files("derive_imported", &[
+            ("todo.api", "struct Todo {\n  id: string\n  title: string\n}\n"),
+            ("main.api", "import { Todo } from \"todo.api\"\nstruct NewTodo = omit Todo (id)\n"),
+        ])?;
+
+        let spec = super::from_file(&dir.join("main.api"), true, None, &mut HashSet::new())?;
+        let new_todo = spec.ty.as_ref().unwrap().get("NewTodo").unwrap();
+        assert_eq!(new_todo.properties.as_ref().unwrap().keys().collect::<Vec<&String>>(), vec!["title"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_match_wildcard() {
+        assert!(super::match_wildcard("*.api", "money.api"));
+        assert!(super::match_wildcard("m?ney*", "money.api"));
+        assert!(super::match_wildcard("*o*.api", "todo.api"));
+        assert!(!super::match_wildcard("*.api", "money.yaml"));
+        assert!(!super::match_wildcard("?.api", "ab.api"));
+    }
+
+    #[test]
+    fn can_resolve_package_imports() -> Result<()> {
+        let dir = write_spec_files("package_imports", &[
+            ("cronus_modules/@acme/common/package.yaml", "version: 1.2.0\n"),
+            ("cronus_modules/@acme/common/money.api", "struct Money {\n  amount: int\n}\n"),
+            ("specs/main.api", "import \"@acme/common/money.api\"\nstruct Price {\n  money: Money\n}\n"),
+        ])?;
+        let lock_path = dir.join(crate::package::LOCK_FILE);
+        let _ = fs::remove_file(&lock_path);
+
+        let spec = super::from_file(&dir.join("specs/main.api"), true, None, &mut HashSet::new())?;
+        assert!(spec.ty.as_ref().unwrap().contains_key("Money"));
+        let lockfile: crate::package::Lockfile = serde_yaml::from_str(&fs::read_to_string(&lock_path)?)?;
+        assert_eq!(lockfile.packages.get("@acme/common").unwrap().version, "1.2.0");
+
+        fs::write(dir.join("cronus_modules/@acme/common/package.yaml"), "version: 1.3.0\n")?;
+        let err = super::from_file(&dir.join("specs/main.api"), true, None, &mut HashSet::new()).unwrap_err();
+        assert!(err.to_string().contains("locks version 1.2.0"), "{}", err);
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_import_json_and_toml() -> Result<()> {
+        let dir = write_spec_files("mixed_formats", &[
+            ("money.json", r#"{
+                "types": {
+                    "Money": {
+                        "properties": {
+                            "amount": { "type": "integer", "required": true }
+                        }
+                    }
+                }
+            }"#),
+            ("currency.toml", r#"
+                [types.Currency]
+                enum_items = [{ name = "Usd" }, { name = "Eur" }]
+            "#),
+            ("main.api", "import money.json\nimport currency.toml\nstruct Price {\n  money: Money\n  currency: Currency\n}\n"),
+        ])?;
+
+        let spec = super::from_file(&dir.join("main.api"), true, None, &mut HashSet::new())?;
+        let tys = spec.ty.as_ref().unwrap();
+        assert_eq!(tys.get("Money").unwrap().properties.as_ref().unwrap().get("amount").unwrap().ty.as_deref(), Some("integer"));
+        assert_eq!(tys.get("Currency").unwrap().enum_items.as_ref().unwrap().len(), 2);
+        assert!(tys.contains_key("Price"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn can_expand_yaml_map_types() -> Result<()> {
+        let spec = super::from_yaml_str(r#"
+types:
+  Todo:
+    properties:
+      by_user:
+        type: map<string, Todo[]>
+      counts:
+        keys: string
+        values:
+          type: int
+"#)?;
+        let props = spec.ty.as_ref().unwrap().get("Todo").unwrap().properties.as_ref().unwrap();
+        let by_user = props.get("by_user").unwrap();
+        assert!(by_user.ty.is_none());
+        assert_eq!(by_user.keys.as_deref(), Some("string"));
+        assert_eq!(by_user.values.as_ref().unwrap().items.as_ref().unwrap().ty.as_deref(), Some("Todo"));
+        assert_eq!(props.get("counts").unwrap().values.as_ref().unwrap().ty.as_deref(), Some("int"));
+
+        for ty in ["map<string", "map<string>", "string[", "map<string[],int>"] {
+            let yaml = format!("types:\n  Todo:\n    properties:\n      a:\n        type: \"{}\"\n", ty);
+            let err = super::from_yaml_str(&yaml).unwrap_err();
+            assert!(err.to_string().contains(ty) || err.to_string().contains("key of a map"), "{}: {}", ty, err);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use std::{collections::BTreeMap, fs, path::{Path, PathBuf}};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// The directory of the installed packages, looked up from the importing file to the root
+pub const PACKAGE_DIR: &str = "cronus_modules";
+
+/// The manifest of a package, in the directory of the package
+pub const PACKAGE_MANIFEST: &str = "package.yaml";
+
+/// The versions of the used packages, beside the directory of the packages
+pub const LOCK_FILE: &str = "cronus.lock";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackageManifest {
+    pub version: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Lockfile {
+    #[serde(default)]
+    pub packages: BTreeMap<String, LockedPackage>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct LockedPackage {
+    pub version: String,
+}
+
+/// Whether an import is a file of a package, e.g. @acme/common/money.api
+pub fn is_package_import(import: &str) -> bool {
+    import.starts_with('@')
+}
+
+/// Get the name of the package of an import, e.g. @acme/common of @acme/common/money.api
+pub fn get_package_name(import: &str) -> Option<String> {
+    let mut segments = import.split('/');
+    match (segments.next(), segments.next()) {
+        (Some(scope), Some(name)) if scope.len() > 1 && !name.is_empty() => Some(format!("{}/{}", scope, name)),
+        _ => None,
+    }
+}
+
+/// Get the package directories the importing file can use, the closest one first
+pub fn get_package_roots(importing_dir: &Path) -> Vec<PathBuf> {
+    importing_dir
+        .ancestors()
+        .map(|dir| dir.join(PACKAGE_DIR))
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+/// Check the version of a package against the lockfile beside the package directory,
+/// the version is recorded if the package is not locked yet
+pub fn lock_package(package_root: &Path, package_name: &str) -> Result<()> {
+    let manifest_path = package_root.join(package_name).join(PACKAGE_MANIFEST);
+    let manifest: PackageManifest = serde_yaml::from_str(
+        &fs::read_to_string(&manifest_path).with_context(|| format!("package '{}' has no {}", package_name, PACKAGE_MANIFEST))?
+    ).with_context(|| format!("invalid manifest {}", manifest_path.display()))?;
+
+    let lock_path = match package_root.parent() {
+        Some(dir) => dir.join(LOCK_FILE),
+        None => bail!("no directory for the {} of '{}'", LOCK_FILE, package_root.display()),
+    };
+    let mut lockfile: Lockfile = if lock_path.exists() {
+        serde_yaml::from_str(&fs::read_to_string(&lock_path)?)
+            .with_context(|| format!("invalid lockfile {}", lock_path.display()))?
+    } else {
+        Lockfile::default()
+    };
+
+    match lockfile.packages.get(package_name) {
+        Some(locked) if locked.version != manifest.version => {
+            bail!("package '{}' is at version {}, but {} locks version {}", package_name, manifest.version, lock_path.display(), locked.version)
+        },
+        Some(_) => Ok(()),
+        None => {
+            lockfile.packages.insert(package_name.to_string(), LockedPackage { version: manifest.version });
+            fs::write(&lock_path, serde_yaml::to_string(&lockfile)?)?;
+            Ok(())
+        },
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::{HashMap, VecDeque}, error::Error, fs, path::{Path, PathBuf}, sync::Arc};
+use std::{collections::{BTreeMap, HashMap, VecDeque}, error::Error, fs, path::{Path, PathBuf}, sync::Arc};
 use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
@@ -6,6 +6,45 @@ use serde::{Deserialize, Serialize};
 pub struct RawSchemaEnumItem {
     pub name: String,
     pub value: Option<i32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<RawDeprecated>,
+}
+
+/// Marks a type, a property, an enum item, a method or a usecase as deprecated,
+/// either [deprecated] or [deprecated = "use listTodosV2"] with a note of what to use instead
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum RawDeprecated {
+    Flag(bool),
+    Note(String),
+}
+
+impl RawDeprecated {
+    /// Whether it is deprecated, [deprecated = false] is not
+    pub fn is_deprecated(&self) -> bool {
+        !matches!(self, RawDeprecated::Flag(false))
+    }
+
+    /// The note of what to use instead
+    pub fn note(&self) -> Option<&str> {
+        match self {
+            RawDeprecated::Note(note) => Some(note),
+            RawDeprecated::Flag(_) => None,
+        }
+    }
+}
+
+/// The default value of a property, e.g. count: int = 10
+///
+/// An enum item is given by its name, e.g. status: Status = Active
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum RawSchemaDefaultValue {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
 }
 
 
@@ -27,8 +66,10 @@ pub struct GlobalOption {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub usecase_response_suffix: Option<String>,
 
-    /// If given type(s) is/are mentioned in the spec, do not generate
-    pub skip_types: Option<Vec<String>>
+    /// Case of the property names on the wire, which every generator follows, e.g. "camel".
+    /// Default: each generator keeps its own naming
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wire_case: Option<Case>,
 }
 
 
@@ -229,7 +270,11 @@ pub enum Case {
     #[serde(rename = "camel")]
     Camel,
     #[serde(rename = "snake")]
-    Snake
+    Snake,
+    #[serde(rename = "pascal")]
+    Pascal,
+    #[serde(rename = "kebab")]
+    Kebab
 }
 
 
@@ -281,12 +326,25 @@ pub struct RawSchema {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub items: Option<Box<RawSchema>>,
 
+    /// The type of the keys of a map (map<K,V>), default: string
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keys: Option<String>,
+
+    /// The schema of the values of a map (map<K,V>), which has no type itself as an array
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<Box<RawSchema>>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub properties: Option<HashMap<String, RawSchema>>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub required: Option<bool>,
 
+    /// The property can be null (name: string | null), whether it can be absent is given by required
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nullable: Option<bool>,
+
+    /// Namespace the type is declared in (namespace billing { ... }), its key is then qualified, e.g. billing.Invoice
     #[serde(skip_serializing_if = "Option::is_none")]
     pub namespace: Option<String>,
 
@@ -296,11 +354,117 @@ pub struct RawSchema {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub option: Option<RawSchemaPropertyOption>,
 
+    /// Parent types to inherit from (struct Admin : User), field name => type name
+    /// 
+    /// The field name is used by the generators which embed the parent rather than inherit from it
     #[serde(skip_serializing_if = "Option::is_none")]
     pub extends: Option<HashMap<String, String>>,
 
+    /// Types whose fields are copied into this one (struct X { ...Base })
     #[serde(skip_serializing_if = "Option::is_none")]
     pub flat_extends: Option<Vec<String>>,
+
+    /// Value used when the property is absent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<RawSchemaDefaultValue>,
+
+    /// Set for a declared type (type Timestamp), which is not generated but mapped to a type of each language
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<RawSchemaAlias>,
+
+    /// Set for a struct derived from another one (struct UpdateTodo = partial Todo), replaced by its properties when the spec is loaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub derive: Option<RawSchemaDerive>,
+}
+
+/// The fields a derived struct takes from another struct, e.g. pick Todo (id, title)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RawSchemaDerive {
+    pub op: RawSchemaDeriveOp,
+
+    /// The struct the fields are taken from
+    pub from: String,
+
+    /// The picked or omitted fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RawSchemaDeriveOp {
+    /// Every field, made optional
+    Partial,
+    /// The given fields only
+    Pick,
+    /// Every field but the given ones
+    Omit,
+}
+
+/// The per-language types of a declared type, e.g. [@golang = "time.Time"] type Timestamp
+///
+/// A language without a mapping uses the name of the declared type as is.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct RawSchemaAlias {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rust: Option<RawSchemaAliasTarget>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub python: Option<RawSchemaAliasTarget>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub golang: Option<RawSchemaAliasTarget>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub typescript: Option<RawSchemaAliasTarget>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub openapi: Option<RawSchemaAliasOpenApiTarget>,
+}
+
+/// The type a declared type maps to in a language
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum RawSchemaAliasTarget {
+    /// The type only, e.g. [@rust = "chrono::DateTime<chrono::Utc>"]
+    Type(String),
+
+    /// The type and what has to be imported for it, e.g. [@golang.type = "time.Time"] [@golang.import = "time"]
+    Import {
+        #[serde(rename = "type")]
+        ty: String,
+        import: String,
+    },
+}
+
+impl RawSchemaAliasTarget {
+    pub fn ty(&self) -> &str {
+        match self {
+            RawSchemaAliasTarget::Type(ty) => ty,
+            RawSchemaAliasTarget::Import { ty, .. } => ty,
+        }
+    }
+
+    pub fn import(&self) -> Option<&str> {
+        match self {
+            RawSchemaAliasTarget::Type(_) => None,
+            RawSchemaAliasTarget::Import { import, .. } => Some(import),
+        }
+    }
+}
+
+/// The OpenAPI schema of a declared type, e.g. [@openapi.format = "date-time"]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RawSchemaAliasOpenApiTarget {
+    /// Default: "string"
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub ty: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
 }
 
 impl RawSchema {
@@ -309,13 +473,19 @@ impl RawSchema {
             def_loc,
             ty: Some(ty),
             items: None,
+            keys: None,
+            values: None,
             properties: None,
             required: None,
+            nullable: None,
             namespace: None,
             enum_items: None,
             option: None,
             extends: None,
             flat_extends: None,
+            default: None,
+            alias: None,
+            derive: None,
         }
     }
 
@@ -324,13 +494,28 @@ impl RawSchema {
             def_loc: def_loc.clone(),
             ty: None,
             items: Some(Box::new(RawSchema::new(def_loc, items_ty))),
+            keys: None,
+            values: None,
             properties: None,
             required: None,
+            nullable: None,
             namespace: None,
             enum_items: None,
             option: None,
             extends: None,
             flat_extends: None,
+            default: None,
+            alias: None,
+            derive: None,
+        }
+    }
+
+    pub fn new_map_ty(def_loc: Arc<DefLoc>, keys_ty: String, values: RawSchema) -> Self {
+        Self {
+            ty: None,
+            keys: Some(keys_ty),
+            values: Some(Box::new(values)),
+            ..RawSchema::new(def_loc, String::new())
         }
     }
 }
@@ -357,8 +542,62 @@ pub struct RawSchemaPropertyOption {
     pub golang_gin: Option<RawSchemaPropertyGolangGinOption>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub description: Option<String>
+    pub description: Option<String>,
 
+    /// Minimum value of a number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+
+    /// Maximum value of a number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+
+    /// Minimum length of a string or an array
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_len: Option<u64>,
+
+    /// Maximum length of a string or an array
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_len: Option<u64>,
+
+    /// Regular expression a string has to match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+
+    /// Well-known format a string has to follow
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<RawSchemaPropertyFormat>,
+
+    /// The property is assigned by the server, it is sent in responses but never read from requests, e.g. [readonly] id: string
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readonly: Option<bool>,
+
+    /// The property is only read from requests and never sent back, e.g. [writeonly] password: string
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub writeonly: Option<bool>,
+
+    /// Name of the property on the wire, over the wire_case of the spec, e.g. [json = "createdAt"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<RawDeprecated>,
+
+    /// User-defined annotations, e.g. [x.audit = "pii"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<RawExtensions>,
+}
+
+/// Free-form values of the user-defined annotations (x.*), which no generator uses but in-house tooling can read,
+/// annotation name => value
+pub type RawExtensions = BTreeMap<String, serde_yaml::Value>;
+
+/// Well-known format of a string property
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RawSchemaPropertyFormat {
+    Email,
+    Url,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -402,17 +641,64 @@ pub struct RawSchemaPropertyRustOption {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub attrs: Option<Vec<String>>
 }
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RawUsecaseMethod {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub req: Option<RawSchema>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub res: Option<RawSchema>,
 
+    /// The response is a stream of res, e.g. server-sent events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<bool>,
+
+    /// Names of the declared errors the method can fail with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<String>>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub option: Option<RawUsecaseMethodOption>
 }
 
+/// An error a method can fail with, e.g. [rest.status = 404] error NotFound
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RawError {
+    #[serde(skip)]
+    pub def_loc: Arc<DefLoc>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub option: Option<RawErrorOption>
+}
+
+impl RawError {
+    /// The http status code of the error, 400 if it is not given
+    pub fn status(&self) -> u16 {
+        self.option.as_ref()
+            .and_then(|option| option.rest.as_ref())
+            .and_then(|rest| rest.status)
+            .unwrap_or(400)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RawErrorOption {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rest: Option<RawErrorRestOption>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RawErrorRestOption {
+    /// Http status code of the response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>
+}
+
   
 #[derive(Debug, Serialize, Deserialize)]
 pub  struct RawUsecase {
@@ -421,6 +707,10 @@ pub  struct RawUsecase {
 
     pub methods: HashMap<String, RawUsecaseMethod>,
 
+    /// Namespace the type references of the methods are resolved in first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub option: Option<RawUsecaseOption>
 }
@@ -430,7 +720,14 @@ pub  struct RawUsecase {
 pub struct RawUsecaseOption {
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub rest: Option<RawUsecaseRestOption>
+    pub rest: Option<RawUsecaseRestOption>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<RawDeprecated>,
+
+    /// User-defined annotations, e.g. [x.owner = "billing-team"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<RawExtensions>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -442,7 +739,7 @@ pub struct RawUsecaseRestOption {
 }
 
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct RawUsecaseMethodOption {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -458,10 +755,17 @@ pub struct RawUsecaseMethodOption {
     pub golang_gin: Option<RawUsecaseMethodGolangGinOption>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub description: Option<String>
+    pub description: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<RawDeprecated>,
+
+    /// User-defined annotations, e.g. [x.rate_limit = 10]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<RawExtensions>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct RawUsecaseMethodRedisOption {
 
@@ -472,14 +776,14 @@ pub struct RawUsecaseMethodRedisOption {
     pub ack_queue_name: Option<String>
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RawUsecaseMethodGolangGinOption {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub extra_request_fields: Option<Vec<String>>
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RawUsecaseMethodPythonFastApiOption {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub extra_method_args: Option<Vec<String>>,
@@ -508,6 +812,9 @@ pub struct RawSpec {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub usecases: Option<HashMap<String, RawUsecase>>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<HashMap<String, RawError>>,
+
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub option: Option<GlobalOption>,
@@ -516,8 +823,49 @@ pub struct RawSpec {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub imports: Option<Vec<String>>,
 
+    /// Imports of some definitions of a file, e.g. import { Todo } from "todo.api",
+    /// or of a file as a namespace, e.g. import "common.api" as common
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scoped_imports: Option<Vec<RawImport>>,
+
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RawImport {
+    #[serde(skip)]
+    pub def_loc: Arc<DefLoc>,
+
+    /// Line and column of the import in its file
+    #[serde(skip)]
+    pub line_col: Option<(usize, usize)>,
+
+    pub path: String,
+
+    /// The imported types and usecases, the others stay private to the file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub names: Option<Vec<RawImportName>>,
+
+    /// The namespace every type of the file is imported into
+    #[serde(rename = "as", skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
+
+/// An imported type or usecase, with the name it is imported as, e.g. Status as TodoStatus
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RawImportName {
+    pub name: String,
+
+    #[serde(rename = "as", skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
+
+
+/// Whether two definitions of the same name are one, imported twice through different files
+fn is_same_def(a: &DefLoc, b: &DefLoc) -> bool {
+    !a.file.as_os_str().is_empty() && a.file == b.file
+}
 
 impl RawSpec {
 
@@ -526,7 +874,10 @@ impl RawSpec {
         if let Some(to_merge_ty) = to_merge.ty {
             let ty_map = self.ty.get_or_insert_with(HashMap::new);
             for (key, value) in to_merge_ty {
-                if ty_map.contains_key(&key) {
+                if let Some(existing) = ty_map.get(&key) {
+                    if is_same_def(&existing.def_loc, &value.def_loc) {
+                        continue;
+                    }
                     bail!("Conflict for key '{}' in 'ty' hashmap", key);
                 }
                 ty_map.insert(key, value);
@@ -537,15 +888,34 @@ impl RawSpec {
         if let Some(to_merge_usecase) = to_merge.usecases {
             let usecase_map = self.usecases.get_or_insert_with(HashMap::new);
             for (key, value) in to_merge_usecase {
-                if usecase_map.contains_key(&key) {
+                if let Some(existing) = usecase_map.get(&key) {
+                    if is_same_def(&existing.def_loc, &value.def_loc) {
+                        continue;
+                    }
                     bail!("Conflict for key '{}' in 'usecase' hashmap", key)
                 }
                 usecase_map.insert(key, value);
             }
         }
 
+        // Merge 'errors' HashMap
+        if let Some(to_merge_errors) = to_merge.errors {
+            let error_map = self.errors.get_or_insert_with(HashMap::new);
+            for (key, value) in to_merge_errors {
+                if let Some(existing) = error_map.get(&key) {
+                    if is_same_def(&existing.def_loc, &value.def_loc) {
+                        continue;
+                    }
+                    bail!("Conflict for key '{}' in 'errors' hashmap", key)
+                }
+                error_map.insert(key, value);
+            }
+        }
+
         // Global option has to be putted into the entry .api or .yaml file
 
+        // Scoped imports are resolved by the file they are in
+
         // Merge 'imports' Vec
         if let Some(to_merge_imports) = to_merge.imports {
             if let Some(imports) = &mut self.imports {
@@ -567,8 +937,10 @@ impl RawSpec {
          Self { 
             ty: Default::default(), 
             usecases: Default::default(), 
+            errors: Default::default(), 
             option: Default::default(), 
-            imports: Default::default()
+            imports: Default::default(),
+            scoped_imports: Default::default()
          }
     }
 }